@@ -0,0 +1,146 @@
+use serde::Deserialize;
+
+use crate::model::{AddonStatus, ClusterAddon};
+
+/// Makoon installs Helm apps with `microk8s.helm3`, the addon cannot be disabled.
+pub(crate) const REQUIRED_ADDONS: [&str; 1] = ["helm3"];
+pub(crate) const STATUS_CMD: &str = "sudo microk8s status --wait-ready --format yaml";
+
+#[derive(Deserialize)]
+struct MicroK8sStatus {
+    #[serde(default)]
+    addons: Vec<MicroK8sAddon>,
+}
+
+#[derive(Deserialize)]
+struct MicroK8sAddon {
+    name: String,
+    #[serde(default)]
+    repository: String,
+    #[serde(default)]
+    description: String,
+    status: String,
+}
+
+pub(crate) fn validate(addon: &ClusterAddon) -> Result<(), String> {
+    let valid_name = !addon.name.is_empty()
+        && addon
+            .name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid_name {
+        return Err(format!("Invalid addon name [{}]", addon.name));
+    }
+    if let Some(arguments) = addon.arguments.as_ref() {
+        let valid_arguments = !arguments.is_empty()
+            && arguments
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || ".-:,/=_@".contains(c));
+        if !valid_arguments {
+            return Err(format!(
+                "Invalid arguments [{}] of addon [{}]",
+                arguments, addon.name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Addons of the cluster with the required ones first.
+pub(crate) fn with_required(addons: &[ClusterAddon]) -> Vec<ClusterAddon> {
+    let mut result = REQUIRED_ADDONS
+        .iter()
+        .filter(|r| !addons.iter().any(|i| i.name == **r))
+        .map(|i| ClusterAddon {
+            name: i.to_string(),
+            arguments: None,
+        })
+        .collect::<Vec<ClusterAddon>>();
+    result.extend(addons.iter().cloned());
+    result
+}
+
+pub(crate) fn enable_command(addon: &ClusterAddon) -> String {
+    match addon.arguments.as_ref() {
+        Some(v) => format!("sudo microk8s enable {}:{}", addon.name, v),
+        None => format!("sudo microk8s enable {}", addon.name),
+    }
+}
+
+/// Some addons ask for confirmation when they are disabled.
+pub(crate) fn disable_command(name: &str) -> String {
+    format!("echo y | sudo microk8s disable {}", name)
+}
+
+/// Addons from `microk8s status --format yaml`.
+pub(crate) fn parse_status(output: &str) -> Result<Vec<AddonStatus>, String> {
+    let status: MicroK8sStatus = serde_yaml::from_str(output).map_err(|e| e.to_string())?;
+    Ok(status
+        .addons
+        .into_iter()
+        .map(|i| AddonStatus {
+            name: i.name,
+            repository: i.repository,
+            description: i.description,
+            enabled: i.status == "enabled",
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::addons::{enable_command, parse_status, validate, with_required};
+    use crate::model::ClusterAddon;
+
+    fn addon(name: &str, arguments: Option<&str>) -> ClusterAddon {
+        ClusterAddon {
+            name: name.to_string(),
+            arguments: arguments.map(|i| i.to_string()),
+        }
+    }
+
+    #[test]
+    fn status_is_parsed_from_yaml() {
+        let output = "microk8s:
+  running: true
+high-availability:
+  enabled: false
+  nodes:
+  - address: 10.0.0.11:19001
+    role: voter
+addons:
+- name: dns
+  repository: core
+  description: CoreDNS
+  version: 1.10.1
+  status: enabled
+- name: metallb
+  repository: core
+  description: Loadbalancer for your Kubernetes cluster
+  version: 0.13.3
+  status: disabled
+";
+        let result = parse_status(output).unwrap();
+        assert_eq!(2, result.len());
+        assert_eq!("dns", result[0].name);
+        assert!(result[0].enabled);
+        assert_eq!("core", result[1].repository);
+        assert!(!result[1].enabled);
+    }
+
+    #[test]
+    fn addon_commands_and_validation() {
+        let metallb = addon("metallb", Some("10.0.0.100-10.0.0.120"));
+        assert!(validate(&metallb).is_ok());
+        assert!(validate(&addon("metallb", Some("10.0.0.1;reboot"))).is_err());
+        assert!(validate(&addon("Ingress", None)).is_err());
+        assert_eq!(
+            "sudo microk8s enable metallb:10.0.0.100-10.0.0.120",
+            enable_command(&metallb)
+        );
+        assert_eq!(
+            vec![addon("helm3", None), metallb.clone()],
+            with_required(&[metallb])
+        );
+    }
+}
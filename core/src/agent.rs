@@ -0,0 +1,142 @@
+use std::time::{Duration, Instant};
+
+use proxmox_client::model::{AgentExec, AgentExecStatus, VmConfigDetails};
+use proxmox_client::ClientOperations;
+
+pub(crate) const CLOUD_INIT_LOG: &str = "/var/log/cloud-init-output.log";
+const EXEC_STATUS_DELAY: Duration = Duration::from_secs(2);
+
+/// Runs the command through the QEMU guest agent and waits until it exits.
+/// Returns stdout of the command, error contains exit code and output of the command.
+pub(crate) fn execute(
+    proxmox_client: &ClientOperations,
+    node: &str,
+    vm_id: u32,
+    command: &[&str],
+    timeout: Duration,
+) -> Result<String, String> {
+    let pid = proxmox_client
+        .agent_exec(
+            node,
+            vm_id,
+            AgentExec {
+                command: command.iter().map(|i| i.to_string()).collect(),
+                input_data: None,
+            },
+        )?
+        .pid;
+    let started = Instant::now();
+    loop {
+        let status = proxmox_client.agent_exec_status(node, vm_id, pid)?;
+        if status.exited {
+            return command_result(command, status);
+        }
+        if started.elapsed() > timeout {
+            return Err(format!(
+                "Command [{}] hasn't finished in {}s",
+                command.join(" "),
+                timeout.as_secs()
+            ));
+        }
+        std::thread::sleep(EXEC_STATUS_DELAY);
+    }
+}
+
+/// Whether the VM config enables the guest agent, the `agent` option is
+/// `[enabled=]<1|0>[,<other properties>]`. VMs of older clusters don't have it.
+pub(crate) fn is_enabled(config: &VmConfigDetails) -> bool {
+    config
+        .option("agent")
+        .and_then(|i| i.split(',').next())
+        .map(|i| i.trim_start_matches("enabled=") == "1")
+        .unwrap_or_default()
+}
+
+/// Last `lines` lines of the file read through the guest agent.
+pub(crate) fn read_file_tail(
+    proxmox_client: &ClientOperations,
+    node: &str,
+    vm_id: u32,
+    file: &str,
+    lines: usize,
+) -> Result<String, String> {
+    let content = proxmox_client.agent_file_read(node, vm_id, file)?.content;
+    Ok(tail(&content, lines))
+}
+
+fn command_result(command: &[&str], status: AgentExecStatus) -> Result<String, String> {
+    let stdout = status.out_data.unwrap_or_default();
+    match (status.exitcode, status.signal) {
+        (Some(0), _) => Ok(stdout),
+        (_, Some(signal)) => Err(format!(
+            "Command [{}] has been terminated by signal [{}]",
+            command.join(" "),
+            signal
+        )),
+        (exit_code, _) => Err(format!(
+            "Command [{}] failed with exit code [{}]: {}",
+            command.join(" "),
+            exit_code.unwrap_or(-1),
+            [stdout.trim(), status.err_data.unwrap_or_default().trim()]
+                .iter()
+                .filter(|i| !i.is_empty())
+                .cloned()
+                .collect::<Vec<&str>>()
+                .join("\n")
+        )),
+    }
+}
+
+fn tail(content: &str, lines: usize) -> String {
+    let all = content.lines().collect::<Vec<&str>>();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use proxmox_client::model::{AgentExecStatus, VmConfigDetails};
+
+    use crate::agent::{command_result, is_enabled, tail};
+
+    fn status(exitcode: Option<i64>, out: &str, err: &str) -> AgentExecStatus {
+        AgentExecStatus {
+            exited: true,
+            exitcode,
+            signal: None,
+            out_data: Some(out.to_string()),
+            err_data: Some(err.to_string()),
+        }
+    }
+
+    #[test]
+    fn command_result_reports_exit_code_and_output() {
+        assert_eq!(
+            Ok("status: done\n".to_string()),
+            command_result(&["cloud-init", "status"], status(Some(0), "status: done\n", ""))
+        );
+        assert_eq!(
+            Err("Command [cloud-init status] failed with exit code [1]: status: error\nbroken".to_string()),
+            command_result(&["cloud-init", "status"], status(Some(1), "status: error\n", "broken"))
+        );
+    }
+
+    #[test]
+    fn is_enabled_reads_agent_option() {
+        let config = |agent: Option<&str>| VmConfigDetails {
+            options: agent
+                .map(|i| [("agent".to_string(), serde_json::json!(i))].into())
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+        assert!(is_enabled(&config(Some("1"))));
+        assert!(is_enabled(&config(Some("enabled=1,fstrim_cloned_disks=1"))));
+        assert!(!is_enabled(&config(Some("0"))));
+        assert!(!is_enabled(&config(None)));
+    }
+
+    #[test]
+    fn tail_returns_last_lines() {
+        assert_eq!("b\nc", tail("a\nb\nc\n", 2));
+        assert_eq!("a", tail("a", 5));
+    }
+}
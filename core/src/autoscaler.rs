@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::model::{AutoscalingSettings, AutoscalingState, NodePool};
+
+/// `kubectl` command listing the reason of `PodScheduled` condition of pending pods,
+/// one line per pod.
+pub(crate) fn pending_pods_command(kubectl: &str) -> String {
+    format!("sudo {} get pods -A --field-selector=status.phase=Pending -o jsonpath='{{range .items[*]}}{{.metadata.name}}{{\" \"}}{{.status.conditions[?(@.type==\"PodScheduled\")].reason}}{{\"\\n\"}}{{end}}'", kubectl)
+}
+
+pub(crate) fn describe_nodes_command(kubectl: &str) -> String {
+    format!("sudo {} describe nodes", kubectl)
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub(crate) enum Decision {
+    None,
+    ScaleUp,
+    ScaleDown(String),
+}
+
+pub(crate) fn validate(settings: &AutoscalingSettings, pools: &[NodePool]) -> Result<(), String> {
+    let pool = pools
+        .iter()
+        .find(|i| i.name == settings.pool_name)
+        .ok_or(format!("Cannot find node pool [{}]", settings.pool_name))?;
+    if pool.node_type != crate::model::ClusterNodeType::Worker {
+        return Err("Only worker node pool can be autoscaled".to_string());
+    }
+    if settings.max_nodes == 0 || settings.min_nodes > settings.max_nodes {
+        return Err(format!(
+            "Invalid node bounds [{}-{}]",
+            settings.min_nodes, settings.max_nodes
+        ));
+    }
+    if settings.scale_down_utilization == 0 || settings.scale_down_utilization > 100 {
+        return Err("Scale down utilization has to be in range 1-100".to_string());
+    }
+    Ok(())
+}
+
+/// Number of pods which the scheduler cannot place, see [pending_pods_command].
+pub(crate) fn unschedulable_pods(output: &str) -> usize {
+    output
+        .lines()
+        .filter(|i| i.split_whitespace().nth(1) == Some("Unschedulable"))
+        .count()
+}
+
+/// Utilization of Kubernetes nodes in percent, the higher of CPU and memory requests
+/// from `Allocated resources` section of `kubectl describe nodes`.
+pub(crate) fn node_utilization(output: &str) -> HashMap<String, u8> {
+    let mut result = HashMap::new();
+    let mut node: Option<String> = None;
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("Name:") {
+            node = Some(name.trim().to_string());
+            continue;
+        }
+        let mut columns = line.split_whitespace();
+        let resource = columns.next();
+        if resource != Some("cpu") && resource != Some("memory") {
+            continue;
+        }
+        let percent = columns
+            .nth(1)
+            .and_then(|i| i.strip_prefix('('))
+            .and_then(|i| i.strip_suffix("%)"))
+            .and_then(|i| i.parse::<u8>().ok());
+        if let (Some(node), Some(percent)) = (node.as_ref(), percent) {
+            let value = result.entry(node.clone()).or_insert(0);
+            *value = percent.max(*value);
+        }
+    }
+    result
+}
+
+/// Nodes below the utilization threshold keep the time they became idle, others are dropped.
+pub(crate) fn idle_since(
+    previous: &HashMap<String, NaiveDateTime>,
+    utilization: &HashMap<String, u8>,
+    threshold: u8,
+    now: NaiveDateTime,
+) -> HashMap<String, NaiveDateTime> {
+    utilization
+        .iter()
+        .filter(|(_, value)| **value < threshold)
+        .map(|(node, _)| (node.clone(), previous.get(node).cloned().unwrap_or(now)))
+        .collect()
+}
+
+/// Decides one scaling step of the pool. `nodes` are names of the pool nodes with
+/// their utilization, `None` when the node is not reported by Kubernetes yet.
+pub(crate) fn decide(
+    settings: &AutoscalingSettings,
+    state: &AutoscalingState,
+    nodes: &[(String, Option<u8>)],
+    pending_pods: usize,
+    now: NaiveDateTime,
+) -> Decision {
+    let count = nodes.len();
+    let min = usize::from(settings.min_nodes);
+    let max = usize::from(settings.max_nodes);
+    let cooldown = Duration::minutes(i64::from(settings.cooldown));
+
+    if count < min || (pending_pods > 0 && count < max) {
+        return Decision::ScaleUp;
+    }
+    let least_utilized = nodes
+        .iter()
+        .filter_map(|(name, value)| value.map(|v| (v, name)))
+        .min()
+        .map(|(_, name)| name.clone());
+    if count > max {
+        return least_utilized
+            .or(nodes.last().map(|(name, _)| name.clone()))
+            .map(Decision::ScaleDown)
+            .unwrap_or(Decision::None);
+    }
+    if pending_pods > 0 || count <= min {
+        return Decision::None;
+    }
+    if state.last_scale_up.is_some_and(|i| now - i < cooldown) {
+        return Decision::None;
+    }
+    nodes
+        .iter()
+        .filter_map(|(name, value)| {
+            let since = state.idle_since.get(name)?;
+            (now - *since >= cooldown).then_some((value.unwrap_or(0), name))
+        })
+        .min()
+        .map(|(_, name)| Decision::ScaleDown(name.clone()))
+        .unwrap_or(Decision::None)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use chrono::{Duration, NaiveDateTime};
+
+    use crate::autoscaler::{decide, node_utilization, unschedulable_pods, Decision};
+    use crate::model::{AutoscalingSettings, AutoscalingState};
+
+    fn settings() -> AutoscalingSettings {
+        AutoscalingSettings {
+            enabled: true,
+            pool_name: "workers".to_string(),
+            min_nodes: 1,
+            max_nodes: 3,
+            cooldown: 10,
+            scale_down_utilization: 50,
+        }
+    }
+
+    fn nodes(values: &[(&str, Option<u8>)]) -> Vec<(String, Option<u8>)> {
+        values.iter().map(|(n, v)| (n.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn metrics_are_parsed_from_kubectl_output() {
+        assert_eq!(
+            2,
+            unschedulable_pods("web-1 Unschedulable\nweb-2 \ndb-1 Unschedulable\n")
+        );
+        let output = "Name:               c1-workers-1
+Capacity:
+  cpu:                2
+  memory:             4025836Ki
+Allocated resources:
+  (Total limits may be over 100 percent, i.e., overcommitted.)
+  Resource           Requests     Limits
+  --------           --------     ------
+  cpu                750m (37%)   0 (0%)
+  memory             140Mi (3%)   170Mi (4%)
+Name:               c1-workers-2
+Allocated resources:
+  cpu                100m (5%)    0 (0%)
+  memory             1Gi (60%)    0 (0%)
+";
+        assert_eq!(
+            HashMap::from([
+                ("c1-workers-1".to_string(), 37),
+                ("c1-workers-2".to_string(), 60)
+            ]),
+            node_utilization(output)
+        );
+    }
+
+    #[test]
+    fn pending_pods_scale_up_within_bounds() {
+        let now = NaiveDateTime::default();
+        let state = AutoscalingState::default();
+        assert_eq!(
+            Decision::ScaleUp,
+            decide(&settings(), &state, &nodes(&[("w-1", Some(90))]), 2, now)
+        );
+        let full = nodes(&[("w-1", Some(90)), ("w-2", Some(90)), ("w-3", Some(90))]);
+        assert_eq!(Decision::None, decide(&settings(), &state, &full, 2, now));
+        assert_eq!(Decision::ScaleUp, decide(&settings(), &state, &[], 0, now));
+    }
+
+    #[test]
+    fn idle_node_is_removed_after_cooldown() {
+        let now = NaiveDateTime::default() + Duration::hours(1);
+        let pool = nodes(&[("w-1", Some(10)), ("w-2", Some(5))]);
+        let mut state = AutoscalingState {
+            idle_since: HashMap::from([
+                ("w-1".to_string(), now - Duration::minutes(20)),
+                ("w-2".to_string(), now - Duration::minutes(5)),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            Decision::ScaleDown("w-1".to_string()),
+            decide(&settings(), &state, &pool, 0, now)
+        );
+
+        state.last_scale_up = Some(now - Duration::minutes(1));
+        assert_eq!(Decision::None, decide(&settings(), &state, &pool, 0, now));
+    }
+}
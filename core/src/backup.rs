@@ -0,0 +1,155 @@
+use chrono::{DateTime, NaiveDateTime};
+use proxmox_client::model::StorageContent;
+
+use crate::model::{BackupMode, BackupSet, BackupVolume, Cluster};
+
+/// Backups created by one vzdump run don't share any identifier, so volumes
+/// with the same notes created within this window form one backup set.
+const BACKUP_SET_WINDOW_SECONDS: u64 = 2 * 60 * 60;
+
+impl From<&BackupMode> for proxmox_client::model::BackupMode {
+    fn from(value: &BackupMode) -> Self {
+        match value {
+            BackupMode::Snapshot => proxmox_client::model::BackupMode::Snapshot,
+            BackupMode::Suspend => proxmox_client::model::BackupMode::Suspend,
+            BackupMode::Stop => proxmox_client::model::BackupMode::Stop,
+        }
+    }
+}
+
+pub(crate) fn backup_job_id(cluster_name: &str) -> String {
+    format!("makoon-{}", cluster_name)
+}
+
+pub(crate) fn manual_backup_notes(cluster_name: &str, created: NaiveDateTime) -> String {
+    format!("makoon:{}:{}", cluster_name, created.format("%Y%m%d%H%M%S"))
+}
+
+pub(crate) fn scheduled_backup_notes(cluster_name: &str) -> String {
+    format!("makoon:{}:scheduled", cluster_name)
+}
+
+pub fn backup_sets(cluster: &Cluster, volumes: Vec<StorageContent>) -> Vec<BackupSet> {
+    let mut volumes = volumes
+        .into_iter()
+        .filter(|i| {
+            i.vm_id
+                .map(|vm_id| cluster.nodes.iter().any(|n| n.vm_id == vm_id))
+                .unwrap_or(false)
+        })
+        .collect::<Vec<StorageContent>>();
+    volumes.sort_by_key(|i| i.ctime.unwrap_or_default());
+
+    let mut result: Vec<(u64, BackupSet)> = Vec::new();
+    for volume in volumes.into_iter() {
+        let vm_id = volume.vm_id.unwrap_or_default();
+        let created = volume.ctime.unwrap_or_default();
+        let notes = volume.notes.clone().unwrap_or_default();
+        let backup_volume = BackupVolume {
+            vm_id,
+            volid: volume.volid.clone(),
+            size: u32::try_from(volume.size / 1024 / 1024).unwrap_or(u32::MAX),
+        };
+
+        let existing_set = result.iter_mut().rev().find(|(start, set)| {
+            set.notes == notes
+                && created - start <= BACKUP_SET_WINDOW_SECONDS
+                && !set.volumes.iter().any(|v| v.vm_id == vm_id)
+        });
+        match existing_set {
+            Some((_, set)) => set.volumes.push(backup_volume),
+            None => result.push((
+                created,
+                BackupSet {
+                    id: volume.volid,
+                    notes,
+                    created: DateTime::from_timestamp(created as i64, 0)
+                        .map(|i| i.naive_utc())
+                        .unwrap_or_default(),
+                    volumes: vec![backup_volume],
+                    complete: false,
+                },
+            )),
+        }
+    }
+
+    let mut result = result
+        .into_iter()
+        .map(|(_, mut set)| {
+            set.complete = set.volumes.len() == cluster.nodes.len();
+            set
+        })
+        .collect::<Vec<BackupSet>>();
+    result.sort_by_key(|i| std::cmp::Reverse(i.created));
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use proxmox_client::model::StorageContent;
+
+    use crate::backup::backup_sets;
+    use crate::model::{Cluster, ClusterNode, ClusterNodeType};
+    use crate::test_fixtures;
+
+    fn node(vm_id: u32) -> ClusterNode {
+        test_fixtures::node(&format!("node-{}", vm_id), vm_id, "", ClusterNodeType::Master)
+    }
+
+    fn volume(vm_id: u32, ctime: u64, notes: &str) -> StorageContent {
+        StorageContent {
+            format: "vma.zst".to_string(),
+            size: 1024 * 1024 * 1024,
+            volid: format!("backup:backup/vzdump-qemu-{}-{}.vma.zst", vm_id, ctime),
+            vm_id: Some(vm_id),
+            ctime: Some(ctime),
+            encrypted: None,
+            notes: Some(notes.to_string()),
+            parent: None,
+            protected: None,
+            used: None,
+            verification: None,
+        }
+    }
+
+    #[test]
+    fn group_volumes_of_one_run_into_set() {
+        let cluster = Cluster {
+            nodes: vec![node(100), node(101)],
+            ..Default::default()
+        };
+        let result = backup_sets(
+            &cluster,
+            vec![
+                volume(100, 1000, "makoon:c:scheduled"),
+                volume(101, 1200, "makoon:c:scheduled"),
+                volume(100, 90000, "makoon:c:scheduled"),
+                volume(101, 90100, "makoon:c:scheduled"),
+                volume(102, 90200, "makoon:c:scheduled"),
+            ],
+        );
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].volumes.len(), 2);
+        assert!(result[0].complete);
+        assert_eq!(result[1].id, "backup:backup/vzdump-qemu-100-1000.vma.zst");
+    }
+
+    #[test]
+    fn split_sets_with_different_notes() {
+        let cluster = Cluster {
+            nodes: vec![node(100), node(101)],
+            ..Default::default()
+        };
+        let result = backup_sets(
+            &cluster,
+            vec![
+                volume(100, 1000, "makoon:c:20240101000000"),
+                volume(100, 1100, "makoon:c:20240101000140"),
+                volume(101, 1200, "makoon:c:20240101000140"),
+            ],
+        );
+        assert_eq!(result.len(), 2);
+        assert!(result[0].complete);
+        assert!(!result[1].complete);
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::NaiveDateTime;
+
+use crate::model::CertificateStatus;
+
+pub(crate) const CERTS_DIR: &str = "/var/snap/microk8s/current/certs";
+
+/// Certificates which can be refreshed by `microk8s refresh-certs --cert <name>`
+pub(crate) const REFRESHABLE_CERTS: [&str; 2] = ["server.crt", "front-proxy-client.crt"];
+pub(crate) const CA_CERT: &str = "ca.crt";
+
+/// Entry of a node whose certificates cannot be read.
+pub(crate) fn unreadable(node_name: &str, error: String) -> CertificateStatus {
+    CertificateStatus {
+        node_name: node_name.to_string(),
+        file: String::new(),
+        expires: None,
+        error: Some(error),
+    }
+}
+
+/// Prints one `<file> notAfter=<date>` line per certificate in the MicroK8s certs folder.
+pub(crate) fn expiry_command() -> String {
+    format!(
+        "sudo sh -c 'for f in {}/*.crt; do echo \"$(basename $f) $(openssl x509 -enddate -noout -in $f)\"; done'",
+        CERTS_DIR
+    )
+}
+
+pub(crate) fn parse_expiry(node_name: &str, output: &str) -> Vec<CertificateStatus> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (file, end_date) = line.trim().split_once(' ')?;
+            let expires = end_date
+                .trim()
+                .strip_prefix("notAfter=")
+                .and_then(|i| NaiveDateTime::parse_from_str(i.trim(), "%b %e %H:%M:%S %Y GMT").ok());
+            Some(CertificateStatus {
+                node_name: node_name.to_string(),
+                file: file.to_string(),
+                expires,
+                error: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDate;
+    use crate::certificates::{parse_expiry, unreadable};
+
+    #[test]
+    fn parse_expiry_reads_openssl_end_date() {
+        let output = "ca.crt notAfter=Mar  4 10:15:30 2034 GMT\n\
+                      server.crt notAfter=Nov 21 08:00:00 2025 GMT\n\
+                      broken.crt unable to load certificate\n";
+        let result = parse_expiry("master-1", output);
+
+        assert_eq!(3, result.len());
+        assert_eq!("ca.crt", result[0].file);
+        assert_eq!(
+            Some(NaiveDate::from_ymd_opt(2034, 3, 4).unwrap().and_hms_opt(10, 15, 30).unwrap()),
+            result[0].expires
+        );
+        assert_eq!(
+            Some(NaiveDate::from_ymd_opt(2025, 11, 21).unwrap().and_hms_opt(8, 0, 0).unwrap()),
+            result[1].expires
+        );
+        assert_eq!(None, result[2].expires);
+        assert!(result.iter().all(|i| i.node_name == "master-1"));
+        assert!(result.iter().all(|i| i.error.is_none()));
+    }
+
+    #[test]
+    fn unreadable_node_has_error() {
+        let result = unreadable("master-2", "Connection refused".to_string());
+        assert_eq!("master-2", result.node_name);
+        assert_eq!(None, result.expires);
+        assert_eq!(Some("Connection refused".to_string()), result.error);
+    }
+}
@@ -0,0 +1,305 @@
+use proxmox_client::model::StorageContentType;
+use proxmox_client::ClientOperations;
+
+use crate::model::{CloudInitSettings, Cluster, ClusterNode, Network, ValidationError};
+use crate::{keys, Error};
+
+const BOUNDARY: &str = "==MAKOON_CLOUD_INIT_BOUNDARY==";
+// Parts of the user document are merged into the Makoon part, lists are appended and values
+// of Makoon are kept so the user, password and SSH keys stay usable by Makoon
+const MERGE_TYPE: &str = "list(append)+dict(recurse_array,no_replace)+str()";
+const DOCUMENT_TYPES: [(&str, &str); 5] = [
+    ("#cloud-config", "text/cloud-config"),
+    ("#cloud-boothook", "text/cloud-boothook"),
+    ("#part-handler", "text/part-handler"),
+    ("#include", "text/x-include-url"),
+    ("#!", "text/x-shellscript"),
+];
+
+pub(crate) fn validate(settings: &CloudInitSettings) -> Result<(), String> {
+    if settings.storage.trim().is_empty() {
+        return Err("Storage for cloud-init snippets is empty".to_string());
+    }
+    if let Some(user_data) = settings.user_data.as_ref() {
+        document_type(user_data).ok_or(format!(
+            "Unsupported cloud-init user-data, it has to start with one of: {}",
+            supported_headers()
+        ))?;
+        if user_data.contains(BOUNDARY) {
+            return Err("cloud-init user-data contains reserved MIME boundary".to_string());
+        }
+    }
+    if let Some(vendor_data) = settings.vendor_data.as_ref() {
+        document_type(vendor_data).ok_or(format!(
+            "Unsupported cloud-init vendor-data, it has to start with one of: {}",
+            supported_headers()
+        ))?;
+    }
+    Ok(())
+}
+
+/// DHCP nodes are discovered through the QEMU guest agent, which the user-data of Makoon
+/// installs at first boot. Without custom cloud-init settings the user-data of DHCP nodes is
+/// uploaded to the first storage of the Proxmox node with snippets content. Other nodes use
+/// the cloud-init drive of Proxmox and get the agent over SSH.
+pub(crate) fn settings(
+    proxmox_client: &ClientOperations,
+    proxmox_node: &str,
+    settings: Option<CloudInitSettings>,
+    network: &Network,
+    nodes: &[ClusterNode],
+) -> crate::Result<Option<CloudInitSettings>> {
+    if settings.is_some() || !nodes.iter().any(|i| network.is_dhcp_node(i)) {
+        return Ok(settings);
+    }
+    let storage = proxmox_client
+        .storage(proxmox_node, Some(StorageContentType::Snippets))?
+        .into_iter()
+        .next()
+        .ok_or(Error::Validation(vec![ValidationError {
+            field: "cloudInit.storage".to_string(),
+            message: format!(
+                "DHCP nodes need a storage with snippets content on Proxmox node [{}], cloud-init installs QEMU guest agent from there",
+                proxmox_node
+            ),
+        }]))?;
+    Ok(Some(CloudInitSettings {
+        storage: storage.storage,
+        user_data: None,
+        vendor_data: None,
+    }))
+}
+
+/// Value of VM `cicustom` option, `None` when the cluster has no custom documents.
+pub(crate) fn cicustom(cluster: &Cluster, node: &ClusterNode) -> Option<String> {
+    let settings = cluster.cloud_init.as_ref()?;
+    let mut volumes = vec![format!(
+        "user={}",
+        volume_id(&settings.storage, &user_data_file_name(cluster, node))
+    )];
+    if settings.vendor_data.is_some() {
+        volumes.push(format!(
+            "vendor={}",
+            volume_id(&settings.storage, &vendor_data_file_name(cluster))
+        ));
+    }
+    Some(volumes.join(","))
+}
+
+pub(crate) fn user_data_file_name(cluster: &Cluster, node: &ClusterNode) -> String {
+    format!("makoon-{}-{}-user.yaml", cluster.cluster_name, node.name)
+}
+
+pub(crate) fn vendor_data_file_name(cluster: &Cluster) -> String {
+    format!("makoon-{}-vendor.yaml", cluster.cluster_name)
+}
+
+pub(crate) fn volume_id(storage: &str, file_name: &str) -> String {
+    format!("{}:snippets/{}", storage, file_name)
+}
+
+/// User-data of the node. Custom user-data replaces the one generated by Proxmox, so Makoon
+/// adds its own part with the host name, user and SSH keys and lets cloud-init merge both.
+pub(crate) fn user_data(cluster: &Cluster, node: &ClusterNode) -> Result<String, String> {
+    Ok(render_user_data(cluster, node, &hashed_password(&cluster.node_password)?))
+}
+
+/// [user_data] with `mask` in place of the password hash, execution plans show it.
+pub(crate) fn masked_user_data(cluster: &Cluster, node: &ClusterNode, mask: &str) -> String {
+    render_user_data(cluster, node, mask)
+}
+
+fn render_user_data(cluster: &Cluster, node: &ClusterNode, hashed_passwd: &str) -> String {
+    let makoon_part = makoon_cloud_config(cluster, node, hashed_passwd);
+    let document = match cluster
+        .cloud_init
+        .as_ref()
+        .and_then(|i| i.user_data.as_ref())
+    {
+        Some(v) => v,
+        None => return makoon_part,
+    };
+    let content_type = document_type(document).unwrap_or("text/cloud-config");
+
+    [
+        format!("Content-Type: multipart/mixed; boundary=\"{}\"", BOUNDARY),
+        "MIME-Version: 1.0".to_string(),
+        "".to_string(),
+        format!("--{}", BOUNDARY),
+        "Content-Type: text/cloud-config; charset=\"utf-8\"".to_string(),
+        "MIME-Version: 1.0".to_string(),
+        "Content-Disposition: attachment; filename=\"makoon.cfg\"".to_string(),
+        "".to_string(),
+        makoon_part,
+        format!("--{}", BOUNDARY),
+        format!("Content-Type: {}; charset=\"utf-8\"", content_type),
+        "MIME-Version: 1.0".to_string(),
+        format!("Merge-Type: {}", MERGE_TYPE),
+        "Content-Disposition: attachment; filename=\"user-data\"".to_string(),
+        "".to_string(),
+        document.trim_end().to_string(),
+        format!("--{}--", BOUNDARY),
+        "".to_string(),
+    ]
+    .join("\n")
+}
+
+/// Same content as the user-data which Proxmox generates from `ciuser`, `cipassword`
+/// and `sshkeys`, the password is hashed. Strings are written as JSON, which is valid YAML.
+fn makoon_cloud_config(cluster: &Cluster, node: &ClusterNode, hashed_passwd: &str) -> String {
+    let host_name = node.host_name(&cluster.cluster_name);
+    let ssh_keys = keys::cloud_init_ssh_keys(cluster)
+        .lines()
+        .map(|i| format!("  - {}", quote(i)))
+        .collect::<Vec<String>>();
+    [
+        vec![
+            "#cloud-config".to_string(),
+            format!("hostname: {}", quote(&host_name)),
+            "manage_etc_hosts: true".to_string(),
+            format!("fqdn: {}", quote(&host_name)),
+            "user:".to_string(),
+            format!("  name: {}", quote(&cluster.node_username)),
+            format!("  hashed_passwd: {}", quote(hashed_passwd)),
+            "  lock_passwd: false".to_string(),
+            "ssh_authorized_keys:".to_string(),
+        ],
+        ssh_keys,
+        vec![
+            "chpasswd:".to_string(),
+            "  expire: false".to_string(),
+            "users:".to_string(),
+            "  - default".to_string(),
+            "package_upgrade: true".to_string(),
+            "packages:".to_string(),
+            "  - qemu-guest-agent".to_string(),
+            "runcmd:".to_string(),
+            "  - [systemctl, start, qemu-guest-agent]".to_string(),
+            "".to_string(),
+        ],
+    ]
+    .concat()
+    .join("\n")
+}
+
+/// SHA-512 crypt (`$6$`) hash of the password with random salt, the format `chpasswd`
+/// and `/etc/shadow` accept.
+fn hashed_password(password: &str) -> Result<String, String> {
+    pwhash::sha512_crypt::hash(password).map_err(|e| format!("Cannot hash node password: {}", e))
+}
+
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn document_type(document: &str) -> Option<&'static str> {
+    let first_line = document.trim_start().lines().next()?.trim_end();
+    DOCUMENT_TYPES
+        .iter()
+        .find(|(header, _)| first_line.starts_with(header))
+        .map(|(_, content_type)| *content_type)
+}
+
+fn supported_headers() -> String {
+    DOCUMENT_TYPES
+        .iter()
+        .map(|(header, _)| *header)
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cloud_init::{cicustom, hashed_password, user_data, validate};
+    use crate::model::{CloudInitSettings, Cluster, ClusterNode, ClusterNodeType, KeyPair};
+    use crate::test_fixtures;
+
+    fn cluster(user_data: Option<&str>, vendor_data: Option<&str>) -> Cluster {
+        Cluster {
+            cluster_name: "c1".to_string(),
+            node_username: "makoon".to_string(),
+            node_password: "pa\"ss".to_string(),
+            ssh_key: KeyPair {
+                private_key: "".to_string(),
+                public_key: "ssh-ed25519 AAAA makoon".to_string(),
+            },
+            cloud_init: Some(CloudInitSettings {
+                storage: "local".to_string(),
+                user_data: user_data.map(|i| i.to_string()),
+                vendor_data: vendor_data.map(|i| i.to_string()),
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn node() -> ClusterNode {
+        test_fixtures::node("master-1", 101, "10.0.0.11", ClusterNodeType::Master)
+    }
+
+    #[test]
+    fn user_data_merges_user_document_into_makoon_part() {
+        let result = user_data(
+            &cluster(Some("#cloud-config\npackages:\n  - nfs-common\n"), None),
+            &node(),
+        )
+        .unwrap();
+        assert!(result.starts_with("Content-Type: multipart/mixed;"));
+        assert!(result.contains("hostname: \"c1-master-1\""));
+        assert!(result.contains("  hashed_passwd: \"$6$"));
+        assert!(!result.contains("pa\\\"ss"));
+        assert!(result.contains("  - \"ssh-ed25519 AAAA makoon\""));
+        assert!(result.contains("Merge-Type: list(append)"));
+        assert!(result.contains("packages:\n  - nfs-common\n--==MAKOON_CLOUD_INIT_BOUNDARY==--"));
+    }
+
+    fn sha512_crypt(password: &str, salt: &str) -> String {
+        pwhash::sha512_crypt::hash_with(format!("$6${}", salt).as_str(), password).unwrap()
+    }
+
+    #[test]
+    fn sha512_crypt_matches_reference_hashes() {
+        assert_eq!(
+            "$6$saltstring$svn8UoSVapNtMuq1ukKS4tPQd8iKwSMHWjl/O817G3uBnIFNjnQJuesI68u4OTLiBFdcbYEdFCoEOfaS35inz1",
+            sha512_crypt("Hello world!", "saltstring")
+        );
+        assert_eq!(
+            "$6$toolongsaltstrin$iGlL7EUUfzNQx59x3ydJZ.zXPMUu1dOynSEl/vcNhLlas77qD0DzRswhhB6LdrXTz250at0syAfUXra.XrxAI1",
+            sha512_crypt("Hello world!", "toolongsaltstring")
+        );
+        assert_eq!(
+            "$6$abc$pgZOJDv7B5dr8JttBywspRuJLtF.Tc2xA0H.9h66C37Aw92/WCZAMVPRKOooKZyplQngU3Cj6iGpRth5G.2US.",
+            sha512_crypt(
+                "a-very-long-password-of-more-than-sixty-four-bytes-to-cover-the-chunk-loop-ok",
+                "abc"
+            )
+        );
+    }
+
+    #[test]
+    fn hashed_password_uses_random_salt() {
+        let first = hashed_password("secret").unwrap();
+        assert!(first.starts_with("$6$"));
+        assert_eq!(3 + 16 + 1 + 86, first.len());
+        assert_ne!(first, hashed_password("secret").unwrap());
+    }
+
+    #[test]
+    fn user_data_without_user_document_is_plain_cloud_config() {
+        let result = user_data(&cluster(None, Some("#cloud-config\n")), &node()).unwrap();
+        assert!(result.starts_with("#cloud-config\n"));
+        assert_eq!(
+            Some("user=local:snippets/makoon-c1-master-1-user.yaml,vendor=local:snippets/makoon-c1-vendor.yaml".to_string()),
+            cicustom(&cluster(None, Some("#cloud-config\n")), &node())
+        );
+    }
+
+    #[test]
+    fn validate_rejects_unknown_document() {
+        let settings = CloudInitSettings {
+            storage: "local".to_string(),
+            user_data: Some("packages: []".to_string()),
+            vendor_data: None,
+        };
+        assert!(validate(&settings).is_err());
+    }
+}
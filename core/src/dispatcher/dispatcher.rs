@@ -177,6 +177,7 @@ impl Dispatcher {
                 node_name,
                 cores,
                 memory,
+                hardware,
             } => {
                 match change_resources::execute(
                     self.proxmox_client.clone(),
@@ -186,6 +187,7 @@ impl Dispatcher {
                     node_name.clone(),
                     cores,
                     memory,
+                    hardware,
                 ) {
                     Ok(_) => {
                         self.repo.save_log(LogEntry::info(
@@ -231,6 +233,427 @@ impl Dispatcher {
                     }
                 }
             }
+            Event::CreateClusterSnapshot {
+                access,
+                cluster_name,
+                snapshot_name,
+                description,
+                freeze_fs,
+                previous_status,
+            } => {
+                update_cluster_status(&self.repo, cluster_name.clone(), ClusterStatus::Snapshotting)?;
+                match usecase::create_cluster_snapshot::execute(
+                    self.proxmox_client.clone(),
+                    self.repo.clone(),
+                    access,
+                    cluster_name.clone(),
+                    snapshot_name.clone(),
+                    description,
+                    freeze_fs,
+                ) {
+                    Ok(_) => {
+                        self.repo.save_log(LogEntry::info(
+                            &cluster_name,
+                            format!("Snapshot [{}] has been created", snapshot_name),
+                        ))?;
+                        update_cluster_status(&self.repo, cluster_name, previous_status)?;
+                        info!("Cluster snapshot has been created");
+                        Ok(())
+                    }
+                    Err(e) => {
+                        update_cluster_status(
+                            &self.repo,
+                            cluster_name.clone(),
+                            ClusterStatus::Error,
+                        )?;
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
+            Event::RollbackClusterSnapshot {
+                access,
+                cluster_name,
+                snapshot_name,
+                previous_status,
+            } => {
+                update_cluster_status(&self.repo, cluster_name.clone(), ClusterStatus::Snapshotting)?;
+                match usecase::rollback_cluster_snapshot::execute(
+                    self.proxmox_client.clone(),
+                    self.repo.clone(),
+                    access,
+                    cluster_name.clone(),
+                    snapshot_name.clone(),
+                ) {
+                    Ok(_) => {
+                        self.repo.save_log(LogEntry::info(
+                            &cluster_name,
+                            format!("Cluster has been rolled back to snapshot [{}]", snapshot_name),
+                        ))?;
+                        update_cluster_status(&self.repo, cluster_name, previous_status)?;
+                        info!("Cluster has been rolled back");
+                        Ok(())
+                    }
+                    Err(e) => {
+                        update_cluster_status(
+                            &self.repo,
+                            cluster_name.clone(),
+                            ClusterStatus::Error,
+                        )?;
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
+            Event::DeleteClusterSnapshot {
+                access,
+                cluster_name,
+                snapshot_name,
+            } => {
+                match usecase::delete_cluster_snapshot::execute(
+                    self.proxmox_client.clone(),
+                    self.repo.clone(),
+                    access,
+                    cluster_name.clone(),
+                    snapshot_name.clone(),
+                ) {
+                    Ok(_) => {
+                        self.repo.save_log(LogEntry::info(
+                            &cluster_name,
+                            format!("Snapshot [{}] has been deleted", snapshot_name),
+                        ))?;
+                        update_cluster_status(&self.repo, cluster_name, ClusterStatus::Sync)?;
+                        info!("Cluster snapshot has been deleted");
+                        Ok(())
+                    }
+                    Err(e) => {
+                        update_cluster_status(
+                            &self.repo,
+                            cluster_name.clone(),
+                            ClusterStatus::Error,
+                        )?;
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
+            Event::BackupCluster {
+                access,
+                cluster_name,
+            } => {
+                match usecase::backup_cluster::execute(
+                    self.proxmox_client.clone(),
+                    self.repo.clone(),
+                    access,
+                    cluster_name.clone(),
+                ) {
+                    Ok(_) => {
+                        self.repo.save_log(LogEntry::info(
+                            &cluster_name,
+                            "Cluster backup has been created".to_string(),
+                        ))?;
+                        update_cluster_status(&self.repo, cluster_name, ClusterStatus::Sync)?;
+                        info!("Cluster backup has been created");
+                        Ok(())
+                    }
+                    Err(e) => {
+                        update_cluster_status(
+                            &self.repo,
+                            cluster_name.clone(),
+                            ClusterStatus::Error,
+                        )?;
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
+            Event::RestoreClusterBackup {
+                access,
+                cluster_name,
+                backup_set_id,
+                node_name,
+            } => {
+                match usecase::restore_cluster_backup::execute(
+                    self.proxmox_client.clone(),
+                    self.repo.clone(),
+                    access,
+                    cluster_name.clone(),
+                    backup_set_id.clone(),
+                    node_name,
+                ) {
+                    Ok(_) => {
+                        self.repo.save_log(LogEntry::info(
+                            &cluster_name,
+                            format!("Cluster has been restored from backup [{}]", backup_set_id),
+                        ))?;
+                        update_cluster_status(&self.repo, cluster_name, ClusterStatus::Sync)?;
+                        info!("Cluster has been restored from backup");
+                        Ok(())
+                    }
+                    Err(e) => {
+                        update_cluster_status(
+                            &self.repo,
+                            cluster_name.clone(),
+                            ClusterStatus::Error,
+                        )?;
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
+            Event::RotateSshKey {
+                access,
+                cluster_name,
+                previous_status,
+            } => {
+                match usecase::rotate_ssh_key::execute(
+                    self.proxmox_client.clone(),
+                    self.repo.clone(),
+                    access,
+                    cluster_name.clone(),
+                ) {
+                    Ok(_) => {
+                        self.repo.save_log(LogEntry::info(
+                            &cluster_name,
+                            "SSH key has been rotated".to_string(),
+                        ))?;
+                        update_cluster_status(&self.repo, cluster_name, previous_status)?;
+                        info!("SSH key has been rotated");
+                        Ok(())
+                    }
+                    Err(e) => {
+                        update_cluster_status(
+                            &self.repo,
+                            cluster_name.clone(),
+                            ClusterStatus::Error,
+                        )?;
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
+            Event::ScaleUpNodePool {
+                access,
+                cluster_name,
+                pool_name,
+            } => {
+                match usecase::scale_up_node_pool::execute(
+                    self.proxmox_client.clone(),
+                    self.repo.clone(),
+                    access,
+                    cluster_name.clone(),
+                    pool_name.clone(),
+                ) {
+                    Ok(_) => {
+                        self.repo.save_log(LogEntry::info(
+                            &cluster_name,
+                            format!("Node pool [{}] has been scaled up", pool_name),
+                        ))?;
+                        update_cluster_status(&self.repo, cluster_name, ClusterStatus::Sync)?;
+                        info!("Node pool [{}] has been scaled up", pool_name);
+                        Ok(())
+                    }
+                    Err(e) => {
+                        update_cluster_status(
+                            &self.repo,
+                            cluster_name.clone(),
+                            ClusterStatus::Error,
+                        )?;
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
+            Event::ScaleDownNodePool {
+                access,
+                cluster_name,
+                pool_name,
+            } => {
+                match usecase::scale_down_node_pool::execute(
+                    self.proxmox_client.clone(),
+                    self.repo.clone(),
+                    access,
+                    cluster_name.clone(),
+                    pool_name.clone(),
+                ) {
+                    Ok(_) => {
+                        self.repo.save_log(LogEntry::info(
+                            &cluster_name,
+                            format!("Node pool [{}] has been scaled down", pool_name),
+                        ))?;
+                        update_cluster_status(&self.repo, cluster_name, ClusterStatus::Sync)?;
+                        info!("Node pool [{}] has been scaled down", pool_name);
+                        Ok(())
+                    }
+                    Err(e) => {
+                        update_cluster_status(
+                            &self.repo,
+                            cluster_name.clone(),
+                            ClusterStatus::Error,
+                        )?;
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
+            Event::Autoscale {
+                access,
+                cluster_name,
+            } => {
+                match usecase::autoscale::execute(
+                    self.proxmox_client.clone(),
+                    self.repo.clone(),
+                    access,
+                    cluster_name.clone(),
+                ) {
+                    Ok(_) => Ok(()),
+                    Err(e) => {
+                        update_cluster_status(
+                            &self.repo,
+                            cluster_name.clone(),
+                            ClusterStatus::Error,
+                        )?;
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
+            Event::SyncNodeMetadata {
+                cluster_name,
+                node_name,
+                removed_labels,
+                removed_taints,
+                scheduled,
+            } => {
+                match usecase::sync_node_metadata::execute(
+                    self.repo.clone(),
+                    cluster_name.clone(),
+                    node_name,
+                    removed_labels,
+                    removed_taints,
+                ) {
+                    Ok(_) if scheduled => Ok(()),
+                    Ok(_) => {
+                        self.repo.save_log(LogEntry::info(
+                            &cluster_name,
+                            "Labels and taints have been synchronized".to_string(),
+                        ))?;
+                        update_cluster_status(&self.repo, cluster_name, ClusterStatus::Sync)?;
+                        info!("Labels and taints have been synchronized");
+                        Ok(())
+                    }
+                    // Periodic synchronization retries on the next run, the cluster itself is fine
+                    Err(e) if scheduled => {
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                    Err(e) => {
+                        update_cluster_status(
+                            &self.repo,
+                            cluster_name.clone(),
+                            ClusterStatus::Error,
+                        )?;
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
+            Event::ChangeAddon {
+                cluster_name,
+                addon,
+                enabled,
+            } => {
+                let name = addon.name.clone();
+                match usecase::change_addon::execute(
+                    self.repo.clone(),
+                    cluster_name.clone(),
+                    addon,
+                    enabled,
+                ) {
+                    Ok(_) => {
+                        let state = if enabled { "enabled" } else { "disabled" };
+                        self.repo.save_log(LogEntry::info(
+                            &cluster_name,
+                            format!("MicroK8s addon [{}] has been {}", name, state),
+                        ))?;
+                        update_cluster_status(&self.repo, cluster_name, ClusterStatus::Sync)?;
+                        info!("MicroK8s addon [{}] has been {}", name, state);
+                        Ok(())
+                    }
+                    Err(e) => {
+                        update_cluster_status(
+                            &self.repo,
+                            cluster_name.clone(),
+                            ClusterStatus::Error,
+                        )?;
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
+            Event::RefreshCertificates {
+                cluster_name,
+                include_ca,
+            } => {
+                match usecase::refresh_certificates::execute(
+                    self.repo.clone(),
+                    cluster_name.clone(),
+                    include_ca,
+                ) {
+                    Ok(_) => {
+                        self.repo.save_log(LogEntry::info(
+                            &cluster_name,
+                            "Certificates have been refreshed".to_string(),
+                        ))?;
+                        update_cluster_status(&self.repo, cluster_name, ClusterStatus::Sync)?;
+                        info!("Certificates have been refreshed");
+                        Ok(())
+                    }
+                    Err(e) => {
+                        update_cluster_status(
+                            &self.repo,
+                            cluster_name.clone(),
+                            ClusterStatus::Error,
+                        )?;
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
+            Event::ConfigureClusterBackup {
+                access,
+                cluster_name,
+            } => {
+                match usecase::configure_cluster_backup::execute(
+                    self.proxmox_client.clone(),
+                    self.repo.clone(),
+                    access,
+                    cluster_name.clone(),
+                ) {
+                    Ok(_) => {
+                        info!("Cluster backup has been configured");
+                        Ok(())
+                    }
+                    Err(e) => {
+                        self.repo
+                            .save_log(LogEntry::error(&cluster_name, e.clone()))?;
+                        Err(e)
+                    }
+                }
+            }
         }
     }
 }
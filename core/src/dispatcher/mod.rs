@@ -5,4 +5,9 @@ mod usecase;
 mod utils;
 pub use usecase::install_cluster_resource;
 pub use usecase::install_helm_app;
-pub use usecase::HELM_CMD;
+// Dry runs cover the operations below. Makoon has no Kubernetes upgrade operation yet,
+// its plan comes with it.
+pub(crate) use usecase::add_node_to_cluster::plan as plan_add_node_to_cluster;
+pub(crate) use usecase::create_cluster::plan as plan_create_cluster;
+pub(crate) use usecase::delete_cluster::plan as plan_delete_cluster;
+pub(crate) use usecase::delete_node_from_cluster::plan as plan_delete_node_from_cluster;
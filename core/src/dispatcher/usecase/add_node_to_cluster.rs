@@ -5,8 +5,8 @@ use log::info;
 use proxmox_client::model::AccessData;
 use proxmox_client::Client;
 use crate::dispatcher::usecase::common;
-use crate::model::{Cluster, ClusterNode, ClusterNodeType, LogEntry};
-use crate::Repository;
+use crate::model::{Cluster, ClusterNode, ClusterNodeType, ExecutionPlan, LogEntry};
+use crate::{distribution, Repository};
 
 
 pub(crate) fn execute(
@@ -24,7 +24,7 @@ pub(crate) fn execute(
         "Start creating node".to_string(),
     ))?;
 
-    let cluster = repo
+    let mut cluster = repo
         .get_cluster(&cluster_name)?
         .ok_or("Cannot find cluster")?;
     let master_node = cluster
@@ -40,66 +40,181 @@ pub(crate) fn execute(
         .filter(|i| i.name != node_name)
         .map(|i| {
             (
-                format!("{}-{}", cluster.cluster_name, i.name),
+                i.host_name(&cluster.cluster_name),
                 i.ip_address.clone(),
             )
         })
         .collect::<HashMap<String, String>>();
 
-    let existing_nodes = common::vm::get_existing_vms(&proxmox_client, &cluster)?
+    let existing_nodes: Vec<ClusterNode> = common::vm::get_existing_vms(&proxmox_client, &cluster)?
         .into_iter()
         .filter(|i| i.name != node_name)
         .collect();
 
-    let node_to_add = cluster
-        .nodes
-        .iter()
-        .find(|i| i.name == node_name)
-        .ok_or("Cannot find node to create")?;
-
-    common::vm::create(&proxmox_client, repo.clone(), &cluster, node_to_add)?;
-
-    proxmox_client
-        .start_vm(&cluster.node, node_to_add.vm_id)
-        .map_err(|e| format!("Cannot start VM [{}]: {}", node_to_add.vm_id, e))?;
-    repo.save_log(LogEntry::info(
-        &cluster.cluster_name,
-        format!("Starting VM [{}]", node_to_add.vm_id),
-    ))?;
-
-    common::vm::wait_for_start(&proxmox_client, &cluster, &node_to_add)
-        .map_err(|e| format!("Cannot start VM [{}]: {}", node_to_add.vm_id, e))?;
-    repo.save_log(LogEntry::info(
-        &cluster.cluster_name,
-        format!("VM [{}] has been started", node_to_add.vm_id),
-    ))?;
-
-    common::vm::restart_vm_if_necessary(&proxmox_client, repo.clone(), &cluster, node_to_add)?;
+    let mut node_to_add = find_node(&cluster, &node_name)?;
+    for step in steps(&cluster) {
+        match step {
+            Step::CreateVm => common::vm::create(&proxmox_client, repo.clone(), &cluster, &node_to_add)?,
+            Step::StartVm => {
+                proxmox_client
+                    .start_vm(&cluster.node, node_to_add.vm_id)
+                    .map_err(|e| format!("Cannot start VM [{}]: {}", node_to_add.vm_id, e))?;
+                repo.save_log(LogEntry::info(
+                    &cluster.cluster_name,
+                    format!("Starting VM [{}]", node_to_add.vm_id),
+                ))?;
+            }
+            Step::DiscoverIpAddress => {
+                common::vm::discover_ip_address(&proxmox_client, repo.clone(), &mut cluster, node_to_add.vm_id)?;
+                node_to_add = find_node(&cluster, &node_name)?;
+            }
+            Step::WaitForStart => {
+                common::vm::wait_for_start(&proxmox_client, &cluster, &node_to_add)
+                    .map_err(|e| format!("Cannot start VM [{}]: {}", node_to_add.vm_id, e))?;
+                repo.save_log(LogEntry::info(
+                    &cluster.cluster_name,
+                    format!("VM [{}] has been started", node_to_add.vm_id),
+                ))?;
+            }
+            Step::RestartVmIfNecessary => {
+                common::vm::restart_vm_if_necessary(&proxmox_client, repo.clone(), &cluster, &node_to_add)?
+            }
+            Step::SetupVm => setup_vm(repo.clone(), &cluster, &node_to_add, exising_cluster_hosts.clone())?,
+            Step::AddHostToExistingNodes => {
+                add_new_node_host_to_existing_cluster(repo.clone(), &cluster, &existing_nodes)?
+            }
+            Step::InstallKubernetes => {
+                common::cluster::install_kubernetes(repo.clone(), &cluster, &node_to_add, false)?
+            }
+            Step::WaitForReadyKubernetes => {
+                common::cluster::wait_for_ready_kubernetes(repo.clone(), &cluster, &node_to_add)?
+            }
+            Step::JoinNode => {
+                common::cluster::join_node_to_cluster(repo.clone(), &cluster, &master_node, &node_to_add)?
+            }
+            Step::RefreshBackupJob => {
+                common::backup::refresh_backup_job(&proxmox_client, repo.clone(), &cluster_name)?
+            }
+        }
+    }
 
-    setup_vm(repo.clone(), &cluster, node_to_add, exising_cluster_hosts)?;
+    Ok(())
+}
 
-    add_new_node_host_to_existing_cluster(repo.clone(), &cluster, existing_nodes)?;
+/// Steps of adding the node, [execute] runs them and [plan] describes them.
+enum Step {
+    CreateVm,
+    StartVm,
+    DiscoverIpAddress,
+    WaitForStart,
+    RestartVmIfNecessary,
+    SetupVm,
+    AddHostToExistingNodes,
+    InstallKubernetes,
+    WaitForReadyKubernetes,
+    JoinNode,
+    RefreshBackupJob,
+}
 
-    common::cluster::install_kubernetes(repo.clone(), &cluster, node_to_add)?;
+fn steps(cluster: &Cluster) -> Vec<Step> {
+    let mut steps = vec![
+        Step::CreateVm,
+        Step::StartVm,
+        Step::DiscoverIpAddress,
+        Step::WaitForStart,
+        Step::RestartVmIfNecessary,
+        Step::SetupVm,
+        Step::AddHostToExistingNodes,
+        Step::InstallKubernetes,
+    ];
+    if distribution::of(cluster).starts_before_join() {
+        steps.push(Step::WaitForReadyKubernetes);
+    }
+    steps.push(Step::JoinNode);
+    steps.push(Step::RefreshBackupJob);
+    steps
+}
 
-    common::cluster::wait_for_ready_kubernetes(repo.clone(), &cluster, node_to_add)?;
+fn find_node(cluster: &Cluster, node_name: &str) -> Result<ClusterNode, String> {
+    cluster
+        .nodes
+        .iter()
+        .find(|i| i.name == node_name)
+        .cloned()
+        .ok_or("Cannot find node to create".to_string())
+}
 
-    common::cluster::join_node_to_cluster(repo.clone(), &cluster, &master_node, node_to_add)?;
+/// Actions of [execute] in the same order, nothing is run. `cluster` has the node
+/// already, other nodes of the cluster are expected to have their VMs.
+pub(crate) fn plan(cluster: &Cluster, node_name: &str) -> Result<ExecutionPlan, String> {
+    let cluster = &common::plan::with_dhcp_placeholders(cluster.clone());
+    let master_node = cluster
+        .nodes
+        .iter()
+        .find(|i| i.node_type == ClusterNodeType::Master && i.name != node_name)
+        .ok_or("Cannot find any master node".to_string())?;
+    let node_to_add = &find_node(cluster, node_name)?;
+    let hosts = cluster
+        .nodes
+        .iter()
+        .map(|i| (common::plan::host_name(cluster, i), i.ip_address.clone()))
+        .collect::<HashMap<String, String>>();
 
-    Ok(())
+    let mut actions = vec![];
+    for step in steps(cluster) {
+        match step {
+            Step::CreateVm => actions.extend(common::plan::create_vm(cluster, node_to_add)?),
+            Step::StartVm => actions.push(common::plan::start_vm(cluster, node_to_add)),
+            Step::DiscoverIpAddress => {
+                actions.extend(common::plan::discover_ip_address(cluster, node_to_add))
+            }
+            Step::WaitForStart => actions.push(common::plan::wait_for_start(cluster, node_to_add)),
+            Step::RestartVmIfNecessary => {
+                actions.push(common::plan::restart_vm_if_necessary(cluster, node_to_add))
+            }
+            Step::SetupVm => {
+                actions.extend(common::plan::setup_vm(cluster, node_to_add, "Configure VM", &hosts))
+            }
+            Step::AddHostToExistingNodes => {
+                for node in cluster.nodes.iter().filter(|i| i.name != node_name) {
+                    actions.extend(common::plan::setup_vm(
+                        cluster,
+                        node,
+                        "Add new node hostname to existing VM",
+                        &hosts,
+                    ));
+                }
+            }
+            Step::InstallKubernetes => {
+                actions.extend(common::plan::install_kubernetes(cluster, node_to_add, false))
+            }
+            Step::WaitForReadyKubernetes => {
+                actions.push(common::plan::wait_for_ready_kubernetes(cluster, node_to_add))
+            }
+            Step::JoinNode => actions.extend(common::plan::join_node(cluster, master_node, node_to_add)?),
+            Step::RefreshBackupJob => actions.extend(common::plan::refresh_backup_job(cluster)),
+        }
+    }
+    Ok(ExecutionPlan {
+        operation: format!(
+            "Add node [{}] to cluster [{}]",
+            node_name, cluster.cluster_name
+        ),
+        actions,
+    })
 }
 
 fn add_new_node_host_to_existing_cluster(
     repo: Arc<Repository>,
     cluster: &Cluster,
-    existing_nodes: Vec<ClusterNode>,
+    existing_nodes: &[ClusterNode],
 ) -> Result<(), String> {
     let hosts = cluster
         .nodes
         .iter()
         .map(|i| {
             (
-                format!("{}-{}", cluster.cluster_name, i.name),
+                i.host_name(&cluster.cluster_name),
                 i.ip_address.clone(),
             )
         })
@@ -126,7 +241,7 @@ fn setup_vm(
         .iter()
         .map(|i| {
             (
-                format!("{}-{}", cluster.cluster_name, i.name),
+                i.host_name(&cluster.cluster_name),
                 i.ip_address.clone(),
             )
         })
@@ -141,3 +256,76 @@ fn setup_vm(
     common::vm::setup_vm(cluster, node, &hosts)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::dispatcher::usecase::add_node_to_cluster::plan;
+    use crate::model::{BackupMode, BackupSettings, Cluster, ClusterNodeType, Network};
+    use crate::test_fixtures::node;
+
+    #[test]
+    fn plan_follows_add_node_order() {
+        let cluster = Cluster {
+            node: "pve".to_string(),
+            cluster_name: "c1".to_string(),
+            node_username: "ubuntu".to_string(),
+            disk_size: 20,
+            network: Network {
+                gateway: "10.0.0.1".to_string(),
+                subnet_mask: 24,
+                bridge: "vmbr0".to_string(),
+                ..Default::default()
+            },
+            nodes: vec![
+                node("master-1", 101, "10.0.0.11", ClusterNodeType::Master),
+                node("worker-1", 102, "10.0.0.12", ClusterNodeType::Worker),
+                node("worker-2", 103, "10.0.0.13", ClusterNodeType::Worker),
+            ],
+            backup: Some(BackupSettings {
+                storage: "pbs".to_string(),
+                keep_last: 3,
+                schedule: String::new(),
+                mode: BackupMode::default(),
+            }),
+            ..Default::default()
+        };
+        let actions = plan(&cluster, "worker-2").unwrap().actions;
+        let index = |description: &str| {
+            actions
+                .iter()
+                .position(|i| i.description.starts_with(description))
+                .unwrap()
+        };
+        let order = [
+            "Create VM [103]",
+            "Start VM [103]",
+            "Wait for VM [103] start",
+            "Restart VM [103]",
+            "Configure VM",
+            "Add new node hostname to existing VM",
+            "Install Kubernetes",
+            "Generate join token",
+            "Join node",
+            "Update VMs of backup job",
+        ];
+        for pair in order.windows(2) {
+            assert!(index(pair[0]) < index(pair[1]), "{} before {}", pair[0], pair[1]);
+        }
+
+        let hostname_targets = actions
+            .iter()
+            .filter(|i| i.description == "Add new node hostname to existing VM")
+            .map(|i| i.target.as_str())
+            .collect::<HashSet<&str>>();
+        assert_eq!(
+            ["c1-master-1", "c1-worker-1"].into_iter().collect::<HashSet<&str>>(),
+            hostname_targets
+        );
+        assert!(actions
+            .iter()
+            .filter(|i| i.description == "Install Kubernetes")
+            .all(|i| i.target == "c1-worker-2"));
+    }
+}
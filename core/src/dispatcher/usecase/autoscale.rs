@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::Utc;
+use log::{info, warn};
+use proxmox_client::model::AccessData;
+use proxmox_client::{Client, ClientOperations};
+use crate::autoscaler::{self, Decision};
+use crate::dispatcher::usecase::{add_node_to_cluster, delete_node_from_cluster};
+use crate::model::{Cluster, ClusterNode, ClusterNodeLock, ClusterNodeType, ClusterStatus, LogEntry, NodePool};
+use crate::preflight::{self, RequestedVm};
+use crate::{cloud_init, distribution, ipam, network, node_pool, Repository};
+
+/// Checks pending pods and utilization of the autoscaled pool and adds or removes
+/// at most one node. Failures before a node is added or removed only block autoscaling
+/// until the next check, errors of the change itself are returned.
+pub(crate) fn execute(
+    proxmox_client: Arc<Client>,
+    repo: Arc<Repository>,
+    access: AccessData,
+    cluster_name: String,
+) -> Result<(), String> {
+    let mut cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    let settings = match cluster.autoscaling.clone() {
+        Some(v) if v.enabled => v,
+        _ => return Ok(()),
+    };
+    if cluster.status != ClusterStatus::Sync || cluster.nodes.iter().any(|i| i.lock.is_some()) {
+        return Ok(());
+    }
+    let pool = match cluster
+        .node_pools
+        .iter()
+        .find(|i| i.name == settings.pool_name)
+        .cloned()
+    {
+        Some(v) => v,
+        None => {
+            let reason = format!("Cannot find node pool [{}]", settings.pool_name);
+            return block(&repo, cluster, reason);
+        }
+    };
+    let (pending_pods, utilization) = match observe(&cluster) {
+        Ok(v) => v,
+        Err(e) => {
+            let reason = format!("Autoscaler cannot read load of the cluster: {}", e);
+            return block(&repo, cluster, reason);
+        }
+    };
+
+    let pool_nodes = cluster
+        .nodes
+        .iter()
+        .filter(|i| i.pool.as_ref() == Some(&pool.name))
+        .map(|i| {
+            let host_name = i.host_name(&cluster_name);
+            (i.name.clone(), utilization.get(&host_name).cloned())
+        })
+        .collect::<Vec<(String, Option<u8>)>>();
+    let pool_utilization = pool_nodes
+        .iter()
+        .filter_map(|(name, value)| value.map(|v| (name.clone(), v)))
+        .collect::<HashMap<String, u8>>();
+    let now = Utc::now().naive_utc();
+    cluster.autoscaling_state.idle_since = autoscaler::idle_since(
+        &cluster.autoscaling_state.idle_since,
+        &pool_utilization,
+        settings.scale_down_utilization,
+        now,
+    );
+
+    match autoscaler::decide(
+        &settings,
+        &cluster.autoscaling_state,
+        &pool_nodes,
+        pending_pods,
+        now,
+    ) {
+        Decision::None => {
+            cluster.autoscaling_state.blocked_reason = None;
+            repo.save_cluster(cluster)?;
+            Ok(())
+        }
+        Decision::ScaleUp => scale_up(
+            proxmox_client,
+            repo,
+            access,
+            cluster,
+            pool,
+            pending_pods,
+        ),
+        Decision::ScaleDown(node_name) => {
+            info!("Autoscaler removes node [{}]", node_name);
+            update_pool(&mut cluster, &pool.name, pool_nodes.len() - 1);
+            cluster.autoscaling_state.last_scale_down = Some(now);
+            cluster.autoscaling_state.idle_since.remove(&node_name);
+            repo.save_cluster(cluster)?;
+            repo.save_log(LogEntry::info(
+                &cluster_name,
+                format!(
+                    "Autoscaler removes idle node [{}] from pool [{}]",
+                    node_name, pool.name
+                ),
+            ))?;
+            delete_node_from_cluster::execute(
+                proxmox_client,
+                repo,
+                access,
+                cluster_name,
+                node_name,
+            )
+        }
+    }
+}
+
+fn scale_up(
+    proxmox_client: Arc<Client>,
+    repo: Arc<Repository>,
+    access: AccessData,
+    mut cluster: Cluster,
+    pool: NodePool,
+    pending_pods: usize,
+) -> Result<(), String> {
+    let cluster_name = cluster.cluster_name.clone();
+    let operations = proxmox_client.operations(access.clone());
+    let mut nodes = match prepare_nodes(&operations, &repo, &mut cluster, &pool) {
+        Ok(v) => v,
+        Err(e) => {
+            let reason = format!("Autoscaler cannot add node to pool [{}]: {}", pool.name, e);
+            return block(&repo, cluster, reason);
+        }
+    };
+    let mut node = nodes.remove(0);
+    node.lock = Some(ClusterNodeLock::Create);
+    let node_name = node.name.clone();
+
+    let count = cluster
+        .nodes
+        .iter()
+        .filter(|i| i.pool.as_ref() == Some(&pool.name))
+        .count();
+    cluster.nodes.push(node);
+    update_pool(&mut cluster, &pool.name, count + 1);
+    cluster.autoscaling_state.last_scale_up = Some(Utc::now().naive_utc());
+    cluster.autoscaling_state.blocked_reason = None;
+    repo.save_cluster(cluster)?;
+    repo.save_log(LogEntry::info(
+        &cluster_name,
+        format!(
+            "Autoscaler adds node [{}] to pool [{}], unschedulable pods: {}",
+            node_name, pool.name, pending_pods
+        ),
+    ))?;
+
+    add_node_to_cluster::execute(
+        proxmox_client,
+        repo.clone(),
+        access,
+        cluster_name.clone(),
+        node_name.clone(),
+    )?;
+
+    let mut cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    cluster
+        .nodes
+        .iter_mut()
+        .filter(|i| i.name == node_name)
+        .for_each(|i| i.lock = None);
+    repo.save_cluster(cluster)?;
+    repo.save_log(LogEntry::info(
+        &cluster_name,
+        format!("Node [{}] has been created", node_name),
+    ))?;
+    Ok(())
+}
+
+/// Unschedulable pods and utilization of the nodes, read on the first master node.
+fn observe(cluster: &Cluster) -> Result<(usize, HashMap<String, u8>), String> {
+    let master_node = cluster
+        .nodes
+        .iter()
+        .find(|i| i.node_type == ClusterNodeType::Master)
+        .ok_or("Cannot find any master node")?;
+    let mut ssh_client = ssh_client::Client::new();
+    ssh_client.connect(
+        &master_node.ip_address,
+        &cluster.node_username,
+        &cluster.ssh_key.private_key,
+        &cluster.ssh_key.public_key,
+    )?;
+    let kubectl = distribution::of(cluster).kubectl();
+    let pending_pods = autoscaler::unschedulable_pods(
+        &ssh_client.execute(&autoscaler::pending_pods_command(kubectl))?,
+    );
+    let utilization = autoscaler::node_utilization(
+        &ssh_client.execute(&autoscaler::describe_nodes_command(kubectl))?,
+    );
+    Ok((pending_pods, utilization))
+}
+
+/// New node of the pool with an assigned IP address, Proxmox node has to have capacity for it.
+fn prepare_nodes(
+    operations: &ClientOperations,
+    repo: &Repository,
+    cluster: &mut Cluster,
+    pool: &NodePool,
+) -> Result<Vec<ClusterNode>, String> {
+    let mut nodes =
+        node_pool::new_nodes(operations, repo, cluster, pool, 1).map_err(|e| e.to_string())?;
+    network::validate(&cluster.network, &nodes)?;
+    let requested = nodes
+        .iter()
+        .map(|i| RequestedVm::new(&cluster.cluster_name, i, cluster.disk_size))
+        .collect::<Vec<RequestedVm>>();
+    preflight::run(operations, &cluster.node, &[], &requested)
+        .and_then(|i| preflight::require_passed(&i))?;
+    cluster.cloud_init = cloud_init::settings(
+        operations,
+        &cluster.node,
+        cluster.cloud_init.clone(),
+        &cluster.network,
+        &nodes,
+    )
+    .map_err(|e| e.to_string())?;
+    ipam::assign_ip_addresses(repo, &cluster.cluster_name, &cluster.network, &mut nodes)
+        .map_err(|e| e.to_string())?;
+    Ok(nodes)
+}
+
+/// Autoscaling is retried on the next check, the reason is logged once and the
+/// cluster status isn't changed.
+fn block(repo: &Repository, mut cluster: Cluster, reason: String) -> Result<(), String> {
+    if cluster.autoscaling_state.blocked_reason.as_ref() != Some(&reason) {
+        warn!("Autoscaling of cluster [{}] is blocked: {}", cluster.cluster_name, reason);
+        repo.save_log(LogEntry::error(&cluster.cluster_name, reason.clone()))?;
+        cluster.autoscaling_state.blocked_reason = Some(reason);
+        repo.save_cluster(cluster)?;
+    }
+    Ok(())
+}
+
+fn update_pool(cluster: &mut Cluster, pool_name: &str, desired_count: usize) {
+    if let Some(pool) = cluster.node_pools.iter_mut().find(|i| i.name == pool_name) {
+        pool.desired_count = u16::try_from(desired_count).unwrap_or(u16::MAX);
+    }
+}
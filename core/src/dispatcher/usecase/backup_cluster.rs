@@ -0,0 +1,56 @@
+use std::sync::Arc;
+use chrono::Utc;
+use log::info;
+
+use proxmox_client::model::{AccessData, Vzdump};
+use proxmox_client::Client;
+use crate::backup;
+use crate::dispatcher::usecase::common;
+use crate::model::LogEntry;
+use crate::Repository;
+
+
+pub(crate) fn execute(
+    proxmox_client: Arc<Client>,
+    repo: Arc<Repository>,
+    access: AccessData,
+    cluster_name: String,
+) -> Result<(), String> {
+    info!("Request to backup the cluster has been received");
+    let proxmox_client = proxmox_client.operations(access);
+
+    let cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    let settings = cluster
+        .backup
+        .clone()
+        .ok_or("Backup is not configured for the cluster")?;
+    let existing_nodes = common::vm::get_existing_vms(&proxmox_client, &cluster)?;
+
+    repo.save_log(LogEntry::info(
+        &cluster_name,
+        format!("Start backup of VMs to storage [{}]", settings.storage),
+    ))?;
+
+    let upid = proxmox_client.vzdump(Vzdump {
+        node: cluster.node.clone(),
+        vm_id: existing_nodes
+            .iter()
+            .map(|i| i.vm_id.to_string())
+            .collect::<Vec<String>>()
+            .join(","),
+        storage: settings.storage.clone(),
+        mode: (&settings.mode).into(),
+        compress: Some("zstd".to_string()),
+        prune_backups: Some(format!("keep-last={}", settings.keep_last)),
+        notes_template: Some(backup::manual_backup_notes(
+            &cluster_name,
+            Utc::now().naive_utc(),
+        )),
+    })?;
+
+    // Backup of the whole cluster takes much longer than other tasks, wait up to one hour
+    common::vm::wait_for_task_with_opts(&proxmox_client, &cluster.node, &upid, 10, 360)?;
+    Ok(())
+}
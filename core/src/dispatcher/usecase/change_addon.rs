@@ -0,0 +1,45 @@
+use std::sync::Arc;
+use log::info;
+
+use crate::addons;
+use crate::model::{ClusterAddon, ClusterNodeType, LogEntry};
+use crate::Repository;
+
+pub(crate) fn execute(
+    repo: Arc<Repository>,
+    cluster_name: String,
+    addon: ClusterAddon,
+    enabled: bool,
+) -> Result<(), String> {
+    info!("Request to change MicroK8s addon has been received");
+    let cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    let master_node = cluster
+        .nodes
+        .iter()
+        .find(|i| i.node_type == ClusterNodeType::Master)
+        .ok_or("Cannot find any master node")?;
+    let mut ssh_client = ssh_client::Client::new();
+    ssh_client.connect(
+        &master_node.ip_address,
+        &cluster.node_username,
+        &cluster.ssh_key.private_key,
+        &cluster.ssh_key.public_key,
+    )?;
+
+    if enabled {
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Enable MicroK8s addon [{}]", addon.name),
+        ))?;
+        ssh_client.execute(&addons::enable_command(&addon))?;
+    } else {
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Disable MicroK8s addon [{}]", addon.name),
+        ))?;
+        ssh_client.execute(&addons::disable_command(&addon.name))?;
+    }
+    Ok(())
+}
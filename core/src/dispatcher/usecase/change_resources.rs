@@ -2,14 +2,15 @@ use std::sync::Arc;
 use log::info;
 
 use proxmox_client::{
-    model::{AccessData, VmConfig},
+    model::{AccessData, ScsiHw, VmConfig},
     Client,
 };
 use crate::dispatcher::usecase::common;
-use crate::model::LogEntry;
+use crate::model::{HardwareProfile, LogEntry};
 use crate::Repository;
 
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn execute(
     proxmox_client: Arc<Client>,
     repo: Arc<Repository>,
@@ -18,6 +19,7 @@ pub(crate) fn execute(
     node_name: String,
     cores: u16,
     memory: u32,
+    hardware: Option<HardwareProfile>,
 ) -> Result<(), String> {
     info!("Cluster creation request has been received");
     let proxmox_client = proxmox_client.operations(access);
@@ -36,11 +38,19 @@ pub(crate) fn execute(
         .iter()
         .find(|i| i.name == node_name)
         .ok_or("Cannot find node to create")?;
+    let hardware = hardware.as_ref();
     proxmox_client.update_config(VmConfig {
         vm_id: node_to_change.vm_id,
         node: cluster.node.clone(),
-        cores,
-        memory: u64::from(memory),
+        cores: Some(cores),
+        memory: Some(u64::from(memory)),
+        cpu: hardware.and_then(|i| i.cpu_type.clone()),
+        sockets: hardware.and_then(|i| i.sockets),
+        numa: hardware.map(|i| u8::from(i.numa)),
+        balloon: hardware.and_then(|i| i.balloon).map(u64::from),
+        machine: hardware.and_then(|i| i.machine.clone()),
+        scsihw: hardware.map(|i| ScsiHw::from(&i.scsi_controller)),
+        ..Default::default()
     })?;
 
     common::vm::stop_vm(&proxmox_client, &cluster.node, node_to_change.vm_id)?;
@@ -53,6 +63,15 @@ pub(crate) fn execute(
         if node.name == node_name {
             node.cores = cores;
             node.memory = memory;
+            if let Some(hardware) = hardware {
+                // disk options are applied only when the VM is created
+                node.hardware = HardwareProfile {
+                    disk_cache: node.hardware.disk_cache.clone(),
+                    disk_discard: node.hardware.disk_discard,
+                    disk_iothread: node.hardware.disk_iothread,
+                    ..hardware.clone()
+                };
+            }
         }
     }
     repo.save_cluster(cluster)?;
@@ -1,73 +1,54 @@
 pub(crate) mod vm {
     use std::collections::HashMap;
-    use std::path::Path;
     use std::sync::Arc;
+    use std::time::Duration;
+    use chrono::Utc;
     use log::{error, info};
 
     use proxmox_client::model::{
-        CreateVirtualMachine, DownloadImage, DownloadImageContentType, OsType, ParamBuilder,
-        ResizeDisk, ScsiHw, StorageContent, VmStatus,
+        CreateVirtualMachine, DownloadImage, DownloadImageContentType,
+        OsType, ParamBuilder, ResizeDisk, ResourcePool, ScsiHw, StorageContent,
+        TaskState, VmStatus,
     };
     use proxmox_client::{to_url_encoded, ClientOperations};
-    use crate::dispatcher::utils::retry;
+    use crate::agent;
+    use crate::dispatcher::utils::{retry, retry_with_opts};
+    use crate::hardware;
+    use crate::keys;
     use crate::model::{Cluster, ClusterNode, LogEntry};
+    use crate::network;
+    use crate::{os_image, recovery, supported, tags};
     use crate::Repository;
 
 
+    const CLOUD_INIT_TIMEOUT: u64 = 600;
+
     pub(crate) fn create(
         proxmox_client: &ClientOperations,
         repo: Arc<Repository>,
         cluster: &Cluster,
         node: &ClusterNode,
     ) -> Result<(), String> {
-        let os_image_path = download_os_image(proxmox_client, repo, cluster)?;
+        network::interface_params(&cluster.network, node)?;
+        let available_bridges = network::proxmox_bridges(proxmox_client, &cluster.node)?;
+        network::validate_bridges(
+            &cluster.network,
+            std::slice::from_ref(node),
+            &cluster.node,
+            &available_bridges,
+        )?;
 
-        proxmox_client.create_virtual_machine(CreateVirtualMachine {
-            vm_id: node.vm_id,
-            node: cluster.node.clone(),
-            name: format!("{}-{}", cluster.cluster_name, node.name),
-            cores: node.cores,
-            memory: u64::from(node.memory),
-            os_type: OsType::L26,
-            net: HashMap::from([(
-                "net0".to_owned(),
-                ParamBuilder::default()
-                    .add_param("model", "virtio")
-                    .add_param("bridge", &cluster.network.bridge)
-                    .build(),
-            )]),
-            scsihw: Some(ScsiHw::VirtioScsiPci),
-            scsi: HashMap::from([(
-                "scsi0".to_owned(),
-                ParamBuilder::default()
-                    .add_param_with_separator(&node.storage_pool, "0", ":")
-                    .add_param("import-from", &os_image_path)
-                    .build(),
-            )]),
-            ide: HashMap::from([(
-                "ide2".to_owned(),
-                ParamBuilder::default()
-                    .add_param_with_separator(&node.storage_pool, "cloudinit", ":")
-                    .build(),
-            )]),
-            boot: Some(ParamBuilder::default().add_param("order", "scsi0").build()),
-            vga: Some("serial0".to_string()),
-            serial: HashMap::from([("serial0".to_owned(), "socket".to_owned())]),
-            ipconfig: HashMap::from([(
-                "ipconfig0".to_owned(),
-                ParamBuilder::default()
-                    .add_param(
-                        "ip",
-                        format!("{}/{}", node.ip_address, cluster.network.subnet_mask).as_str(),
-                    )
-                    .add_param("gw", cluster.network.gateway.as_str())
-                    .build(),
-            )]),
-            nameserver: Some(cluster.network.dns.clone()),
-            ci_user: Some(cluster.node_username.clone()),
-            ci_password: Some(cluster.node_password.clone()),
-            ssh_keys: Some(to_url_encoded(&cluster.ssh_key.public_key)),
-        })?;
+        if let Some(pool) = cluster.resource_pool.as_ref() {
+            ensure_resource_pool(proxmox_client, repo.clone(), cluster, pool)?;
+        }
+        let os_image_path = download_os_image(proxmox_client, repo, cluster)?;
+        let cicustom = super::cloud_init::upload_snippets(proxmox_client, cluster, node)?;
+        proxmox_client.create_virtual_machine(create_request(
+            cluster,
+            node,
+            &os_image_path,
+            cicustom,
+        )?)?;
 
         retry(|| {
             let locked = proxmox_client
@@ -87,16 +68,97 @@ pub(crate) mod vm {
             vm_id: node.vm_id,
             node: cluster.node.clone(),
             disk: "scsi0".to_string(),
-            size: format!("{}G", cluster.disk_size),
+            size: disk_size(cluster, node),
         })?;
         Ok(())
     }
 
-    fn download_os_image(
+    /// VM of the node, system disk is imported from `os_image` and `cicustom` points to the
+    /// uploaded cloud-init snippets.
+    pub(crate) fn create_request(
+        cluster: &Cluster,
+        node: &ClusterNode,
+        os_image: &str,
+        cicustom: Option<String>,
+    ) -> Result<CreateVirtualMachine, String> {
+        let interfaces = network::interface_params(&cluster.network, node)?;
+        let mut system_disk = ParamBuilder::default();
+        system_disk
+            .add_param_with_separator(&node.storage_pool, "0", ":")
+            .add_param("import-from", os_image);
+        hardware::add_disk_params(&mut system_disk, &node.hardware);
+
+        Ok(CreateVirtualMachine {
+            vm_id: node.vm_id,
+            node: cluster.node.clone(),
+            name: node.host_name(&cluster.cluster_name),
+            cores: node.cores,
+            memory: u64::from(node.memory),
+            os_type: OsType::L26,
+            net: interfaces
+                .iter()
+                .map(|i| (format!("net{}", i.index), i.net.clone()))
+                .collect(),
+            agent: Some("1".to_string()),
+            cpu: node.hardware.cpu_type.clone(),
+            sockets: node.hardware.sockets,
+            numa: node.hardware.numa.then_some(1),
+            balloon: node.hardware.balloon.map(u64::from),
+            machine: node.hardware.machine.clone(),
+            scsihw: Some(ScsiHw::from(&node.hardware.scsi_controller)),
+            scsi: HashMap::from([("scsi0".to_owned(), system_disk.build())]),
+            ide: HashMap::from([(
+                "ide2".to_owned(),
+                ParamBuilder::default()
+                    .add_param_with_separator(&node.storage_pool, "cloudinit", ":")
+                    .build(),
+            )]),
+            boot: Some(ParamBuilder::default().add_param("order", "scsi0").build()),
+            vga: Some("serial0".to_string()),
+            serial: HashMap::from([("serial0".to_owned(), "socket".to_owned())]),
+            ipconfig: interfaces
+                .iter()
+                .map(|i| (format!("ipconfig{}", i.index), i.ipconfig.clone()))
+                .collect(),
+            nameserver: Some(network::nameservers(&cluster.network)),
+            ci_user: Some(cluster.node_username.clone()),
+            ci_password: Some(cluster.node_password.clone()),
+            ssh_keys: Some(to_url_encoded(&keys::cloud_init_ssh_keys(cluster))),
+            cicustom,
+            tags: Some(tags::vm_tags(cluster, node)),
+            pool: cluster.resource_pool.clone(),
+            description: Some(recovery::description(cluster, node)),
+        })
+    }
+
+    pub(crate) fn disk_size(cluster: &Cluster, node: &ClusterNode) -> String {
+        format!("{}G", node.disk_size.unwrap_or(cluster.disk_size))
+    }
+
+    fn ensure_resource_pool(
         proxmox_client: &ClientOperations,
         repo: Arc<Repository>,
         cluster: &Cluster,
-    ) -> Result<String, String> {
+        pool: &str,
+    ) -> Result<(), String> {
+        if proxmox_client.pools()?.iter().any(|i| i.pool_id == pool) {
+            return Ok(());
+        }
+        proxmox_client
+            .create_pool(ResourcePool {
+                pool_id: pool.to_string(),
+                comment: Some(format!("Makoon cluster [{}]", cluster.cluster_name)),
+            })
+            .map_err(|e| format!("Cannot create Proxmox pool [{}]: {}", pool, e))?;
+        repo.save_log(LogEntry::info(
+            &cluster.cluster_name,
+            format!("Proxmox pool [{}] has been created", pool),
+        ))?;
+        Ok(())
+    }
+
+    /// URL of the OS image of the cluster, storage and file name it's downloaded to.
+    pub(crate) fn os_image_source(cluster: &Cluster) -> Result<(String, String, String), String> {
         let os_image = cluster.os_image.clone().unwrap_or(
             "https://cloud-images.ubuntu.com/kinetic/current/kinetic-server-cloudimg-amd64.img"
                 .to_string(),
@@ -106,35 +168,73 @@ pub(crate) mod vm {
             .clone()
             .unwrap_or("local".to_string());
 
-        let file_name = Path::new(&os_image)
-            .file_name()
-            .map(|i| i.to_string_lossy().to_string())
-            .ok_or("Cannot extract file name for path".to_string())?;
+        let catalog = supported::os_images();
+        let file_name = os_image::file_name(&os_image, os_image::find(&catalog, &os_image))?;
+        Ok((os_image, os_image_storage, file_name))
+    }
+
+    pub(crate) fn os_image_volume_id(storage: &str, file_name: &str) -> String {
+        format!("{}:iso/{}", storage, file_name)
+    }
+
+    fn download_os_image(
+        proxmox_client: &ClientOperations,
+        repo: Arc<Repository>,
+        cluster: &Cluster,
+    ) -> Result<String, String> {
+        let (os_image, os_image_storage, file_name) = os_image_source(cluster)?;
+        let catalog = supported::os_images();
+        let catalog_image = os_image::find(&catalog, &os_image);
+        let volid = os_image_volume_id(&os_image_storage, &file_name);
 
         let get_storage_content = || -> Result<Option<StorageContent>, String> {
             Ok(proxmox_client
                 .storage_content(&cluster.node, &os_image_storage)?
                 .iter()
-                .find(|i| i.volid.ends_with(&file_name))
+                .find(|i| i.volid == volid)
                 .cloned())
         };
 
-        let existing_image = get_storage_content()
+        let mut existing_image = get_storage_content()
             .map_err(|e| format!("Cannot check image availability [{}]", e.to_string()))?;
+        let now = u64::try_from(Utc::now().timestamp()).unwrap_or_default();
+        if let Some(image) = existing_image
+            .as_ref()
+            .filter(|i| os_image::is_expired(catalog_image, i.ctime, now))
+        {
+            repo.save_log(LogEntry::info(
+                &cluster.cluster_name,
+                format!("OS image [{}] is outdated, it will be downloaded again", file_name),
+            ))?;
+            if let Some(upid) =
+                proxmox_client.delete_storage_content(&cluster.node, &os_image_storage, &image.volid)?
+            {
+                wait_for_task(proxmox_client, &cluster.node, &upid)?;
+            }
+            existing_image = None;
+        }
 
         let existing_image = match existing_image {
             Some(v) => v,
             None => {
-                proxmox_client.download_image(DownloadImage {
+                let checksum = match catalog_image {
+                    Some(v) => os_image::checksum(v)?,
+                    None => None,
+                };
+                let verified = checksum.is_some();
+                let upid = proxmox_client.download_image(DownloadImage {
                     content: DownloadImageContentType::Iso,
                     filename: file_name.clone(),
                     node: cluster.node.clone(),
                     storage: os_image_storage.clone(),
                     url: os_image,
-                    checksum: None,
-                    checksum_algorithm: None,
+                    checksum: checksum.as_ref().map(|(v, _)| v.clone()),
+                    checksum_algorithm: checksum.map(|(_, a)| a.to_string()),
                     verify_certificates: None,
                 })?;
+                // Checksum is verified by the download task
+                wait_for_task_with_opts(proxmox_client, &cluster.node, &upid, 10, 90)
+                    .map_err(|e| format!("Cannot download OS image [{}]: {}", file_name, e))?;
 
                 let image = retry(|| {
                     get_storage_content()
@@ -146,7 +246,10 @@ pub(crate) mod vm {
 
                 repo.save_log(LogEntry::info(
                     &cluster.cluster_name,
-                    format!("OS image has been downloaded [{}]", file_name),
+                    match verified {
+                        true => format!("OS image has been downloaded and verified [{}]", file_name),
+                        false => format!("OS image has been downloaded [{}]", file_name),
+                    },
                 ))?;
                 image
             }
@@ -190,6 +293,65 @@ pub(crate) mod vm {
         }
     }
 
+    /// Reads the address of a DHCP node through the QEMU guest agent and stores it in
+    /// the cluster, so SSH and hosts files use it afterwards. Static nodes are kept as they are.
+    pub(crate) fn discover_ip_address(
+        proxmox_client: &ClientOperations,
+        repo: Arc<Repository>,
+        cluster: &mut Cluster,
+        vm_id: u32,
+    ) -> Result<(), String> {
+        let node = cluster
+            .nodes
+            .iter()
+            .find(|i| i.vm_id == vm_id)
+            .ok_or(format!("Cannot find node for VM [{}]", vm_id))?;
+        if !cluster.network.is_dhcp_node(node) {
+            return Ok(());
+        }
+
+        repo.save_log(LogEntry::info(
+            &cluster.cluster_name,
+            format!("Discover IP address of VM [{}]", vm_id),
+        ))?;
+        let ipv6_only = network::is_ipv6(&cluster.network.gateway);
+        let (ip_address, ip6_address) = retry(|| {
+            let interfaces = proxmox_client
+                .agent_network_interfaces(&cluster.node, vm_id)
+                .map_err(|e| format!("QEMU guest agent of VM [{}] is not available: {}", vm_id, e))?;
+            network::discovered_addresses(&interfaces, ipv6_only)
+                .ok_or(format!("VM [{}] has no IP address yet", vm_id))
+        })
+        .map_err(|e| {
+            format!(
+                "Cannot discover IP address of VM [{}] through QEMU guest agent: {}",
+                vm_id, e
+            )
+        })?;
+
+        for node in cluster.nodes.iter_mut().filter(|i| i.vm_id == vm_id) {
+            node.ip_address = ip_address.clone();
+            node.ip6_address = ip6_address.clone().or(node.ip6_address.clone());
+        }
+        let mut cluster_to_update = repo
+            .get_cluster(&cluster.cluster_name)?
+            .ok_or("Cannot find cluster")?;
+        for node in cluster_to_update
+            .nodes
+            .iter_mut()
+            .filter(|i| i.vm_id == vm_id)
+        {
+            node.ip_address = ip_address.clone();
+            node.ip6_address = ip6_address.clone().or(node.ip6_address.clone());
+        }
+        repo.save_cluster(cluster_to_update)?;
+        repo.save_log(LogEntry::info(
+            &cluster.cluster_name,
+            format!("VM [{}] got IP address [{}]", vm_id, ip_address),
+        ))?;
+        Ok(())
+    }
+
     pub fn wait_for_start(
         proxmox_client: &proxmox_client::ClientOperations,
         cluster: &Cluster,
@@ -212,6 +374,9 @@ pub(crate) mod vm {
         })?;
 
         info!("Check cloud-init status for VM [{}]", cluster_node.vm_id);
+        if wait_for_agent(proxmox_client, cluster, cluster_node) {
+            wait_for_cloud_init(proxmox_client, cluster, cluster_node)?;
+        }
         retry(|| {
             let mut ssh_client = ssh_client::Client::new();
             ssh_client.connect(
@@ -225,23 +390,114 @@ pub(crate) mod vm {
         Ok(())
     }
 
+    /// Guest agent only gives better cloud-init errors, readiness is checked over SSH as well.
+    /// VMs of older clusters and imported ones may run without the agent, the agent can also
+    /// be missing on the first start of OS images without it.
+    fn wait_for_agent(
+        proxmox_client: &ClientOperations,
+        cluster: &Cluster,
+        cluster_node: &ClusterNode,
+    ) -> bool {
+        let enabled = proxmox_client
+            .vm_config(&cluster.node, cluster_node.vm_id)
+            .map(|i| agent::is_enabled(&i))
+            .unwrap_or_default();
+        if !enabled {
+            info!("QEMU guest agent of VM [{}] is not enabled", cluster_node.vm_id);
+            return false;
+        }
+        let result = retry_with_opts(
+            || proxmox_client.agent_ping(&cluster.node, cluster_node.vm_id),
+            5,
+            12,
+        );
+        if let Err(e) = &result {
+            info!(
+                "QEMU guest agent of VM [{}] is not available: {}",
+                cluster_node.vm_id, e
+            );
+        }
+        result.is_ok()
+    }
+
+    fn wait_for_cloud_init(
+        proxmox_client: &ClientOperations,
+        cluster: &Cluster,
+        cluster_node: &ClusterNode,
+    ) -> Result<(), String> {
+        let result = agent::execute(
+            proxmox_client,
+            &cluster.node,
+            cluster_node.vm_id,
+            &["cloud-init", "status", "--wait"],
+            Duration::from_secs(CLOUD_INIT_TIMEOUT),
+        );
+        if let Err(e) = result {
+            let log = agent::read_file_tail(
+                proxmox_client,
+                &cluster.node,
+                cluster_node.vm_id,
+                agent::CLOUD_INIT_LOG,
+                20,
+            )
+            .unwrap_or_default();
+            return Err(format!(
+                "cloud-init of VM [{}] failed: {}\n{}",
+                cluster_node.vm_id, e, log
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn wait_for_task(
+        proxmox_client: &ClientOperations,
+        node: &str,
+        upid: &str,
+    ) -> Result<(), String> {
+        wait_for_task_with_opts(proxmox_client, node, upid, 10, 30)
+    }
+
+    pub(crate) fn wait_for_task_with_opts(
+        proxmox_client: &ClientOperations,
+        node: &str,
+        upid: &str,
+        delay: u64,
+        attempts: u64,
+    ) -> Result<(), String> {
+        let exit_status = retry_with_opts(|| {
+            let status = proxmox_client.task_status(node, upid)?;
+            match status.status {
+                TaskState::Running => Err(format!("Task [{}] is still running", upid)),
+                TaskState::Stopped => Ok(status.exit_status.unwrap_or_default()),
+            }
+        }, delay, attempts)?;
+        if exit_status == "OK" {
+            Ok(())
+        } else {
+            Err(format!("Task [{}] failed: [{}]", upid, exit_status))
+        }
+    }
+
     pub(crate) fn get_existing_vms(
         proxmox_client: &ClientOperations,
         cluster: &Cluster,
     ) -> Result<Vec<ClusterNode>, String> {
-        let vms = proxmox_client
-            .virtual_machines(&cluster.node, None)?
-            .into_iter()
-            .map(|i| (i.vm_id.clone(), i.name.unwrap_or_default()))
-            .collect::<Vec<(u32, String)>>();
+        let vms = proxmox_client.virtual_machines(&cluster.node, None)?;
 
         let existing_nodes = cluster
             .nodes
             .clone()
             .into_iter()
             .filter(|e| {
-                let name = format!("{}-{}", cluster.cluster_name, e.name);
-                vms.contains(&(e.vm_id.clone(), name))
+                vms.iter().any(|vm| {
+                    vm.vm_id == e.vm_id
+                        && tags::is_cluster_vm(
+                            cluster,
+                            e,
+                            vm.name.as_deref().unwrap_or_default(),
+                            vm.tags.as_deref(),
+                        )
+                })
             })
             .collect::<Vec<ClusterNode>>();
         Ok(existing_nodes)
@@ -317,33 +573,55 @@ pub(crate) mod vm {
             &cluster.ssh_key.private_key,
             &cluster.ssh_key.public_key,
         )?;
-        ssh_client.execute("sudo systemctl enable iscsid")?;
-        for (host, ip) in hosts.iter() {
-            ssh_client.execute(
-                format!(
-                    "echo '{} {}' | sudo tee -a /etc/cloud/templates/hosts.debian.tmpl",
-                    ip, host
-                )
-                    .as_str(),
-            )?;
-            ssh_client
-                .execute(format!("echo '{} {}' | sudo tee -a /etc/hosts", ip, host).as_str())?;
+        for command in setup_commands(cluster, hosts) {
+            ssh_client.execute(command.as_str())?;
         }
         Ok(())
     }
+
+    /// Prepares the OS of the node and adds `hosts` of the cluster to its hosts files.
+    pub(crate) fn setup_commands(cluster: &Cluster, hosts: &HashMap<String, String>) -> Vec<String> {
+        let mut result = os_image::setup_commands(&cluster.os_family);
+        let ip6_hosts = cluster
+            .nodes
+            .iter()
+            .filter_map(|i| {
+                let host = i.host_name(&cluster.cluster_name);
+                i.ip6_address
+                    .as_ref()
+                    .filter(|ip| network::is_ipv6(ip) && hosts.contains_key(&host))
+                    .map(|ip| (host, ip.trim().to_string()))
+            })
+            .collect::<Vec<(String, String)>>();
+        for (host, ip) in hosts.iter().chain(ip6_hosts.iter().map(|(h, i)| (h, i))) {
+            result.push(format!(
+                "echo '{} {}' | sudo tee -a /etc/cloud/templates/hosts.debian.tmpl",
+                ip, host
+            ));
+            result.push(format!("echo '{} {}' | sudo tee -a /etc/hosts", ip, host));
+        }
+        result
+    }
 }
 
 pub(crate) mod cluster {
-    use serde::{Deserialize, Serialize};
     use std::sync::Arc;
-    use crate::model::{Cluster, ClusterNode, ClusterNodeType, LogEntry};
+    use crate::dispatcher::utils::retry;
+    use crate::model::{Cluster, ClusterNode, LogEntry};
+    use crate::{distribution, node_metadata, node_pool};
     use crate::Repository;
 
+    /// `bootstrap` node starts a new cluster, other nodes join it later.
     pub(crate) fn install_kubernetes(
         repo: Arc<Repository>,
         cluster: &Cluster,
         node: &ClusterNode,
+        bootstrap: bool,
     ) -> Result<(), String> {
+        let commands = distribution::of(cluster).install_commands(cluster, node, bootstrap);
+        if commands.is_empty() {
+            return Ok(());
+        }
         repo.save_log(LogEntry::info(
             &cluster.cluster_name,
             format!("Install Kubernetes on VM [{}]", node.vm_id),
@@ -355,16 +633,9 @@ pub(crate) mod cluster {
             &cluster.ssh_key.private_key,
             &cluster.ssh_key.public_key,
         )?;
-        ssh_client.execute(
-            format!(
-                "sudo snap install microk8s --channel={} --classic",
-                cluster
-                    .kube_version
-                    .clone()
-                    .unwrap_or("1.24/stable".to_owned())
-            )
-                .as_str(),
-        )?;
+        for command in commands {
+            ssh_client.execute(command.as_str())?;
+        }
         Ok(())
     }
 
@@ -384,14 +655,41 @@ pub(crate) mod cluster {
             &cluster.ssh_key.private_key,
             &cluster.ssh_key.public_key,
         )?;
-        ssh_client.execute("sudo microk8s status --wait-ready")?;
+        ssh_client.execute(distribution::of(cluster).wait_ready_command().as_str())?;
+        Ok(())
+    }
+
+    /// Puts labels and taints of the node on the joined node, Kubernetes node can
+    /// appear with a delay after join.
+    pub(crate) fn apply_node_metadata(
+        repo: Arc<Repository>,
+        cluster: &Cluster,
+        master_ssh_client: &ssh_client::Client,
+        node: &ClusterNode,
+    ) -> Result<(), String> {
+        repo.save_log(LogEntry::info(
+            &cluster.cluster_name,
+            format!(
+                "Apply labels and taints to node [{}]",
+                node.host_name(&cluster.cluster_name)
+            ),
+        ))?;
+        for command in node_metadata_commands(cluster, node) {
+            retry(|| master_ssh_client.execute(command.as_str()))?;
+        }
         Ok(())
     }
 
-    #[derive(Serialize, Deserialize)]
-    pub(crate) struct JoinNode {
-        pub(crate) token: String,
-        pub(crate) urls: Vec<String>,
+    /// Commands run on a master node, which put labels and taints on the node.
+    pub(crate) fn node_metadata_commands(cluster: &Cluster, node: &ClusterNode) -> Vec<String> {
+        node_pool::node_metadata_commands(
+            &node.host_name(&cluster.cluster_name),
+            &node_metadata::labels(cluster, node),
+            &node_metadata::taints(cluster, node),
+        )
+        .into_iter()
+        .map(|i| format!("sudo {} {}", distribution::of(cluster).kubectl(), i))
+        .collect()
     }
 
     pub(crate) fn join_node_to_cluster(
@@ -412,15 +710,10 @@ pub(crate) mod cluster {
             &cluster.ssh_key.private_key,
             &cluster.ssh_key.public_key,
         )?;
-        let token_content = master_ssh_client.execute("sudo microk8s add-node --format json")?;
-        let join: JoinNode = serde_json::from_str(&token_content).map_err(|e| e.to_string())?;
-        if join.urls.is_empty() {
-            return Err("Join token doesn't have urls to join node".to_string());
-        }
-        let join = join
-            .urls
-            .first()
-            .ok_or("Cannot get url to join".to_string())?;
+        let distribution = distribution::of(cluster);
+        let token_content = master_ssh_client.execute(distribution.join_token_command().as_str())?;
+        let commands =
+            distribution.join_commands(cluster, &token_content, master_node, node_to_join)?;
         let mut worker_ssh_client = ssh_client::Client::new();
         worker_ssh_client.connect(
             &node_to_join.ip_address,
@@ -428,11 +721,6 @@ pub(crate) mod cluster {
             &cluster.ssh_key.private_key,
             &cluster.ssh_key.public_key,
         )?;
-        let command = format!("sudo microk8s join {}", join);
-        let command = match node_to_join.node_type {
-            ClusterNodeType::Master => command,
-            ClusterNodeType::Worker => format!("{} --worker", command),
-        };
 
         repo.save_log(LogEntry::info(
             &cluster.cluster_name,
@@ -441,45 +729,278 @@ pub(crate) mod cluster {
                 node_to_join.vm_id, node_to_join.node_type
             ),
         ))?;
-        worker_ssh_client.execute(command.as_str())?;
-        // master_ssh_client.execute(
-        //     format!("sudo microk8s.kubectl label node {}-{} node-role.kubernetes.io/{}={}",
-        //             cluster.cluster_name,
-        //             node_to_join.name,
-        //             node_to_join.node_type,
-        //             node_to_join.node_type).as_str())?;
+        for command in commands {
+            worker_ssh_client.execute(command.as_str())?;
+        }
+        apply_node_metadata(repo, cluster, &master_ssh_client, node_to_join)?;
         Ok(())
     }
 }
 
+pub(crate) mod backup {
+    use std::sync::Arc;
+    use proxmox_client::model::{BackupJob, StorageContentType};
+    use proxmox_client::ClientOperations;
+    use crate::backup;
+    use crate::model::{Cluster, LogEntry};
+    use crate::Repository;
+
+    /// Keeps the VM list of the scheduled backup job in line with the cluster nodes.
+    /// Failure is only logged, the node operation itself has already succeeded.
+    pub(crate) fn refresh_backup_job(
+        proxmox_client: &ClientOperations,
+        repo: Arc<Repository>,
+        cluster_name: &str,
+    ) -> Result<(), String> {
+        let cluster = repo
+            .get_cluster(cluster_name)?
+            .ok_or("Cannot find cluster")?;
+        if cluster.backup.is_none() {
+            return Ok(());
+        }
+        if let Err(e) = sync_backup_job(proxmox_client, &cluster) {
+            repo.save_log(LogEntry::error(
+                cluster_name,
+                format!("Cannot update backup job: {}", e),
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Creates, updates or removes the Proxmox backup job of the cluster, so it matches
+    /// the cluster backup settings and the current list of cluster VMs.
+    pub(crate) fn sync_backup_job(
+        proxmox_client: &ClientOperations,
+        cluster: &Cluster,
+    ) -> Result<(), String> {
+        let job_id = backup::backup_job_id(&cluster.cluster_name);
+        let job_exists = proxmox_client
+            .backup_jobs()?
+            .iter()
+            .any(|i| i.id == job_id);
+
+        let settings = match &cluster.backup {
+            Some(v) if !v.schedule.trim().is_empty() => v,
+            _ => {
+                if job_exists {
+                    proxmox_client.delete_backup_job(&job_id)?;
+                }
+                return Ok(());
+            }
+        };
+
+        let storage_supports_backup = proxmox_client
+            .storage(&cluster.node, Some(StorageContentType::Backup))?
+            .iter()
+            .any(|i| i.storage == settings.storage);
+        if !storage_supports_backup {
+            return Err(format!(
+                "Storage [{}] doesn't support backup content",
+                settings.storage
+            ));
+        }
+
+        let job = BackupJob {
+            id: job_id,
+            node: Some(cluster.node.clone()),
+            schedule: settings.schedule.trim().to_string(),
+            vm_id: cluster
+                .nodes
+                .iter()
+                .map(|i| i.vm_id.to_string())
+                .collect::<Vec<String>>()
+                .join(","),
+            storage: settings.storage.clone(),
+            mode: (&settings.mode).into(),
+            prune_backups: Some(format!("keep-last={}", settings.keep_last)),
+            notes_template: Some(backup::scheduled_backup_notes(&cluster.cluster_name)),
+        };
+        if job_exists {
+            proxmox_client.update_backup_job(job)?;
+        } else {
+            proxmox_client.create_backup_job(job)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) mod cloud_init {
+    use proxmox_client::model::{StorageContentType, UploadContent};
+    use proxmox_client::ClientOperations;
+    use crate::cloud_init;
+    use crate::dispatcher::usecase::common::vm::wait_for_task;
+    use crate::model::{Cluster, ClusterNode};
+
+    /// Uploads custom cloud-init documents of the node as snippets and returns value
+    /// of VM `cicustom` option. Existing snippets of the node are replaced.
+    pub(crate) fn upload_snippets(
+        proxmox_client: &ClientOperations,
+        cluster: &Cluster,
+        node: &ClusterNode,
+    ) -> Result<Option<String>, String> {
+        let settings = match cluster.cloud_init.as_ref() {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let storage_supports_snippets = proxmox_client
+            .storage(&cluster.node, Some(StorageContentType::Snippets))?
+            .iter()
+            .any(|i| i.storage == settings.storage);
+        if !storage_supports_snippets {
+            return Err(format!(
+                "Storage [{}] doesn't support snippets content",
+                settings.storage
+            ));
+        }
+
+        upload(
+            proxmox_client,
+            cluster,
+            &cloud_init::user_data_file_name(cluster, node),
+            cloud_init::user_data(cluster, node)?,
+        )?;
+        if let Some(vendor_data) = settings.vendor_data.as_ref() {
+            upload(
+                proxmox_client,
+                cluster,
+                &cloud_init::vendor_data_file_name(cluster),
+                vendor_data.clone(),
+            )?;
+        }
+        Ok(cloud_init::cicustom(cluster, node))
+    }
+
+    /// Removes snippets of the given nodes, vendor-data is removed with the last node.
+    /// Missing snippets are ignored.
+    pub(crate) fn delete_snippets(
+        proxmox_client: &ClientOperations,
+        cluster: &Cluster,
+        nodes: &[ClusterNode],
+        include_vendor_data: bool,
+    ) -> Result<(), String> {
+        let settings = match cluster.cloud_init.as_ref() {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let file_names = snippet_file_names(cluster, nodes, include_vendor_data);
+        let existing = proxmox_client
+            .storage_content(&cluster.node, &settings.storage)?
+            .into_iter()
+            .map(|i| i.volid)
+            .collect::<Vec<String>>();
+        for file_name in file_names {
+            let volid = cloud_init::volume_id(&settings.storage, &file_name);
+            if existing.contains(&volid) {
+                proxmox_client.delete_storage_content(&cluster.node, &settings.storage, &volid)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn snippet_file_names(
+        cluster: &Cluster,
+        nodes: &[ClusterNode],
+        include_vendor_data: bool,
+    ) -> Vec<String> {
+        let mut file_names = nodes
+            .iter()
+            .map(|i| cloud_init::user_data_file_name(cluster, i))
+            .collect::<Vec<String>>();
+        let vendor_data = cluster
+            .cloud_init
+            .as_ref()
+            .is_some_and(|i| i.vendor_data.is_some());
+        if include_vendor_data && vendor_data {
+            file_names.push(cloud_init::vendor_data_file_name(cluster));
+        }
+        file_names
+    }
+
+    fn upload(
+        proxmox_client: &ClientOperations,
+        cluster: &Cluster,
+        file_name: &str,
+        content: String,
+    ) -> Result<(), String> {
+        let settings = cluster.cloud_init.as_ref().ok_or("Cluster has no cloud-init settings")?;
+        let volid = cloud_init::volume_id(&settings.storage, file_name);
+        let exists = proxmox_client
+            .storage_content(&cluster.node, &settings.storage)?
+            .iter()
+            .any(|i| i.volid == volid);
+        if exists {
+            proxmox_client.delete_storage_content(&cluster.node, &settings.storage, &volid)?;
+        }
+        let upid = proxmox_client
+            .upload_content(UploadContent {
+                node: cluster.node.clone(),
+                storage: settings.storage.clone(),
+                content: StorageContentType::Snippets,
+                filename: file_name.to_string(),
+                data: content.into_bytes(),
+            })
+            .map_err(|e| format!("Cannot upload cloud-init snippet [{}]: {}", file_name, e))?;
+        wait_for_task(proxmox_client, &cluster.node, &upid)
+    }
+}
+
 pub(crate) mod apps {
     use crate::model::{ClusterResource, HelmApp};
 
-    pub const HELM_CMD: &str = "microk8s.helm3";
+    /// Step run on a cluster node over SSH.
+    pub(crate) enum RemoteStep {
+        Upload { path: String, content: String },
+        Execute(String),
+    }
+
+    fn run(ssh_client: &ssh_client::Client, steps: Vec<RemoteStep>) -> Result<(), String> {
+        for step in steps {
+            match step {
+                RemoteStep::Upload { path, content } => {
+                    ssh_client.upload_file(path.as_str(), &content)?
+                }
+                RemoteStep::Execute(command) => {
+                    ssh_client.execute(command.as_str())?;
+                }
+            }
+        }
+        Ok(())
+    }
 
+    /// `helm` is the Helm binary of the cluster distribution.
+    pub fn install_helm_app(
+        ssh_client: &ssh_client::Client,
+        helm: &str,
+        app: &HelmApp,
+    ) -> Result<(), String> {
+        run(ssh_client, helm_app_steps(helm, app))
+    }
 
-    pub fn install_helm_app(ssh_client: &ssh_client::Client, app: &HelmApp) -> Result<(), String> {
+    pub(crate) fn helm_app_steps(helm: &str, app: &HelmApp) -> Vec<RemoteStep> {
+        let mut result = vec![];
         let values_file_name = app.release_name.trim().replace(" ", "_");
 
         if !app.repository.is_empty() {
-            ssh_client.execute(
-                &helm_client::new(HELM_CMD)
+            result.push(RemoteStep::Execute(
+                helm_client::new(helm)
                     .sudo()
                     .repo()
                     .add(&app.chart_name, &app.repository)
                     .build(),
-            )?;
-            ssh_client.execute(&helm_client::new(HELM_CMD).sudo().repo().update().build())?;
+            ));
+            result.push(RemoteStep::Execute(
+                helm_client::new(helm).sudo().repo().update().build(),
+            ));
         }
 
         if !app.values.is_empty() {
-            ssh_client.upload_file(
-                format!("/tmp/{}.yaml", values_file_name).as_str(),
-                &app.values,
-            )?;
+            result.push(RemoteStep::Upload {
+                path: format!("/tmp/{}.yaml", values_file_name),
+                content: app.values.clone(),
+            });
         }
 
-        let mut command_builder = helm_client::new(HELM_CMD)
+        let mut command_builder = helm_client::new(helm)
             .sudo()
             .upgrade_or_install()
             .create_namespace()
@@ -502,29 +1023,355 @@ pub(crate) mod apps {
         if app.wait {
             command_builder = command_builder.wait();
         }
-        ssh_client.execute(&command_builder.build())?;
+        result.push(RemoteStep::Execute(command_builder.build()));
 
         if !app.values.is_empty() {
-            ssh_client.execute(format!("sudo rm /tmp/{}.yaml", values_file_name).as_str())?;
+            result.push(RemoteStep::Execute(format!(
+                "sudo rm /tmp/{}.yaml",
+                values_file_name
+            )));
         }
-        Ok(())
+        result
     }
 
     pub fn install_cluster_resource(
         ssh_client: &ssh_client::Client,
+        kubectl: &str,
         resource: &ClusterResource,
     ) -> Result<(), String> {
+        run(ssh_client, cluster_resource_steps(kubectl, resource))
+    }
+
+    pub(crate) fn cluster_resource_steps(
+        kubectl: &str,
+        resource: &ClusterResource,
+    ) -> Vec<RemoteStep> {
         let file_name = format!(
             "{}_cluster_resource",
             resource.name.trim().replace(" ", "_")
         );
-        ssh_client.upload_file(
-            format!("/tmp/{}.yaml", file_name).as_str(),
-            resource.content.as_str(),
-        )?;
-        ssh_client
-            .execute(format!("sudo microk8s.kubectl apply -f /tmp/{}.yaml", file_name).as_str())?;
-        ssh_client.execute(format!("sudo rm /tmp/{}.yaml", file_name).as_str())?;
-        Ok(())
+        vec![
+            RemoteStep::Upload {
+                path: format!("/tmp/{}.yaml", file_name),
+                content: resource.content.clone(),
+            },
+            RemoteStep::Execute(format!("sudo {} apply -f /tmp/{}.yaml", kubectl, file_name)),
+            RemoteStep::Execute(format!("sudo rm /tmp/{}.yaml", file_name)),
+        ]
+    }
+}
+
+pub(crate) mod plan {
+    use std::collections::HashMap;
+    use crate::cloud_init;
+    use crate::dispatcher::usecase::common::apps::RemoteStep;
+    use crate::dispatcher::usecase::common::{cluster, vm};
+    use crate::distribution;
+    use crate::model::{Cluster, ClusterNode, PlanAction, PlanActionType};
+
+    const HIDDEN: &str = "********";
+
+    pub(crate) fn host_name(cluster: &Cluster, node: &ClusterNode) -> String {
+        node.host_name(&cluster.cluster_name)
+    }
+
+    pub(crate) fn proxmox(cluster: &Cluster, description: String, details: Option<String>) -> PlanAction {
+        PlanAction {
+            action_type: PlanActionType::ProxmoxApi,
+            target: cluster.node.clone(),
+            description,
+            details,
+        }
+    }
+
+    pub(crate) fn ssh(cluster: &Cluster, node: &ClusterNode, description: &str, command: String) -> PlanAction {
+        PlanAction {
+            action_type: PlanActionType::SshCommand,
+            target: host_name(cluster, node),
+            description: description.to_string(),
+            details: Some(command),
+        }
+    }
+
+    pub(crate) fn wait(target: String, description: String) -> PlanAction {
+        PlanAction {
+            action_type: PlanActionType::Wait,
+            target,
+            description,
+            details: None,
+        }
+    }
+
+    pub(crate) fn remote_steps(
+        cluster: &Cluster,
+        node: &ClusterNode,
+        description: &str,
+        steps: Vec<RemoteStep>,
+    ) -> Vec<PlanAction> {
+        steps
+            .into_iter()
+            .map(|i| match i {
+                RemoteStep::Upload { path, content } => PlanAction {
+                    action_type: PlanActionType::UploadFile,
+                    target: host_name(cluster, node),
+                    description: format!("{}: upload [{}]", description, path),
+                    details: Some(content),
+                },
+                RemoteStep::Execute(command) => ssh(cluster, node, description, command),
+            })
+            .collect()
+    }
+
+    /// Custom cloud-init documents may repeat the node password.
+    fn hide_password(cluster: &Cluster, content: String) -> String {
+        if cluster.node_password.is_empty() {
+            return content;
+        }
+        content.replace(&cluster.node_password, HIDDEN)
+    }
+
+    /// Addresses of DHCP nodes are known after their VMs start, the plan shows a placeholder.
+    pub(crate) fn with_dhcp_placeholders(mut cluster: Cluster) -> Cluster {
+        let network = cluster.network.clone();
+        for node in cluster
+            .nodes
+            .iter_mut()
+            .filter(|i| network.is_dhcp_node(i) && i.ip_address.trim().is_empty())
+        {
+            node.ip_address = format!("<DHCP address of VM {}>", node.vm_id);
+        }
+        cluster
+    }
+
+    /// Same steps as [vm::create], the OS image is imported from its volume, which
+    /// resolves to the file path during the run.
+    pub(crate) fn create_vm(cluster: &Cluster, node: &ClusterNode) -> Result<Vec<PlanAction>, String> {
+        let mut result = vec![];
+        if let Some(pool) = cluster.resource_pool.as_ref() {
+            result.push(proxmox(
+                cluster,
+                format!("Create Proxmox pool [{}] unless it exists", pool),
+                None,
+            ));
+        }
+        let (os_image, storage, file_name) = vm::os_image_source(cluster)?;
+        let volid = vm::os_image_volume_id(&storage, &file_name);
+        result.push(proxmox(
+            cluster,
+            format!(
+                "Download OS image to [{}] unless it exists and is up to date",
+                volid
+            ),
+            Some(os_image),
+        ));
+        if let Some(settings) = cluster.cloud_init.as_ref() {
+            result.push(proxmox(
+                cluster,
+                format!(
+                    "Upload cloud-init snippet [{}]",
+                    cloud_init::volume_id(
+                        &settings.storage,
+                        &cloud_init::user_data_file_name(cluster, node)
+                    )
+                ),
+                Some(hide_password(
+                    cluster,
+                    cloud_init::masked_user_data(cluster, node, HIDDEN),
+                )),
+            ));
+            if let Some(vendor_data) = settings.vendor_data.as_ref() {
+                result.push(proxmox(
+                    cluster,
+                    format!(
+                        "Upload cloud-init snippet [{}]",
+                        cloud_init::volume_id(
+                            &settings.storage,
+                            &cloud_init::vendor_data_file_name(cluster)
+                        )
+                    ),
+                    Some(hide_password(cluster, vendor_data.clone())),
+                ));
+            }
+        }
+        let mut request =
+            vm::create_request(cluster, node, &volid, cloud_init::cicustom(cluster, node))?;
+        if request.ci_password.as_ref().is_some_and(|i| !i.is_empty()) {
+            request.ci_password = Some(HIDDEN.to_string());
+        }
+        let config = serde_json::to_value(&request)
+            .and_then(|i| serde_json::to_string_pretty(&i))
+            .map_err(|e| e.to_string())?;
+        result.push(proxmox(
+            cluster,
+            format!("Create VM [{}] [{}]", node.vm_id, host_name(cluster, node)),
+            Some(config),
+        ));
+        result.push(proxmox(
+            cluster,
+            format!(
+                "Resize disk scsi0 of VM [{}] to [{}]",
+                node.vm_id,
+                vm::disk_size(cluster, node)
+            ),
+            None,
+        ));
+        Ok(result)
+    }
+
+    pub(crate) fn start_vm(cluster: &Cluster, node: &ClusterNode) -> PlanAction {
+        proxmox(cluster, format!("Start VM [{}]", node.vm_id), None)
+    }
+
+    pub(crate) fn discover_ip_address(cluster: &Cluster, node: &ClusterNode) -> Option<PlanAction> {
+        cluster.network.is_dhcp_node(node).then(|| {
+            wait(
+                cluster.node.clone(),
+                format!("Read IP address of VM [{}] from QEMU guest agent", node.vm_id),
+            )
+        })
+    }
+
+    pub(crate) fn wait_for_start(cluster: &Cluster, node: &ClusterNode) -> PlanAction {
+        wait(
+            host_name(cluster, node),
+            format!("Wait for VM [{}] start and cloud-init", node.vm_id),
+        )
+    }
+
+    pub(crate) fn restart_vm_if_necessary(cluster: &Cluster, node: &ClusterNode) -> PlanAction {
+        wait(
+            host_name(cluster, node),
+            format!(
+                "Restart VM [{}] when /var/run/reboot-required exists",
+                node.vm_id
+            ),
+        )
+    }
+
+    pub(crate) fn setup_vm(
+        cluster: &Cluster,
+        node: &ClusterNode,
+        description: &str,
+        hosts: &HashMap<String, String>,
+    ) -> Vec<PlanAction> {
+        vm::setup_commands(cluster, hosts)
+            .into_iter()
+            .map(|i| ssh(cluster, node, description, i))
+            .collect()
+    }
+
+    pub(crate) fn install_kubernetes(
+        cluster: &Cluster,
+        node: &ClusterNode,
+        bootstrap: bool,
+    ) -> Vec<PlanAction> {
+        distribution::of(cluster)
+            .install_commands(cluster, node, bootstrap)
+            .into_iter()
+            .map(|i| ssh(cluster, node, "Install Kubernetes", i))
+            .collect()
+    }
+
+    pub(crate) fn wait_for_ready_kubernetes(cluster: &Cluster, node: &ClusterNode) -> PlanAction {
+        ssh(
+            cluster,
+            node,
+            "Wait for Kubernetes",
+            distribution::of(cluster).wait_ready_command(),
+        )
+    }
+
+    pub(crate) fn apply_node_metadata(
+        cluster: &Cluster,
+        master: &ClusterNode,
+        node: &ClusterNode,
+    ) -> Vec<PlanAction> {
+        let description = format!("Apply labels and taints to node [{}]", host_name(cluster, node));
+        cluster::node_metadata_commands(cluster, node)
+            .into_iter()
+            .map(|i| ssh(cluster, master, &description, i))
+            .collect()
+    }
+
+    /// Same steps as [cluster::join_node_to_cluster], the join token is a placeholder.
+    pub(crate) fn join_node(
+        cluster: &Cluster,
+        master: &ClusterNode,
+        node: &ClusterNode,
+    ) -> Result<Vec<PlanAction>, String> {
+        let distribution = distribution::of(cluster);
+        let mut result = vec![ssh(
+            cluster,
+            master,
+            "Generate join token",
+            distribution.join_token_command(),
+        )];
+        let description = format!("Join node with role [{}] to cluster", node.node_type);
+        for command in distribution.join_commands(
+            cluster,
+            &distribution.join_token_placeholder(master),
+            master,
+            node,
+        )? {
+            result.push(ssh(cluster, node, &description, command));
+        }
+        result.extend(apply_node_metadata(cluster, master, node));
+        Ok(result)
+    }
+
+    pub(crate) fn shutdown_vm(cluster: &Cluster, node: &ClusterNode) -> PlanAction {
+        proxmox(cluster, format!("Shutdown VM [{}]", node.vm_id), None)
+    }
+
+    pub(crate) fn wait_for_shutdown(cluster: &Cluster, node: &ClusterNode) -> PlanAction {
+        wait(
+            cluster.node.clone(),
+            format!(
+                "Wait for VM [{}] shutdown, stop it when it doesn't shut down gracefully",
+                node.vm_id
+            ),
+        )
+    }
+
+    pub(crate) fn delete_vm(cluster: &Cluster, node: &ClusterNode) -> PlanAction {
+        proxmox(cluster, format!("Delete VM [{}]", node.vm_id), None)
+    }
+
+    /// Same snippets as [super::cloud_init::delete_snippets] removes.
+    pub(crate) fn delete_snippets(
+        cluster: &Cluster,
+        nodes: &[ClusterNode],
+        include_vendor_data: bool,
+    ) -> Vec<PlanAction> {
+        let settings = match cluster.cloud_init.as_ref() {
+            Some(v) => v,
+            None => return vec![],
+        };
+        super::cloud_init::snippet_file_names(cluster, nodes, include_vendor_data)
+            .iter()
+            .map(|i| {
+                proxmox(
+                    cluster,
+                    format!(
+                        "Delete cloud-init snippet [{}]",
+                        cloud_init::volume_id(&settings.storage, i)
+                    ),
+                    None,
+                )
+            })
+            .collect()
+    }
+
+    pub(crate) fn refresh_backup_job(cluster: &Cluster) -> Option<PlanAction> {
+        cluster.backup.as_ref().map(|_| {
+            proxmox(
+                cluster,
+                format!(
+                    "Update VMs of backup job [{}]",
+                    crate::backup::backup_job_id(&cluster.cluster_name)
+                ),
+                None,
+            )
+        })
     }
 }
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+use log::info;
+
+use proxmox_client::model::AccessData;
+use proxmox_client::Client;
+use crate::dispatcher::usecase::common;
+use crate::model::LogEntry;
+use crate::Repository;
+
+
+pub(crate) fn execute(
+    proxmox_client: Arc<Client>,
+    repo: Arc<Repository>,
+    access: AccessData,
+    cluster_name: String,
+) -> Result<(), String> {
+    info!("Request to configure the cluster backup has been received");
+    let proxmox_client = proxmox_client.operations(access);
+
+    let cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+
+    common::backup::sync_backup_job(&proxmox_client, &cluster)?;
+    repo.save_log(LogEntry::info(
+        &cluster_name,
+        "Backup job has been synchronized with Proxmox".to_string(),
+    ))?;
+    Ok(())
+}
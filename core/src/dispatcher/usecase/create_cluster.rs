@@ -2,11 +2,11 @@ use log::info;
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use openssl::rsa::Rsa;
-use pem::{encode, Pem};
-
 use crate::dispatcher::usecase::common;
-use crate::model::{Cluster, ClusterNode, ClusterNodeType, KeyPair, LogEntry};
+use crate::{addons, distribution, keys};
+use crate::model::{
+    Cluster, ClusterNode, ClusterNodeType, Distribution, ExecutionPlan, LogEntry, PlanAction,
+};
 use crate::Repository;
 use proxmox_client::model::AccessData;
 use proxmox_client::{Client, ClientOperations};
@@ -28,25 +28,194 @@ pub(crate) fn execute(
     let mut cluster = repo
         .get_cluster(&cluster_name)?
         .ok_or("Cannot find cluster")?;
-    let keys = generate_ssh_keys()?;
-    cluster.ssh_key = keys;
+    if cluster.ssh_key.private_key.trim().is_empty() {
+        cluster.ssh_key = keys::generate_ssh_keys(&cluster.ssh_key_type)?;
+    }
     repo.save_cluster(cluster.clone())?;
 
-    create_vms(&proxmox_client, &cluster, repo.clone())?;
-    start_vms(&proxmox_client, &cluster, repo.clone())?;
-    wait_for_vms_start(&proxmox_client, &cluster, repo.clone())?;
-    restart_vms_if_necessary(&proxmox_client, &cluster, repo.clone())?;
-    setup_vms(repo.clone(), &cluster)?;
-    install_kubernetes(repo.clone(), &cluster)?;
-    wait_for_ready_kubernetes(repo.clone(), &cluster)?;
-    join_nodes_to_cluster(repo.clone(), &cluster)?;
-    add_kubeconfig_to_project(repo.clone(), &mut cluster)?;
-    enable_microk8s_addons(repo.clone(), &cluster)?;
-    install_helm_apps(repo.clone(), &cluster)?;
-    install_cluster_resources(repo.clone(), &cluster)?;
+    for step in steps(&cluster) {
+        match step {
+            Step::CreateVms => create_vms(&proxmox_client, &cluster, repo.clone())?,
+            Step::StartVms => start_vms(&proxmox_client, &cluster, repo.clone())?,
+            Step::DiscoverIpAddresses => {
+                discover_ip_addresses(&proxmox_client, &mut cluster, repo.clone())?
+            }
+            Step::WaitForVmsStart => wait_for_vms_start(&proxmox_client, &cluster, repo.clone())?,
+            Step::RestartVmsIfNecessary => {
+                restart_vms_if_necessary(&proxmox_client, &cluster, repo.clone())?
+            }
+            Step::SetupVms => setup_vms(repo.clone(), &cluster)?,
+            Step::InstallKubernetes => install_kubernetes(repo.clone(), &cluster)?,
+            Step::WaitForReadyKubernetes => wait_for_ready_kubernetes(repo.clone(), &cluster)?,
+            Step::JoinNodesToCluster => join_nodes_to_cluster(repo.clone(), &cluster)?,
+            Step::AddKubeconfigToProject => add_kubeconfig_to_project(repo.clone(), &mut cluster)?,
+            Step::EnableMicroK8sAddons => enable_microk8s_addons(repo.clone(), &cluster)?,
+            Step::InstallHelmApps => install_helm_apps(repo.clone(), &cluster)?,
+            Step::InstallClusterResources => install_cluster_resources(repo.clone(), &cluster)?,
+        }
+    }
     Ok(())
 }
 
+/// Steps of the cluster creation, [execute] runs them and [plan] describes them.
+enum Step {
+    CreateVms,
+    StartVms,
+    DiscoverIpAddresses,
+    WaitForVmsStart,
+    RestartVmsIfNecessary,
+    SetupVms,
+    InstallKubernetes,
+    WaitForReadyKubernetes,
+    JoinNodesToCluster,
+    AddKubeconfigToProject,
+    EnableMicroK8sAddons,
+    InstallHelmApps,
+    InstallClusterResources,
+}
+
+fn steps(cluster: &Cluster) -> Vec<Step> {
+    let mut steps = vec![
+        Step::CreateVms,
+        Step::StartVms,
+        Step::DiscoverIpAddresses,
+        Step::WaitForVmsStart,
+        Step::RestartVmsIfNecessary,
+        Step::SetupVms,
+        Step::InstallKubernetes,
+        Step::WaitForReadyKubernetes,
+        Step::JoinNodesToCluster,
+        Step::AddKubeconfigToProject,
+    ];
+    if cluster.distribution == Distribution::MicroK8s {
+        steps.push(Step::EnableMicroK8sAddons);
+    }
+    steps.push(Step::InstallHelmApps);
+    steps.push(Step::InstallClusterResources);
+    steps
+}
+
+/// Actions of [execute] in the same order, nothing is run.
+pub(crate) fn plan(cluster: &Cluster) -> Result<ExecutionPlan, String> {
+    let cluster = &common::plan::with_dhcp_placeholders(cluster.clone());
+    let distribution = distribution::of(cluster);
+    let master_node = cluster
+        .nodes
+        .iter()
+        .find(|i| i.node_type == ClusterNodeType::Master)
+        .ok_or("Cannot find any master node".to_string())?;
+    let mut actions: Vec<PlanAction> = vec![];
+    for step in steps(cluster) {
+        match step {
+            Step::CreateVms => {
+                for node in cluster.nodes.iter() {
+                    actions.extend(common::plan::create_vm(cluster, node)?);
+                }
+            }
+            Step::StartVms => {
+                for node in cluster.nodes.iter() {
+                    actions.push(common::plan::start_vm(cluster, node));
+                }
+            }
+            Step::DiscoverIpAddresses => {
+                for node in cluster.nodes.iter() {
+                    actions.extend(common::plan::discover_ip_address(cluster, node));
+                }
+            }
+            Step::WaitForVmsStart => {
+                for node in cluster.nodes.iter() {
+                    actions.push(common::plan::wait_for_start(cluster, node));
+                }
+            }
+            Step::RestartVmsIfNecessary => {
+                for node in cluster.nodes.iter() {
+                    actions.push(common::plan::restart_vm_if_necessary(cluster, node));
+                }
+            }
+            Step::SetupVms => {
+                let hosts = cluster
+                    .nodes
+                    .iter()
+                    .map(|i| (common::plan::host_name(cluster, i), i.ip_address.clone()))
+                    .collect::<HashMap<String, String>>();
+                for node in cluster.nodes.iter() {
+                    actions.extend(common::plan::setup_vm(cluster, node, "Configure VM", &hosts));
+                }
+            }
+            Step::InstallKubernetes => {
+                for node in cluster.nodes.iter() {
+                    actions.extend(common::plan::install_kubernetes(
+                        cluster,
+                        node,
+                        is_bootstrap(cluster, node),
+                    ));
+                }
+            }
+            Step::WaitForReadyKubernetes => {
+                for node in cluster.nodes.iter() {
+                    if distribution.starts_before_join() || is_bootstrap(cluster, node) {
+                        actions.push(common::plan::wait_for_ready_kubernetes(cluster, node));
+                    }
+                }
+            }
+            Step::JoinNodesToCluster => {
+                actions.extend(common::plan::apply_node_metadata(cluster, master_node, master_node));
+                for node in cluster.nodes.iter().filter(|i| i.vm_id != master_node.vm_id) {
+                    actions.extend(common::plan::join_node(cluster, master_node, node)?);
+                }
+            }
+            Step::AddKubeconfigToProject => {
+                let first_master_node = cluster
+                    .nodes
+                    .iter()
+                    .filter(|i| i.node_type == ClusterNodeType::Master)
+                    .min_by_key(|i| i.vm_id)
+                    .ok_or("Cannot get first master node".to_string())?;
+                actions.push(common::plan::ssh(
+                    cluster,
+                    first_master_node,
+                    "Read kube config",
+                    distribution.kubeconfig_command(),
+                ));
+            }
+            Step::EnableMicroK8sAddons => {
+                for addon in addons::with_required(&cluster.addons).iter() {
+                    actions.push(common::plan::ssh(
+                        cluster,
+                        master_node,
+                        &format!("Enable MicroK8s addon [{}]", addon.name),
+                        addons::enable_command(addon),
+                    ));
+                }
+            }
+            Step::InstallHelmApps => {
+                for app in cluster.helm_apps.iter() {
+                    actions.extend(common::plan::remote_steps(
+                        cluster,
+                        master_node,
+                        &format!("Install Helm release [{}]", app.release_name),
+                        common::apps::helm_app_steps(distribution.helm(), app),
+                    ));
+                }
+            }
+            Step::InstallClusterResources => {
+                for resource in cluster.cluster_resources.iter() {
+                    actions.extend(common::plan::remote_steps(
+                        cluster,
+                        master_node,
+                        &format!("Apply cluster resource [{}]", resource.name),
+                        common::apps::cluster_resource_steps(distribution.kubectl(), resource),
+                    ));
+                }
+            }
+        }
+    }
+    Ok(ExecutionPlan {
+        operation: format!("Create cluster [{}]", cluster.cluster_name),
+        actions,
+    })
+}
+
 fn install_cluster_resources(repo: Arc<Repository>, cluster: &Cluster) -> Result<(), String> {
     repo.save_log(LogEntry::info(
         &cluster.cluster_name,
@@ -73,7 +242,11 @@ fn install_cluster_resources(repo: Arc<Repository>, cluster: &Cluster) -> Result
             &cluster.cluster_name,
             format!("Apply cluster resource: [{}]", resource.name),
         ))?;
-        common::apps::install_cluster_resource(&ssh_client, resource)?;
+        common::apps::install_cluster_resource(
+            &ssh_client,
+            distribution::of(cluster).kubectl(),
+            resource,
+        )?;
     }
     Ok(())
 }
@@ -104,15 +277,23 @@ fn install_helm_apps(repo: Arc<Repository>, cluster: &Cluster) -> Result<(), Str
             &cluster.cluster_name,
             format!("Install Helm app: [{}]", app.release_name),
         ))?;
-        common::apps::install_helm_app(&ssh_client, app)?;
+        common::apps::install_helm_app(&ssh_client, distribution::of(cluster).helm(), app)?;
     }
     Ok(())
 }
 
 fn enable_microk8s_addons(repo: Arc<Repository>, cluster: &Cluster) -> Result<(), String> {
+    let addons = addons::with_required(&cluster.addons);
     repo.save_log(LogEntry::info(
         &cluster.cluster_name,
-        "Enable MicroK8s addons: [dns, helm3]".to_string(),
+        format!(
+            "Enable MicroK8s addons: [{}]",
+            addons
+                .iter()
+                .map(|i| i.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        ),
     ))?;
     let master_node = cluster
         .nodes
@@ -128,8 +309,9 @@ fn enable_microk8s_addons(repo: Arc<Repository>, cluster: &Cluster) -> Result<()
         &cluster.ssh_key.private_key,
         &cluster.ssh_key.public_key,
     )?;
-    ssh_client.execute("sudo microk8s enable dns")?;
-    ssh_client.execute("sudo microk8s enable helm3")?;
+    for addon in addons.iter() {
+        ssh_client.execute(&addons::enable_command(addon))?;
+    }
     Ok(())
 }
 
@@ -155,7 +337,11 @@ fn add_kubeconfig_to_project(repo: Arc<Repository>, cluster: &mut Cluster) -> Re
         &cluster.ssh_key.private_key,
         &cluster.ssh_key.public_key,
     )?;
-    let kube_config_content = ssh_client.execute("sudo microk8s config")?;
+    let distribution = distribution::of(cluster);
+    let kube_config_content = distribution.kubeconfig(
+        &ssh_client.execute(distribution.kubeconfig_command().as_str())?,
+        first_master_node,
+    );
     cluster.cluster_config = kube_config_content.clone();
     let mut cluster_to_update = repo
         .get_cluster(&cluster.cluster_name)?
@@ -166,10 +352,6 @@ fn add_kubeconfig_to_project(repo: Arc<Repository>, cluster: &mut Cluster) -> Re
 }
 
 fn join_nodes_to_cluster(repo: Arc<Repository>, cluster: &Cluster) -> Result<(), String> {
-    if cluster.nodes.len() == 1 {
-        return Ok(());
-    }
-
     let master_node = cluster
         .nodes
         .iter()
@@ -177,6 +359,15 @@ fn join_nodes_to_cluster(repo: Arc<Repository>, cluster: &Cluster) -> Result<(),
         .cloned()
         .ok_or("Cannot find any master node".to_string())?;
 
+    let mut master_ssh_client = ssh_client::Client::new();
+    master_ssh_client.connect(
+        &master_node.ip_address,
+        &cluster.node_username,
+        &cluster.ssh_key.private_key,
+        &cluster.ssh_key.public_key,
+    )?;
+    common::cluster::apply_node_metadata(repo.clone(), cluster, &master_ssh_client, &master_node)?;
+
     let nodes_to_join = cluster
         .nodes
         .clone()
@@ -190,10 +381,24 @@ fn join_nodes_to_cluster(repo: Arc<Repository>, cluster: &Cluster) -> Result<(),
     Ok(())
 }
 
+/// The first master node starts the cluster, other nodes join it.
+fn is_bootstrap(cluster: &Cluster, node: &ClusterNode) -> bool {
+    cluster
+        .nodes
+        .iter()
+        .find(|i| i.node_type == ClusterNodeType::Master)
+        .is_some_and(|i| i.vm_id == node.vm_id)
+}
+
 pub(crate) fn install_kubernetes(repo: Arc<Repository>, cluster: &Cluster) -> Result<(), String> {
     info!("Install Kubernetes");
     for node in cluster.nodes.iter() {
-        common::cluster::install_kubernetes(repo.clone(), cluster, node)?;
+        common::cluster::install_kubernetes(
+            repo.clone(),
+            cluster,
+            node,
+            is_bootstrap(cluster, node),
+        )?;
     }
     Ok(())
 }
@@ -202,8 +407,11 @@ pub(crate) fn wait_for_ready_kubernetes(
     repo: Arc<Repository>,
     cluster: &Cluster,
 ) -> Result<(), String> {
+    let starts_before_join = distribution::of(cluster).starts_before_join();
     for node in cluster.nodes.iter() {
-        common::cluster::wait_for_ready_kubernetes(repo.clone(), cluster, node)?;
+        if starts_before_join || is_bootstrap(cluster, node) {
+            common::cluster::wait_for_ready_kubernetes(repo.clone(), cluster, node)?;
+        }
     }
     Ok(())
 }
@@ -220,6 +428,18 @@ pub(crate) fn restart_vms_if_necessary(
     Ok(())
 }
 
+fn discover_ip_addresses(
+    proxmox_client: &ClientOperations,
+    cluster: &mut Cluster,
+    repo: Arc<Repository>,
+) -> Result<(), String> {
+    let vm_ids = cluster.nodes.iter().map(|i| i.vm_id).collect::<Vec<u32>>();
+    for vm_id in vm_ids {
+        common::vm::discover_ip_address(proxmox_client, repo.clone(), cluster, vm_id)?;
+    }
+    Ok(())
+}
+
 pub(crate) fn wait_for_vms_start(
     proxmox_client: &ClientOperations,
     cluster: &Cluster,
@@ -244,7 +464,7 @@ fn setup_vms(repo: Arc<Repository>, cluster: &Cluster) -> Result<(), String> {
         .iter()
         .map(|i| {
             (
-                format!("{}-{}", cluster.cluster_name, i.name),
+                i.host_name(&cluster.cluster_name),
                 i.ip_address.clone(),
             )
         })
@@ -260,24 +480,6 @@ fn setup_vms(repo: Arc<Repository>, cluster: &Cluster) -> Result<(), String> {
     Ok(())
 }
 
-fn generate_ssh_keys() -> Result<KeyPair, String> {
-    info!("Generate SSH keys");
-    let rsa = Rsa::generate(4096).map_err(|e| e.to_string())?;
-    let private_key = rsa.private_key_to_der().map_err(|e| e.to_string())?;
-    let private_key = Pem::new(String::from("RSA PRIVATE KEY"), private_key);
-    let private_key: String = encode(&private_key);
-
-    let ssh_private_key =
-        ssh_keys::openssh::parse_private_key(private_key.as_str()).map_err(|e| e.to_string())?;
-    let ssh_private_key = ssh_private_key.get(0).ok_or("Cannot parse private key")?;
-    let ssh_public_key = ssh_private_key.public_key();
-    info!("SSH keys has been generated");
-    Ok(KeyPair {
-        public_key: ssh_public_key.to_string(),
-        private_key,
-    })
-}
-
 pub(crate) fn create_vms(
     proxmox_client: &ClientOperations,
     cluster: &Cluster,
@@ -330,3 +532,94 @@ pub(crate) fn start_vms(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dispatcher::usecase::create_cluster::plan;
+    use crate::model::{CloudInitSettings, Cluster, ClusterNodeType, HelmApp, Network, PlanActionType};
+    use crate::test_fixtures::node;
+
+    #[test]
+    fn plan_follows_creation_order_and_hides_password() {
+        let cluster = Cluster {
+            node: "pve".to_string(),
+            cluster_name: "c1".to_string(),
+            node_username: "ubuntu".to_string(),
+            node_password: "secret".to_string(),
+            disk_size: 20,
+            network: Network {
+                gateway: "10.0.0.1".to_string(),
+                subnet_mask: 24,
+                bridge: "vmbr0".to_string(),
+                ..Default::default()
+            },
+            nodes: vec![
+                node("master-1", 101, "10.0.0.11", ClusterNodeType::Master),
+                node("worker-1", 102, "10.0.0.12", ClusterNodeType::Worker),
+            ],
+            helm_apps: vec![HelmApp {
+                id: "1".to_string(),
+                chart_name: "ingress-nginx".to_string(),
+                chart_version: String::new(),
+                repository: String::new(),
+                release_name: "ingress".to_string(),
+                namespace: "ingress".to_string(),
+                values: String::new(),
+                wait: false,
+            }],
+            ..Default::default()
+        };
+        let actions = plan(&cluster).unwrap().actions;
+        let index = |description: &str| {
+            actions
+                .iter()
+                .position(|i| i.description.starts_with(description))
+                .unwrap()
+        };
+        assert!(index("Create VM [102]") < index("Start VM [101]"));
+        assert!(index("Install Kubernetes") < index("Join node"));
+        assert!(index("Join node") < index("Install Helm release [ingress]"));
+
+        let config = actions[index("Create VM [101]")].details.clone().unwrap();
+        assert!(config.contains("\"cipassword\": \"********\""));
+        assert!(!config.contains("secret"));
+
+        let join = &actions[index("Join node")];
+        assert_eq!(PlanActionType::SshCommand, join.action_type);
+        assert_eq!("c1-worker-1", join.target);
+        assert_eq!(
+            Some("sudo microk8s join 10.0.0.11:25000/TOKEN --worker".to_string()),
+            join.details
+        );
+    }
+
+    #[test]
+    fn plan_shows_cloud_init_snippets_without_password() {
+        let cluster = Cluster {
+            node: "pve".to_string(),
+            cluster_name: "c1".to_string(),
+            node_username: "ubuntu".to_string(),
+            node_password: "secret".to_string(),
+            disk_size: 20,
+            nodes: vec![node("master-1", 101, "10.0.0.11", ClusterNodeType::Master)],
+            cloud_init: Some(CloudInitSettings {
+                storage: "local".to_string(),
+                user_data: Some("#cloud-config\npassword: secret\n".to_string()),
+                vendor_data: Some("#cloud-config\npackages:\n  - nfs-common\n".to_string()),
+            }),
+            ..Default::default()
+        };
+        let actions = plan(&cluster).unwrap().actions;
+        let snippets = actions
+            .iter()
+            .filter(|i| i.description.starts_with("Upload cloud-init snippet"))
+            .map(|i| i.details.clone().unwrap())
+            .collect::<Vec<String>>();
+        assert_eq!(2, snippets.len());
+        assert!(snippets[0].contains("  hashed_passwd: \"********\""));
+        assert!(snippets[0].contains("password: ********"));
+        assert!(!snippets[0].contains("secret"));
+        assert!(!snippets[0].contains("$6$"));
+        assert!(snippets[1].contains("  - nfs-common"));
+    }
+}
@@ -0,0 +1,179 @@
+use std::sync::Arc;
+use log::info;
+
+use proxmox_client::model::{AccessData, CreateSnapshot};
+use proxmox_client::{Client, ClientOperations};
+use crate::dispatcher::usecase::common;
+use crate::model::{Cluster, ClusterNode, LogEntry};
+use crate::Repository;
+
+
+pub(crate) fn execute(
+    proxmox_client: Arc<Client>,
+    repo: Arc<Repository>,
+    access: AccessData,
+    cluster_name: String,
+    snapshot_name: String,
+    description: String,
+    freeze_fs: bool,
+) -> Result<(), String> {
+    info!("Request to snapshot the cluster has been received");
+    let proxmox_client = proxmox_client.operations(access);
+
+    repo.save_log(LogEntry::info(
+        &cluster_name,
+        format!("Start creating snapshot [{}]", snapshot_name),
+    ))?;
+
+    let cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    let existing_nodes = common::vm::get_existing_vms(&proxmox_client, &cluster)?;
+
+    for (idx, node) in existing_nodes.iter().enumerate() {
+        let created = snapshot_vm(
+            &proxmox_client,
+            repo.clone(),
+            &cluster,
+            node,
+            &snapshot_name,
+            &description,
+            freeze_fs,
+        );
+        if let Err(e) = created {
+            let deleted = delete_snapshots(
+                &proxmox_client,
+                repo.clone(),
+                &cluster,
+                &existing_nodes[..idx],
+                &snapshot_name,
+            );
+            return combine_errors(Err(e), deleted);
+        }
+    }
+    Ok(())
+}
+
+/// Error of the first operation is kept when the next one fails as well, both are reported.
+fn combine_errors(result: Result<(), String>, next: Result<(), String>) -> Result<(), String> {
+    match (result, next) {
+        (Err(e), Err(next_error)) => Err(format!("{}, {}", e, next_error)),
+        (result, next) => result.and(next),
+    }
+}
+
+/// The filesystem of the VM is frozen only for its own snapshot, other VMs keep running.
+fn snapshot_vm(
+    proxmox_client: &ClientOperations,
+    repo: Arc<Repository>,
+    cluster: &Cluster,
+    node: &ClusterNode,
+    snapshot_name: &str,
+    description: &str,
+    freeze_fs: bool,
+) -> Result<(), String> {
+    if freeze_fs {
+        proxmox_client
+            .agent_fsfreeze_freeze(&cluster.node, node.vm_id)
+            .map_err(|e| format!("Cannot freeze filesystem of VM [{}]: {}", node.vm_id, e))?;
+        repo.save_log(LogEntry::info(
+            &cluster.cluster_name,
+            format!("Filesystem of VM [{}] has been frozen", node.vm_id),
+        ))?;
+    }
+    let result = create_snapshot(
+        proxmox_client,
+        repo.clone(),
+        cluster,
+        node,
+        snapshot_name,
+        description,
+    );
+    if freeze_fs {
+        let thawed = proxmox_client
+            .agent_fsfreeze_thaw(&cluster.node, node.vm_id)
+            .map_err(|e| format!("Cannot thaw filesystem of VM [{}]: {}", node.vm_id, e));
+        if thawed.is_ok() {
+            repo.save_log(LogEntry::info(
+                &cluster.cluster_name,
+                format!("Filesystem of VM [{}] has been thawed", node.vm_id),
+            ))?;
+        }
+        return combine_errors(result, thawed);
+    }
+    result
+}
+
+fn create_snapshot(
+    proxmox_client: &ClientOperations,
+    repo: Arc<Repository>,
+    cluster: &Cluster,
+    node: &ClusterNode,
+    snapshot_name: &str,
+    description: &str,
+) -> Result<(), String> {
+    let upid = proxmox_client
+        .create_snapshot(CreateSnapshot {
+            vm_id: node.vm_id,
+            node: cluster.node.clone(),
+            snap_name: snapshot_name.to_string(),
+            description: Some(description.to_string()),
+            vm_state: None,
+        })
+        .map_err(|e| format!("Cannot create snapshot of VM [{}]: {}", node.vm_id, e))?;
+    common::vm::wait_for_task(proxmox_client, &cluster.node, &upid)?;
+    repo.save_log(LogEntry::info(
+        &cluster.cluster_name,
+        format!("Snapshot [{}] of VM [{}] has been created", snapshot_name, node.vm_id),
+    ))?;
+    Ok(())
+}
+
+/// Snapshots of the VMs done before the failure are removed, the cluster doesn't keep
+/// a partial snapshot.
+fn delete_snapshots(
+    proxmox_client: &ClientOperations,
+    repo: Arc<Repository>,
+    cluster: &Cluster,
+    nodes: &[ClusterNode],
+    snapshot_name: &str,
+) -> Result<(), String> {
+    let mut errors = Vec::new();
+    for node in nodes.iter() {
+        let deleted = proxmox_client
+            .delete_snapshot(&cluster.node, node.vm_id, snapshot_name)
+            .map_err(|e| e.to_string())
+            .and_then(|upid| common::vm::wait_for_task(proxmox_client, &cluster.node, &upid));
+        match deleted {
+            Ok(_) => repo.save_log(LogEntry::info(
+                &cluster.cluster_name,
+                format!("Snapshot [{}] of VM [{}] has been deleted", snapshot_name, node.vm_id),
+            ))?,
+            Err(e) => errors.push(format!("Cannot delete snapshot of VM [{}]: {}", node.vm_id, e)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dispatcher::usecase::create_cluster_snapshot::combine_errors;
+
+    #[test]
+    fn combine_errors_keeps_snapshot_error() {
+        assert_eq!(Ok(()), combine_errors(Ok(()), Ok(())));
+        assert_eq!(Err("thaw".to_string()), combine_errors(Ok(()), Err("thaw".to_string())));
+        assert_eq!(
+            Err("snapshot".to_string()),
+            combine_errors(Err("snapshot".to_string()), Ok(()))
+        );
+        assert_eq!(
+            Err("snapshot, thaw".to_string()),
+            combine_errors(Err("snapshot".to_string()), Err("thaw".to_string()))
+        );
+    }
+}
@@ -2,8 +2,9 @@ use std::sync::Arc;
 
 use proxmox_client::model::AccessData;
 use proxmox_client::ClientOperations;
+use crate::backup;
 use crate::dispatcher::usecase::common;
-use crate::model::{Cluster, ClusterNode, LogEntry};
+use crate::model::{Cluster, ClusterNode, ExecutionPlan, LogEntry};
 use crate::Repository;
 
 
@@ -19,13 +20,91 @@ pub(crate) fn execute(
         .ok_or("Cannot find cluster")?;
 
     let existing_nodes = common::vm::get_existing_vms(&proxmox_client, &cluster)?;
-    stop_vms(&repo, &proxmox_client, &cluster, &existing_nodes)?;
-    delete_vms(repo.clone(), &proxmox_client, &cluster, &existing_nodes)?;
+    for step in steps(&cluster) {
+        match step {
+            Step::DeleteBackupJob => {
+                let job_id = backup::backup_job_id(&cluster_name);
+                if let Err(e) = proxmox_client.delete_backup_job(&job_id) {
+                    repo.save_log(LogEntry::error(
+                        &cluster_name,
+                        format!("Cannot delete backup job [{}]: {}", job_id, e),
+                    ))?;
+                }
+            }
+            Step::StopVms => stop_vms(&repo, &proxmox_client, &cluster, &existing_nodes)?,
+            Step::DeleteVms => delete_vms(repo.clone(), &proxmox_client, &cluster, &existing_nodes)?,
+            Step::DeleteSnippets => {
+                let snippets_deleted =
+                    common::cloud_init::delete_snippets(&proxmox_client, &cluster, &cluster.nodes, true);
+                if let Err(e) = snippets_deleted {
+                    repo.save_log(LogEntry::error(
+                        &cluster_name,
+                        format!("Cannot delete cloud-init snippets: {}", e),
+                    ))?;
+                }
+            }
+        }
+    }
 
     repo.delete_cluster(&cluster_name)?;
     Ok(())
 }
 
+/// Steps of the cluster deletion, [execute] runs them and [plan] describes them.
+enum Step {
+    DeleteBackupJob,
+    StopVms,
+    DeleteVms,
+    DeleteSnippets,
+}
+
+fn steps(cluster: &Cluster) -> Vec<Step> {
+    let mut steps = vec![];
+    if cluster.backup.is_some() {
+        steps.push(Step::DeleteBackupJob);
+    }
+    steps.extend([Step::StopVms, Step::DeleteVms, Step::DeleteSnippets]);
+    steps
+}
+
+/// Actions of [execute] in the same order, nothing is run. All nodes are expected
+/// to have their VMs.
+pub(crate) fn plan(cluster: &Cluster) -> ExecutionPlan {
+    let mut actions = vec![];
+    for step in steps(cluster) {
+        match step {
+            Step::DeleteBackupJob => actions.push(common::plan::proxmox(
+                cluster,
+                format!(
+                    "Delete backup job [{}]",
+                    backup::backup_job_id(&cluster.cluster_name)
+                ),
+                None,
+            )),
+            Step::StopVms => {
+                for node in cluster.nodes.iter() {
+                    actions.push(common::plan::shutdown_vm(cluster, node));
+                }
+                for node in cluster.nodes.iter() {
+                    actions.push(common::plan::wait_for_shutdown(cluster, node));
+                }
+            }
+            Step::DeleteVms => {
+                for node in cluster.nodes.iter() {
+                    actions.push(common::plan::delete_vm(cluster, node));
+                }
+            }
+            Step::DeleteSnippets => {
+                actions.extend(common::plan::delete_snippets(cluster, &cluster.nodes, true))
+            }
+        }
+    }
+    ExecutionPlan {
+        operation: format!("Delete cluster [{}]", cluster.cluster_name),
+        actions,
+    }
+}
+
 pub(crate) fn stop_vms(
     repo: &Arc<Repository>,
     proxmox_client: &ClientOperations,
@@ -80,3 +159,72 @@ pub(crate) fn delete_vms(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dispatcher::usecase::delete_cluster::plan;
+    use crate::model::{BackupMode, BackupSettings, Cluster, ClusterNode, CloudInitSettings};
+
+    #[test]
+    fn plan_follows_delete_cluster_order() {
+        let cluster = Cluster {
+            node: "pve".to_string(),
+            cluster_name: "c1".to_string(),
+            nodes: vec![
+                ClusterNode {
+                    name: "master-1".to_string(),
+                    vm_id: 101,
+                    ..Default::default()
+                },
+                ClusterNode {
+                    name: "worker-1".to_string(),
+                    vm_id: 102,
+                    ..Default::default()
+                },
+            ],
+            backup: Some(BackupSettings {
+                storage: "pbs".to_string(),
+                keep_last: 3,
+                schedule: String::new(),
+                mode: BackupMode::default(),
+            }),
+            cloud_init: Some(CloudInitSettings {
+                storage: "local".to_string(),
+                user_data: None,
+                vendor_data: Some("#cloud-config".to_string()),
+            }),
+            ..Default::default()
+        };
+        let descriptions = plan(&cluster)
+            .actions
+            .into_iter()
+            .map(|i| i.description)
+            .collect::<Vec<String>>();
+        let index = |description: &str| {
+            descriptions
+                .iter()
+                .position(|i| i.starts_with(description))
+                .unwrap()
+        };
+        let order = [
+            "Delete backup job",
+            "Shutdown VM [101]",
+            "Shutdown VM [102]",
+            "Wait for VM [101] shutdown",
+            "Delete VM [101]",
+            "Delete VM [102]",
+            "Delete cloud-init snippet",
+        ];
+        for pair in order.windows(2) {
+            assert!(index(pair[0]) < index(pair[1]), "{} before {}", pair[0], pair[1]);
+        }
+        // user-data of both nodes and the vendor-data of the cluster
+        assert_eq!(
+            3,
+            descriptions
+                .iter()
+                .filter(|i| i.starts_with("Delete cloud-init snippet"))
+                .count()
+        );
+    }
+}
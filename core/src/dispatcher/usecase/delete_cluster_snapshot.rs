@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use log::info;
+
+use proxmox_client::model::AccessData;
+use proxmox_client::Client;
+use crate::dispatcher::usecase::common;
+use crate::model::LogEntry;
+use crate::Repository;
+
+
+pub(crate) fn execute(
+    proxmox_client: Arc<Client>,
+    repo: Arc<Repository>,
+    access: AccessData,
+    cluster_name: String,
+    snapshot_name: String,
+) -> Result<(), String> {
+    info!("Request to delete the cluster snapshot has been received");
+    let proxmox_client = proxmox_client.operations(access);
+
+    repo.save_log(LogEntry::info(
+        &cluster_name,
+        format!("Start deleting snapshot [{}]", snapshot_name),
+    ))?;
+
+    let cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    let existing_nodes = common::vm::get_existing_vms(&proxmox_client, &cluster)?;
+
+    for node in existing_nodes.iter() {
+        let has_snapshot = proxmox_client
+            .snapshots(&cluster.node, node.vm_id)?
+            .iter()
+            .any(|i| i.name == snapshot_name);
+        if !has_snapshot {
+            continue;
+        }
+        let upid = proxmox_client
+            .delete_snapshot(&cluster.node, node.vm_id, &snapshot_name)
+            .map_err(|e| format!("Cannot delete snapshot of VM [{}]: {}", node.vm_id, e))?;
+        common::vm::wait_for_task(&proxmox_client, &cluster.node, &upid)?;
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Snapshot [{}] of VM [{}] has been deleted", snapshot_name, node.vm_id),
+        ))?;
+    }
+    Ok(())
+}
@@ -4,9 +4,10 @@ use std::time::Duration;
 use proxmox_client::model::AccessData;
 use proxmox_client::{Client, ClientOperations};
 use crate::dispatcher::usecase::common;
-use crate::model::{ClusterNodeType, LogEntry};
-use crate::Repository;
+use crate::model::{Cluster, ClusterNode, ClusterNodeType, ExecutionPlan, LogEntry};
+use crate::{distribution, Repository};
 
+const DRAIN_WAIT_SECONDS: u64 = 30;
 
 pub(crate) fn execute(
     proxmox_client: Arc<Client>,
@@ -45,98 +46,157 @@ pub(crate) fn execute(
         .find(|i| i.name == node_name)
         .map(|i| i.clone())
         .ok_or("Cannot find node to delete".to_string())?;
+    let host_name = node_to_delete.host_name(&cluster_name);
 
-    if common::vm::get_existing_vms(&proxmox_client, &cluster)?
+    let vm_exists = common::vm::get_existing_vms(&proxmox_client, &cluster)?
         .iter()
-        .find(|i| i.vm_id == node_to_delete.vm_id)
-        .is_none()
-    {
-        remove_node_from_project(repo.clone(), &cluster_name, &node_name)?;
-        remove_hosts_from_rest_of_nodes(repo.clone(), &proxmox_client, &cluster_name, &node_name)?;
-        return Ok(());
+        .any(|i| i.vm_id == node_to_delete.vm_id);
+    let distribution = distribution::of(&cluster);
+    for step in steps(vm_exists) {
+        match step {
+            Step::Drain => {
+                let mut master_ssh_client = ssh_client::Client::new();
+                master_ssh_client.connect(
+                    &master_node.ip_address,
+                    &cluster.node_username,
+                    &cluster.ssh_key.private_key,
+                    &cluster.ssh_key.public_key,
+                )?;
+                repo.save_log(LogEntry::info(
+                    &cluster_name,
+                    format!("Drain a node [{}]", host_name),
+                ))?;
+                master_ssh_client.execute(drain_command(&cluster, &node_to_delete).as_str())?;
+            }
+            Step::WaitForPods => {
+                repo.save_log(LogEntry::info(
+                    &cluster_name,
+                    format!("Wait {}s to gracefully shutdown pods", DRAIN_WAIT_SECONDS),
+                ))?;
+                std::thread::sleep(Duration::from_secs(DRAIN_WAIT_SECONDS));
+            }
+            Step::Detach => {
+                repo.save_log(LogEntry::info(
+                    &cluster_name,
+                    format!("Detach a node [{}] from the cluster", host_name),
+                ))?;
+                if let Some(command) = distribution.leave_command() {
+                    let mut node_to_delete_ssh_client = ssh_client::Client::new();
+                    node_to_delete_ssh_client.connect(
+                        &node_to_delete.ip_address,
+                        &cluster.node_username,
+                        &cluster.ssh_key.private_key,
+                        &cluster.ssh_key.public_key,
+                    )?;
+                    node_to_delete_ssh_client.execute(command.as_str())?;
+                }
+                let mut master_ssh_client = ssh_client::Client::new();
+                master_ssh_client.connect(
+                    &master_node.ip_address,
+                    &cluster.node_username,
+                    &cluster.ssh_key.private_key,
+                    &cluster.ssh_key.public_key,
+                )?;
+                master_ssh_client.execute(distribution.remove_node_command(&host_name).as_str())?;
+            }
+            Step::DeleteVm => delete_vm(&proxmox_client, repo.clone(), &mut cluster, &node_to_delete)?,
+            Step::DeleteSnippets => delete_snippets(&proxmox_client, repo.clone(), &cluster, &node_to_delete)?,
+            Step::RemoveFromProject => remove_node_from_project(repo.clone(), &cluster_name, &node_name)?,
+            Step::RemoveHosts => {
+                remove_hosts_from_rest_of_nodes(repo.clone(), &proxmox_client, &cluster_name, &host_name)?
+            }
+            Step::RefreshBackupJob => {
+                common::backup::refresh_backup_job(&proxmox_client, repo.clone(), &cluster_name)?
+            }
+        }
     }
 
-    let mut master_ssh_client = ssh_client::Client::new();
-    master_ssh_client.connect(
-        &master_node.ip_address,
-        &cluster.node_username,
-        &cluster.ssh_key.private_key,
-        &cluster.ssh_key.public_key,
-    )?;
+    Ok(())
+}
 
+/// Steps of deleting the node, [execute] runs them and [plan] describes them. A node
+/// without VM is only removed from the project.
+enum Step {
+    Drain,
+    WaitForPods,
+    Detach,
+    DeleteVm,
+    DeleteSnippets,
+    RemoveFromProject,
+    RemoveHosts,
+    RefreshBackupJob,
+}
+
+fn steps(vm_exists: bool) -> Vec<Step> {
+    let mut steps = vec![];
+    if vm_exists {
+        steps.extend([Step::Drain, Step::WaitForPods, Step::Detach, Step::DeleteVm]);
+    }
+    steps.extend([
+        Step::DeleteSnippets,
+        Step::RemoveFromProject,
+        Step::RemoveHosts,
+        Step::RefreshBackupJob,
+    ]);
+    steps
+}
+
+fn delete_vm(
+    proxmox_client: &ClientOperations,
+    repo: Arc<Repository>,
+    cluster: &mut Cluster,
+    node_to_delete: &ClusterNode,
+) -> Result<(), String> {
+    cluster.nodes.retain_mut(|i| i.name == node_to_delete.name);
+    let vm_exists = common::vm::get_existing_vms(proxmox_client, cluster)?
+        .iter()
+        .any(|i| i.vm_id == node_to_delete.vm_id);
+    if !vm_exists {
+        return Ok(());
+    }
     repo.save_log(LogEntry::info(
-        &cluster_name,
-        format!("Drain a node [{}-{}]", cluster_name, node_name),
+        &cluster.cluster_name,
+        format!("Removing VM [{}]", node_to_delete.vm_id),
     ))?;
-    master_ssh_client.execute(
-        format!(
-            "sudo microk8s.kubectl drain {}-{} --ignore-daemonsets --grace-period=30 --timeout=60s",
-            cluster_name, node_name
-        )
-        .as_str(),
-    )?;
+
+    proxmox_client
+        .shutdown_vm(&cluster.node, node_to_delete.vm_id)
+        .map_err(|e| format!("Shutdown VM [{}], error: [{}]", node_to_delete.vm_id, e))?;
     repo.save_log(LogEntry::info(
-        &cluster_name,
-        "Wait 30s to gracefully shutdown pods".to_string(),
+        &cluster.cluster_name,
+        format!("Requested VM [{}] to shutdown", node_to_delete.vm_id),
     ))?;
-    std::thread::sleep(Duration::from_secs(30));
+    let is_shutdown =
+        common::vm::wait_for_shutdown(proxmox_client, &cluster.node, node_to_delete.vm_id)?;
+    if !is_shutdown {
+        proxmox_client.stop_vm(&cluster.node, node_to_delete.vm_id)?;
+        common::vm::wait_for_shutdown(proxmox_client, &cluster.node, node_to_delete.vm_id)?;
+    }
 
+    proxmox_client
+        .delete_vm(&cluster.node, node_to_delete.vm_id)
+        .map_err(|e| format!("Delete VM [{}], error: [{}]", node_to_delete.vm_id, e))?;
     repo.save_log(LogEntry::info(
-        &cluster_name,
-        format!(
-            "Detach a node [{}-{}] from the cluster",
-            cluster_name, node_name
-        ),
+        &cluster.cluster_name,
+        format!("VM [{}] has been deleted", node_to_delete.vm_id),
     ))?;
-    let mut node_to_delete_ssh_client = ssh_client::Client::new();
-    node_to_delete_ssh_client.connect(
-        &node_to_delete.ip_address,
-        &cluster.node_username,
-        &cluster.ssh_key.private_key,
-        &cluster.ssh_key.public_key,
-    )?;
-    node_to_delete_ssh_client.execute("sudo microk8s leave")?;
-
-    master_ssh_client
-        .execute(format!("sudo microk8s remove-node {}-{}", cluster_name, node_name).as_str())?;
-
-    cluster.nodes.retain_mut(|i| i.name == node_name);
-    let vm_exists = common::vm::get_existing_vms(&proxmox_client, &cluster)?
-        .iter()
-        .any(|i| i.vm_id == node_to_delete.vm_id);
-
-    if vm_exists {
-        repo.save_log(LogEntry::info(
-            &cluster_name,
-            format!("Removing VM [{}]", node_to_delete.vm_id),
-        ))?;
-
-        proxmox_client
-            .shutdown_vm(&cluster.node, node_to_delete.vm_id)
-            .map_err(|e| format!("Shutdown VM [{}], error: [{}]", node_to_delete.vm_id, e))?;
-        repo.save_log(LogEntry::info(
-            &cluster.cluster_name,
-            format!("Requested VM [{}] to shutdown", node_to_delete.vm_id),
-        ))?;
-        let is_shutdown =
-            common::vm::wait_for_shutdown(&proxmox_client, &cluster.node, node_to_delete.vm_id)?;
-        if !is_shutdown {
-            proxmox_client.stop_vm(&cluster.node, node_to_delete.vm_id)?;
-            common::vm::wait_for_shutdown(&proxmox_client, &cluster.node, node_to_delete.vm_id)?;
-        }
+    Ok(())
+}
 
-        proxmox_client
-            .delete_vm(&cluster.node, node_to_delete.vm_id)
-            .map_err(|e| format!("Delete VM [{}], error: [{}]", node_to_delete.vm_id, e))?;
-        repo.save_log(LogEntry::info(
+fn delete_snippets(
+    proxmox_client: &ClientOperations,
+    repo: Arc<Repository>,
+    cluster: &Cluster,
+    node: &ClusterNode,
+) -> Result<(), String> {
+    let result =
+        common::cloud_init::delete_snippets(proxmox_client, cluster, std::slice::from_ref(node), false);
+    if let Err(e) = result {
+        repo.save_log(LogEntry::error(
             &cluster.cluster_name,
-            format!("VM [{}] has been deleted", node_to_delete.vm_id),
+            format!("Cannot delete cloud-init snippets of node [{}]: {}", node.name, e),
         ))?;
     }
-
-    remove_node_from_project(repo.clone(), &cluster_name, &node_name)?;
-    remove_hosts_from_rest_of_nodes(repo.clone(), &proxmox_client, &cluster_name, &node_name)?;
-
     Ok(())
 }
 
@@ -150,6 +210,7 @@ fn remove_node_from_project(
         .ok_or("Cannot find cluster")?;
     cluster.nodes.retain_mut(|i| i.name != node_name);
     repo.save_cluster(cluster)?;
+    repo.release_ip_allocation(cluster_name, node_name)?;
     Ok(())
 }
 
@@ -157,7 +218,7 @@ fn remove_hosts_from_rest_of_nodes(
     repo: Arc<Repository>,
     proxmox_client: &ClientOperations,
     cluster_name: &str,
-    node_name: &str,
+    host_name: &str,
 ) -> Result<(), String> {
     let cluster = repo
         .get_cluster(cluster_name)?
@@ -171,16 +232,172 @@ fn remove_hosts_from_rest_of_nodes(
             &cluster.ssh_key.private_key,
             &cluster.ssh_key.public_key,
         )?;
-        ssh_client.execute(
-            format!(
-                "sudo sed -i '/{}-{}/d' /etc/cloud/templates/hosts.debian.tmpl",
-                cluster_name, node_name
-            )
-            .as_str(),
-        )?;
-        ssh_client.execute(
-            format!("sudo sed -i '/{}-{}/d' /etc/hosts", cluster_name, node_name).as_str(),
-        )?;
+        for command in remove_host_commands(host_name) {
+            ssh_client.execute(command.as_str())?;
+        }
     }
     Ok(())
 }
+
+fn drain_command(cluster: &Cluster, node: &ClusterNode) -> String {
+    format!(
+        "sudo {} drain {} --ignore-daemonsets --grace-period=30 --timeout=60s",
+        distribution::of(cluster).kubectl(),
+        node.host_name(&cluster.cluster_name)
+    )
+}
+
+fn remove_host_commands(host_name: &str) -> Vec<String> {
+    vec![
+        format!(
+            "sudo sed -i '/{}/d' /etc/cloud/templates/hosts.debian.tmpl",
+            host_name
+        ),
+        format!("sudo sed -i '/{}/d' /etc/hosts", host_name),
+    ]
+}
+
+/// Actions of [execute] in the same order, nothing is run. All nodes are expected
+/// to have their VMs.
+pub(crate) fn plan(cluster: &Cluster, node_name: &str) -> Result<ExecutionPlan, String> {
+    if cluster.nodes.len() == 1 {
+        return Err("Cannot delete last node, delete whole cluster instead".to_string());
+    }
+    let master_node = cluster
+        .nodes
+        .iter()
+        .find(|i| i.node_type == ClusterNodeType::Master && i.name != node_name)
+        .ok_or("Cannot find any master node".to_string())?;
+    let node_to_delete = cluster
+        .nodes
+        .iter()
+        .find(|i| i.name == node_name)
+        .ok_or("Cannot find node to delete".to_string())?;
+    let distribution = distribution::of(cluster);
+    let host_name = common::plan::host_name(cluster, node_to_delete);
+
+    let mut actions = vec![];
+    for step in steps(true) {
+        match step {
+            Step::Drain => actions.push(common::plan::ssh(
+                cluster,
+                master_node,
+                "Drain node",
+                drain_command(cluster, node_to_delete),
+            )),
+            Step::WaitForPods => actions.push(common::plan::wait(
+                host_name.clone(),
+                format!("Wait {}s to gracefully shutdown pods", DRAIN_WAIT_SECONDS),
+            )),
+            Step::Detach => {
+                if let Some(command) = distribution.leave_command() {
+                    actions.push(common::plan::ssh(
+                        cluster,
+                        node_to_delete,
+                        "Detach node from the cluster",
+                        command,
+                    ));
+                }
+                actions.push(common::plan::ssh(
+                    cluster,
+                    master_node,
+                    "Detach node from the cluster",
+                    distribution.remove_node_command(&host_name),
+                ));
+            }
+            Step::DeleteVm => {
+                actions.push(common::plan::shutdown_vm(cluster, node_to_delete));
+                actions.push(common::plan::wait_for_shutdown(cluster, node_to_delete));
+                actions.push(common::plan::delete_vm(cluster, node_to_delete));
+            }
+            Step::DeleteSnippets => actions.extend(common::plan::delete_snippets(
+                cluster,
+                std::slice::from_ref(node_to_delete),
+                false,
+            )),
+            // Only the project of Makoon changes
+            Step::RemoveFromProject => {}
+            Step::RemoveHosts => {
+                for node in cluster.nodes.iter().filter(|i| i.name != node_name) {
+                    for command in remove_host_commands(&host_name) {
+                        actions.push(common::plan::ssh(
+                            cluster,
+                            node,
+                            "Remove hostname of deleted node",
+                            command,
+                        ));
+                    }
+                }
+            }
+            Step::RefreshBackupJob => actions.extend(common::plan::refresh_backup_job(cluster)),
+        }
+    }
+    Ok(ExecutionPlan {
+        operation: format!(
+            "Delete node [{}] from cluster [{}]",
+            node_name, cluster.cluster_name
+        ),
+        actions,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dispatcher::usecase::delete_node_from_cluster::plan;
+    use crate::model::{Cluster, ClusterNodeType, CloudInitSettings};
+    use crate::test_fixtures::node;
+
+    fn cluster() -> Cluster {
+        Cluster {
+            node: "pve".to_string(),
+            cluster_name: "c1".to_string(),
+            nodes: vec![
+                node("master-1", 101, "", ClusterNodeType::Master),
+                node("worker-1", 102, "", ClusterNodeType::Worker),
+                node("worker-2", 103, "", ClusterNodeType::Worker),
+            ],
+            cloud_init: Some(CloudInitSettings {
+                storage: "local".to_string(),
+                user_data: None,
+                vendor_data: None,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn plan_follows_delete_node_order() {
+        let actions = plan(&cluster(), "worker-2").unwrap().actions;
+        let index = |description: &str| {
+            actions
+                .iter()
+                .position(|i| i.description.starts_with(description))
+                .unwrap()
+        };
+        let order = [
+            "Drain node",
+            "Wait 30s",
+            "Detach node from the cluster",
+            "Shutdown VM [103]",
+            "Wait for VM [103] shutdown",
+            "Delete VM [103]",
+            "Delete cloud-init snippet",
+            "Remove hostname of deleted node",
+        ];
+        for pair in order.windows(2) {
+            assert!(index(pair[0]) < index(pair[1]), "{} before {}", pair[0], pair[1]);
+        }
+        assert_eq!("c1-master-1", actions[index("Drain node")].target);
+        assert!(actions
+            .iter()
+            .filter(|i| i.description == "Remove hostname of deleted node")
+            .all(|i| i.target != "c1-worker-2"));
+    }
+
+    #[test]
+    fn plan_keeps_last_node() {
+        let mut cluster = cluster();
+        cluster.nodes.truncate(1);
+        assert!(plan(&cluster, "master-1").is_err());
+    }
+}
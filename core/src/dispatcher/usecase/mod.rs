@@ -1,9 +1,21 @@
 pub mod add_node_to_cluster;
+pub mod autoscale;
+pub mod backup_cluster;
+pub mod change_addon;
 pub mod change_resources;
 mod common;
+pub mod configure_cluster_backup;
 pub mod create_cluster;
+pub mod create_cluster_snapshot;
 pub mod delete_cluster;
+pub mod delete_cluster_snapshot;
 pub mod delete_node_from_cluster;
+pub mod refresh_certificates;
+pub mod restore_cluster_backup;
+pub mod rollback_cluster_snapshot;
+pub mod rotate_ssh_key;
+pub mod scale_down_node_pool;
+pub mod scale_up_node_pool;
+pub mod sync_node_metadata;
 pub use common::apps::install_cluster_resource;
 pub use common::apps::install_helm_app;
-pub use common::apps::HELM_CMD;
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+use log::info;
+
+use crate::{certificates, distribution};
+use crate::dispatcher::usecase::common;
+use crate::model::{Cluster, ClusterNode, ClusterNodeType, LogEntry};
+use crate::Repository;
+
+
+pub(crate) fn execute(
+    repo: Arc<Repository>,
+    cluster_name: String,
+    include_ca: bool,
+) -> Result<(), String> {
+    info!("Request to refresh cluster certificates has been received");
+
+    let cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    let mut master_nodes = cluster
+        .nodes
+        .iter()
+        .filter(|i| i.node_type == ClusterNodeType::Master)
+        .cloned()
+        .collect::<Vec<ClusterNode>>();
+    master_nodes.sort_by_key(|i| i.vm_id);
+    if master_nodes.is_empty() {
+        return Err("Cannot find any master node".to_string());
+    }
+
+    // Nodes are refreshed one by one, the next one starts when Kubernetes is ready again
+    for node in master_nodes.iter() {
+        let mut ssh_client = ssh_client::Client::new();
+        ssh_client.connect(
+            &node.ip_address,
+            &cluster.node_username,
+            &cluster.ssh_key.private_key,
+            &cluster.ssh_key.public_key,
+        )?;
+        let certs: Vec<&str> = if include_ca {
+            // Refreshing CA regenerates all certificates signed by it as well
+            vec![certificates::CA_CERT]
+        } else {
+            certificates::REFRESHABLE_CERTS.to_vec()
+        };
+        for cert in certs {
+            repo.save_log(LogEntry::info(
+                &cluster_name,
+                format!("Refresh certificate [{}] on VM [{}]", cert, node.vm_id),
+            ))?;
+            ssh_client.execute(format!("sudo microk8s refresh-certs --cert {}", cert).as_str())?;
+        }
+        common::cluster::wait_for_ready_kubernetes(repo.clone(), &cluster, node)?;
+    }
+
+    let first_master_node = master_nodes
+        .first()
+        .ok_or("Cannot get first master node".to_string())?;
+    if include_ca {
+        rejoin_workers(repo.clone(), &cluster, first_master_node)?;
+    }
+    let mut ssh_client = ssh_client::Client::new();
+    ssh_client.connect(
+        &first_master_node.ip_address,
+        &cluster.node_username,
+        &cluster.ssh_key.private_key,
+        &cluster.ssh_key.public_key,
+    )?;
+    let kube_config_content = ssh_client.execute("sudo microk8s config")?;
+    let mut cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    cluster.cluster_config = kube_config_content;
+    repo.save_cluster(cluster)?;
+    repo.save_log(LogEntry::info(
+        &cluster_name,
+        "Kube config has been refreshed".to_string(),
+    ))?;
+    Ok(())
+}
+
+/// Workers keep trusting the old CA, they leave the cluster and join it again.
+fn rejoin_workers(
+    repo: Arc<Repository>,
+    cluster: &Cluster,
+    master_node: &ClusterNode,
+) -> Result<(), String> {
+    let mut worker_nodes = cluster
+        .nodes
+        .iter()
+        .filter(|i| i.node_type == ClusterNodeType::Worker)
+        .collect::<Vec<&ClusterNode>>();
+    worker_nodes.sort_by_key(|i| i.vm_id);
+    if worker_nodes.is_empty() {
+        return Ok(());
+    }
+
+    let distribution = distribution::of(cluster);
+    let mut master_ssh_client = ssh_client::Client::new();
+    master_ssh_client.connect(
+        &master_node.ip_address,
+        &cluster.node_username,
+        &cluster.ssh_key.private_key,
+        &cluster.ssh_key.public_key,
+    )?;
+    for node in worker_nodes {
+        let host_name = node.host_name(&cluster.cluster_name);
+        repo.save_log(LogEntry::info(
+            &cluster.cluster_name,
+            format!("Rejoin node [{}] to cluster with refreshed CA", host_name),
+        ))?;
+        if let Some(command) = distribution.leave_command() {
+            let mut ssh_client = ssh_client::Client::new();
+            ssh_client.connect(
+                &node.ip_address,
+                &cluster.node_username,
+                &cluster.ssh_key.private_key,
+                &cluster.ssh_key.public_key,
+            )?;
+            ssh_client.execute(command.as_str())?;
+        }
+        master_ssh_client.execute(distribution.remove_node_command(&host_name).as_str())?;
+        common::cluster::join_node_to_cluster(repo.clone(), cluster, master_node, node)?;
+        common::cluster::wait_for_ready_kubernetes(repo.clone(), cluster, node)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+use log::info;
+
+use proxmox_client::model::{AccessData, RestoreVirtualMachine};
+use proxmox_client::Client;
+use crate::backup;
+use crate::dispatcher::usecase::common;
+use crate::model::{BackupVolume, LogEntry};
+use crate::Repository;
+
+
+pub(crate) fn execute(
+    proxmox_client: Arc<Client>,
+    repo: Arc<Repository>,
+    access: AccessData,
+    cluster_name: String,
+    backup_set_id: String,
+    node_name: Option<String>,
+) -> Result<(), String> {
+    info!("Request to restore the cluster from backup has been received");
+    let proxmox_client = proxmox_client.operations(access);
+
+    let cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    let settings = cluster
+        .backup
+        .clone()
+        .ok_or("Backup is not configured for the cluster")?;
+
+    let backup_set = backup::backup_sets(
+        &cluster,
+        proxmox_client.backups(&cluster.node, &settings.storage, None)?,
+    )
+    .into_iter()
+    .find(|i| i.id == backup_set_id)
+    .ok_or(format!("Cannot find backup set [{}]", backup_set_id))?;
+
+    let volumes_to_restore = match &node_name {
+        Some(node_name) => {
+            let node = cluster
+                .nodes
+                .iter()
+                .find(|i| &i.name == node_name)
+                .ok_or(format!("Cannot find node [{}]", node_name))?;
+            backup_set
+                .volumes
+                .iter()
+                .filter(|i| i.vm_id == node.vm_id)
+                .cloned()
+                .collect::<Vec<BackupVolume>>()
+        }
+        None => backup_set.volumes.clone(),
+    };
+    if volumes_to_restore.is_empty() {
+        return Err(format!("Backup set [{}] has no VMs to restore", backup_set_id));
+    }
+    if node_name.is_none() && !backup_set.complete {
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            "Backup set doesn't contain all nodes, only nodes with backup will be restored"
+                .to_string(),
+        ))?;
+    }
+
+    let existing_nodes = common::vm::get_existing_vms(&proxmox_client, &cluster)?;
+    for volume in volumes_to_restore.iter() {
+        if existing_nodes.iter().any(|i| i.vm_id == volume.vm_id) {
+            repo.save_log(LogEntry::info(
+                &cluster_name,
+                format!("Shutdown VM [{}]", volume.vm_id),
+            ))?;
+            common::vm::stop_vm(&proxmox_client, &cluster.node, volume.vm_id)?;
+        }
+    }
+
+    for volume in volumes_to_restore.iter() {
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Restore VM [{}] from [{}]", volume.vm_id, volume.volid),
+        ))?;
+        let upid = proxmox_client
+            .restore_virtual_machine(RestoreVirtualMachine {
+                node: cluster.node.clone(),
+                vm_id: volume.vm_id,
+                archive: volume.volid.clone(),
+                force: Some(1),
+                start: None,
+            })
+            .map_err(|e| format!("Cannot restore VM [{}]: {}", volume.vm_id, e))?;
+        common::vm::wait_for_task_with_opts(&proxmox_client, &cluster.node, &upid, 10, 360)?;
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("VM [{}] has been restored", volume.vm_id),
+        ))?;
+    }
+
+    for volume in volumes_to_restore.iter() {
+        proxmox_client
+            .start_vm(&cluster.node, volume.vm_id)
+            .map_err(|e| format!("Cannot start VM [{}]: {}", volume.vm_id, e))?;
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Starting VM [{}]", volume.vm_id),
+        ))?;
+    }
+
+    for volume in volumes_to_restore.iter() {
+        let node = cluster
+            .nodes
+            .iter()
+            .find(|i| i.vm_id == volume.vm_id)
+            .ok_or(format!("Cannot find node for VM [{}]", volume.vm_id))?;
+        common::vm::wait_for_start(&proxmox_client, &cluster, node)
+            .map_err(|e| format!("Cannot start VM [{}]: {}", volume.vm_id, e))?;
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("VM [{}] has been started", volume.vm_id),
+        ))?;
+    }
+    Ok(())
+}
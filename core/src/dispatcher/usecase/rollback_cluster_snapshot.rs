@@ -0,0 +1,82 @@
+use std::sync::Arc;
+use log::info;
+
+use proxmox_client::model::AccessData;
+use proxmox_client::Client;
+use crate::dispatcher::usecase::common;
+use crate::model::LogEntry;
+use crate::Repository;
+
+
+pub(crate) fn execute(
+    proxmox_client: Arc<Client>,
+    repo: Arc<Repository>,
+    access: AccessData,
+    cluster_name: String,
+    snapshot_name: String,
+) -> Result<(), String> {
+    info!("Request to rollback the cluster has been received");
+    let proxmox_client = proxmox_client.operations(access);
+
+    repo.save_log(LogEntry::info(
+        &cluster_name,
+        format!("Start rolling back cluster to snapshot [{}]", snapshot_name),
+    ))?;
+
+    let cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    let existing_nodes = common::vm::get_existing_vms(&proxmox_client, &cluster)?;
+
+    for node in existing_nodes.iter() {
+        let has_snapshot = proxmox_client
+            .snapshots(&cluster.node, node.vm_id)?
+            .iter()
+            .any(|i| i.name == snapshot_name);
+        if !has_snapshot {
+            return Err(format!(
+                "VM [{}] doesn't have snapshot [{}]",
+                node.vm_id, snapshot_name
+            ));
+        }
+    }
+
+    for node in existing_nodes.iter() {
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Shutdown VM [{}]", node.vm_id),
+        ))?;
+        common::vm::stop_vm(&proxmox_client, &cluster.node, node.vm_id)?;
+    }
+
+    for node in existing_nodes.iter() {
+        let upid = proxmox_client
+            .rollback_snapshot(&cluster.node, node.vm_id, &snapshot_name)
+            .map_err(|e| format!("Cannot rollback VM [{}]: {}", node.vm_id, e))?;
+        common::vm::wait_for_task(&proxmox_client, &cluster.node, &upid)?;
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("VM [{}] has been rolled back to [{}]", node.vm_id, snapshot_name),
+        ))?;
+    }
+
+    for node in existing_nodes.iter() {
+        proxmox_client
+            .start_vm(&cluster.node, node.vm_id)
+            .map_err(|e| format!("Cannot start VM [{}]: {}", node.vm_id, e))?;
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Starting VM [{}]", node.vm_id),
+        ))?;
+    }
+
+    for node in existing_nodes.iter() {
+        common::vm::wait_for_start(&proxmox_client, &cluster, node)
+            .map_err(|e| format!("Cannot start VM [{}]: {}", node.vm_id, e))?;
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("VM [{}] has been started", node.vm_id),
+        ))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use std::sync::Arc;
+use log::{error, info};
+
+use proxmox_client::model::{AccessData, VmConfig};
+use proxmox_client::{to_url_encoded, Client};
+use crate::dispatcher::usecase::common;
+use crate::keys;
+use crate::model::{ClusterNode, KeyPair, LogEntry};
+use crate::Repository;
+
+
+pub(crate) fn execute(
+    proxmox_client: Arc<Client>,
+    repo: Arc<Repository>,
+    access: AccessData,
+    cluster_name: String,
+) -> Result<(), String> {
+    info!("Request to rotate SSH key has been received");
+    let proxmox_client = proxmox_client.operations(access);
+
+    let cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    let existing_nodes = common::vm::get_existing_vms(&proxmox_client, &cluster)?;
+    let old_key = cluster.ssh_key.clone();
+    let old_key_body =
+        keys::public_key_body(&old_key.public_key).ok_or("Cannot parse current public key")?;
+
+    repo.save_log(LogEntry::info(
+        &cluster_name,
+        "Generate new SSH key".to_string(),
+    ))?;
+    let new_key = keys::generate_ssh_keys(&cluster.ssh_key_type)?;
+    let new_key_body =
+        keys::public_key_body(&new_key.public_key).ok_or("Cannot parse new public key")?;
+
+    // Until the new key is saved, the old key is the only one Makoon knows,
+    // so any failure here has to leave the nodes accessible by the old key
+    if let Err(e) = install_and_verify_key(
+        &repo,
+        &cluster_name,
+        &cluster.node_username,
+        &existing_nodes,
+        &old_key,
+        &new_key,
+    ) {
+        for node in existing_nodes.iter() {
+            if let Err(e) = remove_key(&cluster.node_username, node, &old_key, new_key_body) {
+                error!("Cannot remove new key from VM [{}]: {}", node.vm_id, e);
+            }
+        }
+        return Err(e);
+    }
+
+    let mut cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    cluster.ssh_key = new_key.clone();
+    repo.save_cluster(cluster.clone())?;
+    repo.save_log(LogEntry::info(
+        &cluster_name,
+        "New SSH key has been saved".to_string(),
+    ))?;
+
+    for node in existing_nodes.iter() {
+        remove_key(&cluster.node_username, node, &new_key, old_key_body)
+            .map_err(|e| format!("Cannot remove old key from VM [{}]: {}", node.vm_id, e))?;
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Old SSH key has been removed from VM [{}]", node.vm_id),
+        ))?;
+    }
+
+    for node in existing_nodes.iter() {
+        proxmox_client
+            .update_config(VmConfig {
+                vm_id: node.vm_id,
+                node: cluster.node.clone(),
+                ssh_keys: Some(to_url_encoded(&keys::cloud_init_ssh_keys(&cluster))),
+                ..Default::default()
+            })
+            .map_err(|e| format!("Cannot update cloud-init of VM [{}]: {}", node.vm_id, e))?;
+    }
+    repo.save_log(LogEntry::info(
+        &cluster_name,
+        "Cloud-init SSH keys have been updated".to_string(),
+    ))?;
+
+    // Custom user-data of the snippets replaces `sshkeys`, so it has to carry the new key too
+    if cluster.cloud_init.is_some() {
+        for node in existing_nodes.iter() {
+            common::cloud_init::upload_snippets(&proxmox_client, &cluster, node).map_err(|e| {
+                format!("Cannot update cloud-init snippets of VM [{}]: {}", node.vm_id, e)
+            })?;
+        }
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            "Cloud-init snippets have been updated".to_string(),
+        ))?;
+    }
+    Ok(())
+}
+
+fn install_and_verify_key(
+    repo: &Arc<Repository>,
+    cluster_name: &str,
+    username: &str,
+    nodes: &[ClusterNode],
+    old_key: &KeyPair,
+    new_key: &KeyPair,
+) -> Result<(), String> {
+    for node in nodes.iter() {
+        let mut ssh_client = ssh_client::Client::new();
+        ssh_client.connect(
+            &node.ip_address,
+            username,
+            &old_key.private_key,
+            &old_key.public_key,
+        )?;
+        ssh_client.execute(
+            format!(
+                "echo '{}' >> ~/.ssh/authorized_keys",
+                new_key.public_key.trim()
+            )
+            .as_str(),
+        )?;
+        repo.save_log(LogEntry::info(
+            cluster_name,
+            format!("New SSH key has been installed on VM [{}]", node.vm_id),
+        ))?;
+    }
+
+    for node in nodes.iter() {
+        let mut ssh_client = ssh_client::Client::new();
+        ssh_client
+            .connect(
+                &node.ip_address,
+                username,
+                &new_key.private_key,
+                &new_key.public_key,
+            )
+            .map_err(|e| format!("Cannot login to VM [{}] with new key: {}", node.vm_id, e))?;
+        ssh_client.execute("true")?;
+        repo.save_log(LogEntry::info(
+            cluster_name,
+            format!("Login with new SSH key to VM [{}] works", node.vm_id),
+        ))?;
+    }
+    Ok(())
+}
+
+fn remove_key(
+    username: &str,
+    node: &ClusterNode,
+    login_key: &KeyPair,
+    key_body_to_remove: &str,
+) -> Result<(), String> {
+    let mut ssh_client = ssh_client::Client::new();
+    ssh_client.connect(
+        &node.ip_address,
+        username,
+        &login_key.private_key,
+        &login_key.public_key,
+    )?;
+    ssh_client.execute(
+        format!(
+            "sed -i '\\|{}|d' ~/.ssh/authorized_keys",
+            key_body_to_remove
+        )
+        .as_str(),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use proxmox_client::model::AccessData;
+use proxmox_client::Client;
+use crate::dispatcher::usecase::delete_node_from_cluster;
+use crate::model::{ClusterNode, ClusterNodeType, LogEntry};
+use crate::{distribution, node_pool};
+use crate::Repository;
+
+/// Removes nodes of the pool above its desired count, nodes with the fewest pods go first.
+pub(crate) fn execute(
+    proxmox_client: Arc<Client>,
+    repo: Arc<Repository>,
+    access: AccessData,
+    cluster_name: String,
+    pool_name: String,
+) -> Result<(), String> {
+    let cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    let pool = cluster
+        .node_pools
+        .iter()
+        .find(|i| i.name == pool_name)
+        .ok_or(format!("Cannot find node pool [{}]", pool_name))?;
+    let pool_nodes = cluster
+        .nodes
+        .iter()
+        .filter(|i| i.pool.as_ref() == Some(&pool_name))
+        .collect::<Vec<&ClusterNode>>();
+    let count = pool_nodes
+        .len()
+        .saturating_sub(usize::from(pool.desired_count));
+    if count == 0 {
+        return Ok(());
+    }
+
+    let master_node = cluster
+        .nodes
+        .iter()
+        .find(|i| i.node_type == ClusterNodeType::Master && i.pool.as_ref() != Some(&pool_name))
+        .or(cluster
+            .nodes
+            .iter()
+            .find(|i| i.node_type == ClusterNodeType::Master))
+        .ok_or("Cannot find any master node")?;
+    let mut ssh_client = ssh_client::Client::new();
+    ssh_client.connect(
+        &master_node.ip_address,
+        &cluster.node_username,
+        &cluster.ssh_key.private_key,
+        &cluster.ssh_key.public_key,
+    )?;
+    let pods = ssh_client.execute(
+        format!(
+            "sudo {} get pods -A --no-headers -o custom-columns=NODE:.spec.nodeName",
+            distribution::of(&cluster).kubectl()
+        )
+        .as_str(),
+    )?;
+    let nodes_to_remove = node_pool::nodes_to_remove(
+        &cluster_name,
+        &pool_nodes,
+        &node_pool::pod_counts(&pods),
+        count,
+    );
+
+    for node_name in nodes_to_remove {
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Scale down pool [{}], remove node [{}]", pool_name, node_name),
+        ))?;
+        delete_node_from_cluster::execute(
+            proxmox_client.clone(),
+            repo.clone(),
+            access.clone(),
+            cluster_name.clone(),
+            node_name,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use proxmox_client::model::AccessData;
+use proxmox_client::Client;
+use crate::dispatcher::usecase::add_node_to_cluster;
+use crate::model::{ClusterNodeLock, LogEntry};
+use crate::Repository;
+
+/// Creates nodes of the pool which were saved with the create lock when the pool was scaled up.
+pub(crate) fn execute(
+    proxmox_client: Arc<Client>,
+    repo: Arc<Repository>,
+    access: AccessData,
+    cluster_name: String,
+    pool_name: String,
+) -> Result<(), String> {
+    let cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    let node_names = cluster
+        .nodes
+        .iter()
+        .filter(|i| i.pool.as_ref() == Some(&pool_name))
+        .filter(|i| matches!(i.lock, Some(ClusterNodeLock::Create)))
+        .map(|i| i.name.clone())
+        .collect::<Vec<String>>();
+
+    for node_name in node_names {
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Scale up pool [{}], add node [{}]", pool_name, node_name),
+        ))?;
+        add_node_to_cluster::execute(
+            proxmox_client.clone(),
+            repo.clone(),
+            access.clone(),
+            cluster_name.clone(),
+            node_name.clone(),
+        )?;
+
+        let mut cluster = repo
+            .get_cluster(&cluster_name)?
+            .ok_or("Cannot find cluster")?;
+        cluster
+            .nodes
+            .iter_mut()
+            .filter(|i| i.name == node_name)
+            .for_each(|i| i.lock = None);
+        repo.save_cluster(cluster)?;
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Node [{}] has been created", node_name),
+        ))?;
+    }
+    Ok(())
+}
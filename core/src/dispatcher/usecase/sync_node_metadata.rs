@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use log::{info, warn};
+
+use crate::model::{kube, ClusterNodeType, LogEntry};
+use crate::{distribution, node_metadata};
+use crate::Repository;
+
+/// Applies labels and taints of the nodes which are missing or differ in Kubernetes and
+/// removes the ones Makoon stopped managing. `node_name` limits the sync to one node.
+pub(crate) fn execute(
+    repo: Arc<Repository>,
+    cluster_name: String,
+    node_name: Option<String>,
+    removed_labels: Vec<String>,
+    removed_taints: Vec<String>,
+) -> Result<(), String> {
+    info!("Request to synchronize labels and taints has been received");
+    let cluster = repo
+        .get_cluster(&cluster_name)?
+        .ok_or("Cannot find cluster")?;
+    let master_node = cluster
+        .nodes
+        .iter()
+        .find(|i| i.node_type == ClusterNodeType::Master)
+        .ok_or("Cannot find any master node")?;
+    let mut ssh_client = ssh_client::Client::new();
+    ssh_client.connect(
+        &master_node.ip_address,
+        &cluster.node_username,
+        &cluster.ssh_key.private_key,
+        &cluster.ssh_key.public_key,
+    )?;
+    let kubectl = distribution::of(&cluster).kubectl();
+    let kube_nodes: kube::Nodes = serde_json::from_str(
+        &ssh_client.execute(format!("sudo {} get nodes -o json", kubectl).as_str())?,
+    )
+    .map_err(|e| e.to_string())?;
+
+    for node in cluster
+        .nodes
+        .iter()
+        .filter(|i| i.lock.is_none())
+        .filter(|i| node_name.as_ref().is_none_or(|n| *n == i.name))
+    {
+        let host_name = node.host_name(&cluster_name);
+        let actual = match kube_nodes.items.iter().find(|i| i.metadata.name == host_name) {
+            Some(v) => v,
+            None => {
+                warn!("Cannot find Kubernetes node [{}], it's skipped", host_name);
+                continue;
+            }
+        };
+        let commands = node_metadata::sync_commands(
+            &host_name,
+            &node_metadata::labels(&cluster, node),
+            &node_metadata::taints(&cluster, node),
+            &removed_labels,
+            &removed_taints,
+            actual,
+        );
+        if commands.is_empty() {
+            continue;
+        }
+        repo.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Synchronize labels and taints of node [{}]", host_name),
+        ))?;
+        for command in commands {
+            ssh_client.execute(format!("sudo {} {}", kubectl, command).as_str())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,375 @@
+use serde::Deserialize;
+
+use crate::model::{Cluster, ClusterNode, ClusterNodeType, Distribution};
+use crate::{network, supported};
+
+const K3S_INSTALL_SCRIPT: &str = "curl -sfL https://get.k3s.io";
+const K3S_KUBECONFIG: &str = "/etc/rancher/k3s/k3s.yaml";
+const K3S_API_PORT: u16 = 6443;
+// Join commands accept only alphanumeric tokens
+const JOIN_TOKEN_PLACEHOLDER: &str = "TOKEN";
+const HELM_INSTALL: &str = "curl -fsSL https://raw.githubusercontent.com/helm/helm/main/scripts/get-helm-3 | sudo bash";
+
+/// Steps of the cluster lifecycle which differ between Kubernetes distributions. Methods
+/// return shell commands, the caller runs them over SSH on the node.
+pub(crate) trait KubernetesDistribution {
+    /// Channel used when neither the cluster nor the catalog has a Kubernetes version.
+    fn latest_version(&self) -> &'static str;
+
+    /// Installs Kubernetes on the node. `bootstrap` node starts a new cluster, other nodes
+    /// may be installed by [KubernetesDistribution::join_commands].
+    fn install_commands(&self, cluster: &Cluster, node: &ClusterNode, bootstrap: bool)
+        -> Vec<String>;
+
+    /// Whether Kubernetes runs on the node before it joins the cluster.
+    fn starts_before_join(&self) -> bool;
+
+    fn wait_ready_command(&self) -> String;
+
+    /// Runs on a master node, its output is passed to [KubernetesDistribution::join_commands].
+    fn join_token_command(&self) -> String;
+
+    /// Output of the join token command shown in execution plans, the real token is
+    /// generated during the run.
+    fn join_token_placeholder(&self, master: &ClusterNode) -> String;
+
+    /// Commands joining the node to the cluster of `master`.
+    fn join_commands(
+        &self,
+        cluster: &Cluster,
+        token_output: &str,
+        master: &ClusterNode,
+        node: &ClusterNode,
+    ) -> Result<Vec<String>, String>;
+
+    /// Runs on the removed node before it's deleted from the cluster.
+    fn leave_command(&self) -> Option<String>;
+
+    /// Runs on a master node and deletes the removed node from the cluster.
+    fn remove_node_command(&self, host_name: &str) -> String;
+
+    fn kubeconfig_command(&self) -> String;
+
+    /// Kubeconfig usable from outside of the node read by the kubeconfig command.
+    fn kubeconfig(&self, output: &str, master: &ClusterNode) -> String;
+
+    /// `kubectl` binary, it's run with `sudo`.
+    fn kubectl(&self) -> &'static str;
+
+    /// `helm` binary, it's run with `sudo`.
+    fn helm(&self) -> &'static str;
+}
+
+pub(crate) struct MicroK8s;
+
+pub(crate) struct K3s;
+
+#[derive(Deserialize)]
+struct JoinNode {
+    urls: Vec<String>,
+}
+
+pub(crate) fn of(cluster: &Cluster) -> &'static dyn KubernetesDistribution {
+    match cluster.distribution {
+        Distribution::MicroK8s => &MicroK8s,
+        Distribution::K3s => &K3s,
+    }
+}
+
+/// Features built on MicroK8s tooling (addons, `refresh-certs`) are not available elsewhere.
+pub(crate) fn require_microk8s(cluster: &Cluster, feature: &str) -> Result<(), String> {
+    match cluster.distribution {
+        Distribution::MicroK8s => Ok(()),
+        _ => Err(format!(
+            "{} are not supported by [{}] distribution",
+            feature, cluster.distribution
+        )),
+    }
+}
+
+/// Version of the cluster, the default one of the catalog when it's not set.
+fn version(cluster: &Cluster, distribution: &dyn KubernetesDistribution) -> String {
+    cluster
+        .kube_version
+        .clone()
+        .filter(|i| !i.trim().is_empty())
+        .or_else(|| supported::default_kube_version(&cluster.distribution))
+        .unwrap_or(distribution.latest_version().to_string())
+}
+
+impl KubernetesDistribution for MicroK8s {
+    fn latest_version(&self) -> &'static str {
+        "latest/stable"
+    }
+
+    fn install_commands(
+        &self,
+        cluster: &Cluster,
+        _node: &ClusterNode,
+        _bootstrap: bool,
+    ) -> Vec<String> {
+        let mut result = vec![];
+        if let Some(config) = network::microk8s_launch_config(&cluster.network, &cluster.nodes) {
+            result.push("sudo mkdir -p /var/snap/microk8s/common".to_string());
+            result.push(format!(
+                "printf '%s' '{}' | sudo tee /var/snap/microk8s/common/.microk8s.yaml",
+                config
+            ));
+        }
+        result.push(format!(
+            "sudo snap install microk8s --channel={} --classic",
+            version(cluster, self)
+        ));
+        result
+    }
+
+    fn starts_before_join(&self) -> bool {
+        true
+    }
+
+    fn wait_ready_command(&self) -> String {
+        "sudo microk8s status --wait-ready".to_string()
+    }
+
+    fn join_token_command(&self) -> String {
+        "sudo microk8s add-node --format json".to_string()
+    }
+
+    fn join_token_placeholder(&self, master: &ClusterNode) -> String {
+        let url = format!("{}:25000/{}", master.ip_address.trim(), JOIN_TOKEN_PLACEHOLDER);
+        serde_json::json!({ "urls": [url] }).to_string()
+    }
+
+    fn join_commands(
+        &self,
+        _cluster: &Cluster,
+        token_output: &str,
+        _master: &ClusterNode,
+        node: &ClusterNode,
+    ) -> Result<Vec<String>, String> {
+        let join: JoinNode = serde_json::from_str(token_output).map_err(|e| e.to_string())?;
+        let url = join
+            .urls
+            .first()
+            .ok_or("Join token doesn't have urls to join node".to_string())?;
+        let command = format!("sudo microk8s join {}", url);
+        Ok(vec![match node.node_type {
+            ClusterNodeType::Master => command,
+            ClusterNodeType::Worker => format!("{} --worker", command),
+        }])
+    }
+
+    fn leave_command(&self) -> Option<String> {
+        Some("sudo microk8s leave".to_string())
+    }
+
+    fn remove_node_command(&self, host_name: &str) -> String {
+        format!("sudo microk8s remove-node {}", host_name)
+    }
+
+    fn kubeconfig_command(&self) -> String {
+        "sudo microk8s config".to_string()
+    }
+
+    fn kubeconfig(&self, output: &str, _master: &ClusterNode) -> String {
+        output.to_string()
+    }
+
+    fn kubectl(&self) -> &'static str {
+        "microk8s.kubectl"
+    }
+
+    fn helm(&self) -> &'static str {
+        "microk8s.helm3"
+    }
+}
+
+impl K3s {
+    fn server_command(&self, cluster: &Cluster, environment: &str, args: &[String]) -> String {
+        let args = args
+            .iter()
+            .cloned()
+            .chain(network::k3s_cidr_args(&cluster.network, &cluster.nodes))
+            .collect::<Vec<String>>();
+        format!(
+            "{} | sudo INSTALL_K3S_CHANNEL={}{} sh -s - server {}",
+            K3S_INSTALL_SCRIPT,
+            version(cluster, self),
+            environment,
+            args.join(" ")
+        )
+        .trim_end()
+        .to_string()
+    }
+}
+
+impl KubernetesDistribution for K3s {
+    fn latest_version(&self) -> &'static str {
+        "stable"
+    }
+
+    fn install_commands(
+        &self,
+        cluster: &Cluster,
+        _node: &ClusterNode,
+        bootstrap: bool,
+    ) -> Vec<String> {
+        if !bootstrap {
+            return vec![];
+        }
+        vec![
+            self.server_command(cluster, "", &["--cluster-init".to_string()]),
+            HELM_INSTALL.to_string(),
+        ]
+    }
+
+    fn starts_before_join(&self) -> bool {
+        false
+    }
+
+    fn wait_ready_command(&self) -> String {
+        "sudo timeout 300 sh -c 'until k3s kubectl get nodes > /dev/null 2>&1; do sleep 5; done' && sudo k3s kubectl wait --for=condition=Ready nodes --all --timeout=300s".to_string()
+    }
+
+    fn join_token_command(&self) -> String {
+        "sudo cat /var/lib/rancher/k3s/server/node-token".to_string()
+    }
+
+    fn join_token_placeholder(&self, _master: &ClusterNode) -> String {
+        JOIN_TOKEN_PLACEHOLDER.to_string()
+    }
+
+    fn join_commands(
+        &self,
+        cluster: &Cluster,
+        token_output: &str,
+        master: &ClusterNode,
+        node: &ClusterNode,
+    ) -> Result<Vec<String>, String> {
+        let token = token_output.trim();
+        if token.is_empty() || !token.chars().all(|c| c.is_ascii_alphanumeric() || c == ':') {
+            return Err("Join token of k3s is invalid".to_string());
+        }
+        // Node names of the cluster are in /etc/hosts of every node
+        let environment = format!(
+            " K3S_URL=https://{}:{} K3S_TOKEN={}",
+            master.host_name(&cluster.cluster_name),
+            K3S_API_PORT,
+            token
+        );
+        Ok(match node.node_type {
+            ClusterNodeType::Master => vec![
+                self.server_command(cluster, &environment, &[]),
+                HELM_INSTALL.to_string(),
+            ],
+            ClusterNodeType::Worker => vec![format!(
+                "{} | sudo INSTALL_K3S_CHANNEL={}{} sh -s - agent",
+                K3S_INSTALL_SCRIPT,
+                version(cluster, self),
+                environment
+            )],
+        })
+    }
+
+    fn leave_command(&self) -> Option<String> {
+        None
+    }
+
+    fn remove_node_command(&self, host_name: &str) -> String {
+        format!("sudo k3s kubectl delete node {}", host_name)
+    }
+
+    fn kubeconfig_command(&self) -> String {
+        format!("sudo cat {}", K3S_KUBECONFIG)
+    }
+
+    fn kubeconfig(&self, output: &str, master: &ClusterNode) -> String {
+        let host = if network::is_ipv6(&master.ip_address) {
+            format!("[{}]", master.ip_address.trim())
+        } else {
+            master.ip_address.trim().to_string()
+        };
+        output.replace(
+            &format!("https://127.0.0.1:{}", K3S_API_PORT),
+            &format!("https://{}:{}", host, K3S_API_PORT),
+        )
+    }
+
+    fn kubectl(&self) -> &'static str {
+        "k3s kubectl"
+    }
+
+    fn helm(&self) -> &'static str {
+        "helm --kubeconfig /etc/rancher/k3s/k3s.yaml"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::distribution::{KubernetesDistribution, K3s, MicroK8s};
+    use crate::model::{Cluster, ClusterNodeType, Distribution};
+    use crate::test_fixtures::node;
+
+    fn cluster() -> Cluster {
+        Cluster {
+            cluster_name: "c1".to_string(),
+            kube_version: Some("v1.29".to_string()),
+            distribution: Distribution::K3s,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn k3s_nodes_are_installed_by_join() {
+        let master = node("master-1", 101, "10.0.0.11", ClusterNodeType::Master);
+        let worker = node("worker-1", 102, "10.0.0.11", ClusterNodeType::Worker);
+        assert_eq!(
+            "curl -sfL https://get.k3s.io | sudo INSTALL_K3S_CHANNEL=v1.29 sh -s - server --cluster-init",
+            K3s.install_commands(&cluster(), &master, true)[0]
+        );
+        assert!(K3s.install_commands(&cluster(), &worker, false).is_empty());
+        assert_eq!(
+            vec!["curl -sfL https://get.k3s.io | sudo INSTALL_K3S_CHANNEL=v1.29 K3S_URL=https://c1-master-1:6443 K3S_TOKEN=K10abc::server:def sh -s - agent".to_string()],
+            K3s.join_commands(&cluster(), "K10abc::server:def\n", &master, &worker)
+                .unwrap()
+        );
+        assert!(K3s
+            .join_commands(&cluster(), "abc; reboot", &master, &worker)
+            .is_err());
+        assert_eq!(
+            "server: https://10.0.0.11:6443",
+            K3s.kubeconfig("server: https://127.0.0.1:6443", &master)
+        );
+    }
+
+    #[test]
+    fn microk8s_worker_joins_with_worker_flag() {
+        let output = r#"{"token":"abc","urls":["10.0.0.11:25000/abc"]}"#;
+        assert_eq!(
+            vec!["sudo microk8s join 10.0.0.11:25000/abc --worker".to_string()],
+            MicroK8s
+                .join_commands(
+                    &cluster(),
+                    output,
+                    &node("master-1", 101, "10.0.0.11", ClusterNodeType::Master),
+                    &node("worker-1", 102, "10.0.0.11", ClusterNodeType::Worker)
+                )
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn join_token_placeholder_is_accepted_by_join() {
+        let master = node("master-1", 101, "10.0.0.11", ClusterNodeType::Master);
+        let worker = node("worker-1", 102, "10.0.0.11", ClusterNodeType::Worker);
+        assert_eq!(
+            vec!["sudo microk8s join 10.0.0.11:25000/TOKEN --worker".to_string()],
+            MicroK8s
+                .join_commands(&cluster(), &MicroK8s.join_token_placeholder(&master), &master, &worker)
+                .unwrap()
+        );
+        assert!(K3s
+            .join_commands(&cluster(), &K3s.join_token_placeholder(&master), &master, &worker)
+            .is_ok());
+    }
+}
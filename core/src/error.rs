@@ -1,5 +1,6 @@
 use std::fmt::{Display, Formatter};
 use std::sync::mpsc::SendError;
+use crate::model::ValidationError;
 use crate::repository;
 
 
@@ -8,6 +9,7 @@ pub enum Error {
     ResourceNotFound,
     ResourceAlreadyExists,
     Generic(String),
+    Validation(Vec<ValidationError>),
 }
 
 impl From<repository::Error> for Error {
@@ -62,6 +64,15 @@ impl Display for Error {
             Error::ResourceAlreadyExists => write!(f, "Cluster already exists"),
             Error::Generic(e) => write!(f, "Unknown error: {}", e),
             Error::ResourceNotFound => write!(f, "Cluster not found"),
+            Error::Validation(errors) => write!(
+                f,
+                "Validation failed: {}",
+                errors
+                    .iter()
+                    .map(|i| format!("{}: {}", i.field, i.message))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
         }
     }
 }
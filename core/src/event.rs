@@ -1,5 +1,7 @@
 use proxmox_client::model::AccessData;
 
+use crate::model::{ClusterAddon, ClusterStatus, HardwareProfile};
+
 #[derive(Debug)]
 pub enum Event {
     CreateCluster {
@@ -27,5 +29,79 @@ pub enum Event {
         cores: u16,
         #[doc = "Unit: MiB"]
         memory: u32,
+        hardware: Option<HardwareProfile>,
+    },
+    CreateClusterSnapshot {
+        access: AccessData,
+        cluster_name: String,
+        snapshot_name: String,
+        description: String,
+        freeze_fs: bool,
+        #[doc = "Status the cluster gets back when the snapshot is created"]
+        previous_status: ClusterStatus,
+    },
+    RollbackClusterSnapshot {
+        access: AccessData,
+        cluster_name: String,
+        snapshot_name: String,
+        #[doc = "Status the cluster gets back when the rollback is done"]
+        previous_status: ClusterStatus,
+    },
+    DeleteClusterSnapshot {
+        access: AccessData,
+        cluster_name: String,
+        snapshot_name: String,
+    },
+    BackupCluster {
+        access: AccessData,
+        cluster_name: String,
+    },
+    RestoreClusterBackup {
+        access: AccessData,
+        cluster_name: String,
+        backup_set_id: String,
+        node_name: Option<String>,
+    },
+    ConfigureClusterBackup {
+        access: AccessData,
+        cluster_name: String,
+    },
+    RotateSshKey {
+        access: AccessData,
+        cluster_name: String,
+        #[doc = "Status the cluster gets back when the key is rotated"]
+        previous_status: ClusterStatus,
+    },
+    RefreshCertificates {
+        cluster_name: String,
+        include_ca: bool,
+    },
+    ScaleUpNodePool {
+        access: AccessData,
+        cluster_name: String,
+        pool_name: String,
+    },
+    ScaleDownNodePool {
+        access: AccessData,
+        cluster_name: String,
+        pool_name: String,
+    },
+    Autoscale {
+        access: AccessData,
+        cluster_name: String,
+    },
+    SyncNodeMetadata {
+        cluster_name: String,
+        #[doc = "Node to synchronize, all nodes when empty"]
+        node_name: Option<String>,
+        removed_labels: Vec<String>,
+        removed_taints: Vec<String>,
+        #[doc = "Periodic synchronization, it isn't logged when nothing has changed"]
+        scheduled: bool,
+    },
+    ChangeAddon {
+        cluster_name: String,
+        addon: ClusterAddon,
+        enabled: bool,
     },
 }
@@ -1,8 +1,9 @@
 use log::info;
 use std::string::ToString;
+use std::sync::Arc;
 
-use crate::model::{ClusterNode, ClusterNodeType, ClusterRequest, KeyPair, Network};
-use crate::Error;
+use crate::model::{default_addons, ClusterNode, ClusterNodeType, ClusterRequest, Distribution, HardwareProfile, Ip6Mode, KeyPair, Network, SshKeyType};
+use crate::{ipam, Error, Repository};
 use proxmox_client::model::{NetworkType, StorageContentType};
 use proxmox_client::ClientOperations;
 
@@ -10,11 +11,15 @@ const EMPTY: String = String::new();
 
 pub struct DefaultClusterConfigurationGenerator {
     proxmox_client: ClientOperations,
+    repository: Arc<Repository>,
 }
 
 impl DefaultClusterConfigurationGenerator {
-    pub fn new(proxmox_client: ClientOperations) -> Self {
-        DefaultClusterConfigurationGenerator { proxmox_client }
+    pub fn new(proxmox_client: ClientOperations, repository: Arc<Repository>) -> Self {
+        DefaultClusterConfigurationGenerator {
+            proxmox_client,
+            repository,
+        }
     }
 
     pub fn generate(&self) -> crate::Result<ClusterRequest> {
@@ -27,16 +32,20 @@ impl DefaultClusterConfigurationGenerator {
         let default_network = get_default_network(&self.proxmox_client, &default_proxmox_node)?;
         let default_start_vm_id =
             get_default_start_vm_id(&self.proxmox_client, &default_proxmox_node)?;
+        let default_ip_address = self.get_default_ip_address(&default_network.iface)?;
         Ok(ClusterRequest {
             os_image: get_default_os_image(),
             os_image_storage: default_iso_storage,
-            kube_version: get_default_kube_version(),
+            kube_version: crate::supported::default_kube_version(&Distribution::default())
+                .unwrap_or_default(),
             node: default_proxmox_node,
             cluster_name: EMPTY,
             ssh_key: KeyPair {
                 private_key: EMPTY,
                 public_key: EMPTY,
             },
+            ssh_key_type: SshKeyType::default(),
+            authorized_keys: vec![],
             node_username: "makoon".to_string(),
             node_password: "makoon".to_string(),
             helm_apps: vec![],
@@ -47,10 +56,19 @@ impl DefaultClusterConfigurationGenerator {
                 name: "master-1".to_string(),
                 cores: 2,
                 memory: 2048,
-                ip_address: "".to_string(),
+                ip_address: default_ip_address,
                 storage_pool: default_disk_storage,
                 node_type: ClusterNodeType::Master,
                 lock: None,
+                dhcp: None,
+                interfaces: vec![],
+                ip6_address: None,
+                hardware: HardwareProfile::default(),
+                pool: None,
+                disk_size: None,
+                labels: Default::default(),
+                taints: vec![],
+                host_name: None,
             }],
             network: Network {
                 gateway: default_network.gateway.clone().unwrap_or_default(),
@@ -61,24 +79,53 @@ impl DefaultClusterConfigurationGenerator {
                     .unwrap_or(24),
                 dns: default_network.gateway.unwrap_or_default(),
                 bridge: default_network.iface,
+                dhcp: false,
+                vlan_tag: None,
+                mtu: None,
+                firewall: false,
+                interfaces: vec![],
+                ip6_mode: Ip6Mode::Disabled,
+                ip6_gateway: None,
+                ip6_prefix_length: None,
+                dns_servers: vec![],
+                ip6_cluster_cidr: None,
+                ip6_service_cidr: None,
             },
+            cloud_init: None,
+            addons: default_addons(),
+            distribution: Default::default(),
+            resource_pool: None,
         })
     }
+
+    /// Proposes the first free address of the bridge IP pool, address isn't reserved until
+    /// the cluster is created. Empty when there is no pool or it's exhausted.
+    fn get_default_ip_address(&self, bridge: &str) -> crate::Result<String> {
+        let pool = match self
+            .repository
+            .ip_pools()?
+            .into_iter()
+            .find(|i| i.bridge == bridge)
+        {
+            Some(v) => v,
+            None => return Ok(String::new()),
+        };
+        let used = ipam::used_addresses(
+            &self.repository.get_clusters()?,
+            &self.repository.ip_allocations()?,
+        );
+        Ok(ipam::next_free_addresses(&pool, &used, 1)
+            .ok()
+            .and_then(|i| i.first().map(|i| i.to_string()))
+            .unwrap_or_default())
+    }
 }
 
 fn get_default_os_image() -> String {
     crate::supported::os_images()
-        .values()
-        .next()
-        .unwrap()
-        .to_string()
-}
-
-fn get_default_kube_version() -> String {
-    crate::supported::kube_versions()
         .first()
+        .map(|i| i.url.clone())
         .unwrap()
-        .to_string()
 }
 
 fn get_default_start_vm_id(proxmox_client: &ClientOperations, node: &str) -> crate::Result<u32> {
@@ -0,0 +1,117 @@
+use proxmox_client::model::{ParamBuilder, ScsiHw};
+
+use crate::model::{HardwareProfile, ScsiController};
+
+const MAX_SOCKETS: u16 = 4;
+
+impl From<&ScsiController> for ScsiHw {
+    fn from(value: &ScsiController) -> Self {
+        match value {
+            ScsiController::VirtioScsiPci => ScsiHw::VirtioScsiPci,
+            ScsiController::VirtioScsiSingle => ScsiHw::VirtioScsiSingle,
+            ScsiController::Lsi => ScsiHw::Lsi,
+            ScsiController::Megasas => ScsiHw::Megasas,
+            ScsiController::Pvscsi => ScsiHw::Pvscsi,
+        }
+    }
+}
+
+/// Checks the profile of the node with `memory` MiB, values are passed to Proxmox as they are.
+pub(crate) fn validate(profile: &HardwareProfile, memory: u32) -> Result<(), String> {
+    for value in [&profile.cpu_type, &profile.machine].into_iter().flatten() {
+        let valid = !value.is_empty()
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' || c == '+');
+        if !valid {
+            return Err(format!("Invalid CPU or machine type [{}]", value));
+        }
+    }
+    if let Some(sockets) = profile.sockets {
+        if sockets == 0 || sockets > MAX_SOCKETS {
+            return Err(format!("Sockets [{}] is out of range 1-{}", sockets, MAX_SOCKETS));
+        }
+    }
+    if let Some(balloon) = profile.balloon {
+        if balloon > memory {
+            return Err(format!(
+                "Balloon minimum [{}] MiB is greater than memory [{}] MiB",
+                balloon, memory
+            ));
+        }
+    }
+    if profile.disk_iothread && profile.scsi_controller != ScsiController::VirtioScsiSingle {
+        return Err("Disk IO thread requires VirtioScsiSingle controller".to_string());
+    }
+    Ok(())
+}
+
+/// Appends options of the system disk to its `scsi0` definition.
+pub(crate) fn add_disk_params(builder: &mut ParamBuilder, profile: &HardwareProfile) {
+    if let Some(cache) = profile.disk_cache.as_ref() {
+        builder.add_param("cache", cache.to_string().as_str());
+    }
+    if profile.disk_discard {
+        builder.add_param("discard", "on");
+    }
+    if profile.disk_iothread {
+        builder.add_param("iothread", "1");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proxmox_client::model::ParamBuilder;
+
+    use crate::hardware::{add_disk_params, validate};
+    use crate::model::{DiskCache, HardwareProfile, ScsiController};
+
+    #[test]
+    fn disk_params_are_appended() {
+        let profile = HardwareProfile {
+            disk_cache: Some(DiskCache::Writeback),
+            disk_discard: true,
+            disk_iothread: true,
+            scsi_controller: ScsiController::VirtioScsiSingle,
+            ..Default::default()
+        };
+        let mut builder = ParamBuilder::default();
+        builder.add_param_with_separator("local-lvm", "0", ":");
+        add_disk_params(&mut builder, &profile);
+        assert_eq!(
+            "local-lvm:0,cache=writeback,discard=on,iothread=1",
+            builder.build()
+        );
+    }
+
+    #[test]
+    fn validate_rejects_invalid_profile() {
+        let iothread = HardwareProfile {
+            disk_iothread: true,
+            ..Default::default()
+        };
+        assert!(validate(&iothread, 2048).is_err());
+
+        let balloon = HardwareProfile {
+            balloon: Some(4096),
+            ..Default::default()
+        };
+        assert!(validate(&balloon, 2048).is_err());
+
+        let cpu = HardwareProfile {
+            cpu_type: Some("host,flags=+aes".to_string()),
+            ..Default::default()
+        };
+        assert!(validate(&cpu, 2048).is_err());
+
+        let valid = HardwareProfile {
+            cpu_type: Some("host".to_string()),
+            sockets: Some(2),
+            numa: true,
+            balloon: Some(1024),
+            machine: Some("q35".to_string()),
+            ..Default::default()
+        };
+        assert!(validate(&valid, 2048).is_ok());
+    }
+}
@@ -0,0 +1,124 @@
+use crate::model::helm::InstalledRelease;
+use crate::model::kube::Item;
+use crate::model::{ClusterNodeType, HelmApp};
+use crate::tags;
+
+const MICROK8S_RUNNING: &str = "microk8s is running";
+const WORKER_LABEL: &str = "node.kubernetes.io/microk8s-worker";
+
+pub(crate) const STATUS_COMMAND: &str = "sudo microk8s status";
+
+pub(crate) fn check_running(status_output: &str) -> Result<(), String> {
+    match status_output.contains(MICROK8S_RUNNING) {
+        true => Ok(()),
+        false => Err(format!("MicroK8s is not running: {}", status_output.trim())),
+    }
+}
+
+/// Nodes joined with `--worker` are labeled by MicroK8s, others are part of the control plane.
+pub(crate) fn node_type(item: &Item) -> ClusterNodeType {
+    match item.metadata.labels.contains_key(WORKER_LABEL) {
+        true => ClusterNodeType::Worker,
+        false => ClusterNodeType::Master,
+    }
+}
+
+pub(crate) fn internal_ip(item: &Item) -> Option<&str> {
+    item.status
+        .addresses
+        .iter()
+        .find(|i| i.address_type == "InternalIP")
+        .map(|i| i.address.as_str())
+}
+
+/// MicroK8s channel of the kubelet version, e.g. `v1.29.4` is `1.29/stable`.
+pub(crate) fn kube_version(kubelet_version: &str) -> Option<String> {
+    let mut parts = kubelet_version.trim().trim_start_matches('v').split('.');
+    let major = parts.next().filter(|i| i.parse::<u16>().is_ok())?;
+    let minor = parts.next().filter(|i| i.parse::<u16>().is_ok())?;
+    Some(format!("{}.{}/stable", major, minor))
+}
+
+/// Node name and the Kubernetes host name kept on the node when it isn't `<cluster>-<node>`.
+pub(crate) fn node_name(cluster_name: &str, host_name: &str) -> (String, Option<String>) {
+    match host_name
+        .strip_prefix(&format!("{}-", cluster_name))
+        .filter(|i| !i.is_empty())
+    {
+        Some(v) => (v.to_string(), None),
+        None => (
+            host_name.split('.').next().unwrap_or(host_name).to_string(),
+            Some(host_name.to_string()),
+        ),
+    }
+}
+
+/// Helm app of the installed release, the chart repository isn't known so the chart is
+/// reinstalled from repositories already added on the node.
+pub(crate) fn helm_app(release: &InstalledRelease) -> HelmApp {
+    let (chart_name, chart_version) = match release.chart.rsplit_once('-') {
+        Some((name, version)) if version.starts_with(|c: char| c.is_ascii_digit()) => {
+            (name.to_string(), version.to_string())
+        }
+        _ => (release.chart.clone(), String::new()),
+    };
+    HelmApp {
+        id: uuid::Uuid::new_v4().to_string(),
+        chart_name,
+        chart_version,
+        repository: String::new(),
+        release_name: release.name.clone(),
+        namespace: release.namespace.clone(),
+        values: String::new(),
+        wait: false,
+    }
+}
+
+/// Existing tags of the VM with the Makoon tags added.
+pub(crate) fn merge_tags(existing: Option<&str>, makoon_tags: &str) -> String {
+    let mut result = tags::parse(existing);
+    for tag in tags::parse(Some(makoon_tags)) {
+        if !result.contains(&tag) {
+            result.push(tag);
+        }
+    }
+    result.join(";")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::import::{helm_app, kube_version, merge_tags, node_name};
+    use crate::model::helm::InstalledRelease;
+    use crate::model::AppStatusType;
+
+    #[test]
+    fn node_names_and_version_follow_makoon_conventions() {
+        assert_eq!(Some("1.29/stable".to_string()), kube_version("v1.29.4"));
+        assert_eq!(None, kube_version("unknown"));
+        assert_eq!(("master-1".to_string(), None), node_name("prod", "prod-master-1"));
+        assert_eq!(
+            ("k8s-master".to_string(), Some("k8s-master.lan".to_string())),
+            node_name("prod", "k8s-master.lan")
+        );
+        assert_eq!(
+            "web;makoon;makoon-prod;master",
+            merge_tags(Some("web,makoon"), "makoon;makoon-prod;master")
+        );
+    }
+
+    #[test]
+    fn helm_release_is_split_to_chart_and_version() {
+        let release = InstalledRelease {
+            name: "ingress".to_string(),
+            namespace: "ingress".to_string(),
+            revision: "1".to_string(),
+            updated: String::new(),
+            status: AppStatusType::Deployed,
+            chart: "ingress-nginx-4.10.1".to_string(),
+            app_version: "1.10.1".to_string(),
+        };
+        let app = helm_app(&release);
+        assert_eq!("ingress-nginx", app.chart_name);
+        assert_eq!("4.10.1", app.chart_version);
+    }
+}
@@ -0,0 +1,249 @@
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
+
+use crate::model::{Cluster, ClusterNode, IpAllocation, IpPool, Network};
+use crate::{Error, Repository};
+
+pub(crate) fn parse_range(pool: &IpPool) -> Result<(Ipv4Addr, Ipv4Addr), String> {
+    let start = Ipv4Addr::from_str(pool.range_start.trim())
+        .map_err(|_| format!("Invalid IP address [{}]", pool.range_start))?;
+    let end = Ipv4Addr::from_str(pool.range_end.trim())
+        .map_err(|_| format!("Invalid IP address [{}]", pool.range_end))?;
+    if u32::from(start) > u32::from(end) {
+        return Err(format!(
+            "Range start [{}] is after range end [{}]",
+            start, end
+        ));
+    }
+    Ok((start, end))
+}
+
+/// Checks the range of a pool which is being saved, it has to be inside the subnet of the pool.
+pub(crate) fn validate_pool(pool: &IpPool) -> Result<(), String> {
+    let (start, end) = parse_range(pool)?;
+    let gateway = Ipv4Addr::from_str(pool.gateway.trim())
+        .map_err(|_| format!("Invalid gateway [{}]", pool.gateway))?;
+    if pool.subnet_mask == 0 || pool.subnet_mask > 32 {
+        return Err(format!("Invalid subnet mask [{}]", pool.subnet_mask));
+    }
+    let mask = u32::MAX << (32 - pool.subnet_mask);
+    for address in [start, end] {
+        if u32::from(address) & mask != u32::from(gateway) & mask {
+            return Err(format!(
+                "IP address [{}] is outside of subnet [{}/{}]",
+                address, gateway, pool.subnet_mask
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// All addresses taken by Makoon: the allocation table plus addresses of cluster nodes,
+/// which covers clusters created before the allocation table existed. IPv6 addresses
+/// of dual-stack nodes are included as well.
+pub(crate) fn used_addresses(clusters: &[Cluster], allocations: &[IpAllocation]) -> Vec<IpAddr> {
+    let mut result: Vec<IpAddr> = allocations
+        .iter()
+        .map(|i| i.ip_address.as_str())
+        .chain(clusters.iter().flat_map(|c| {
+            c.nodes.iter().flat_map(|i| {
+                std::iter::once(i.ip_address.as_str()).chain(i.ip6_address.as_deref())
+            })
+        }))
+        .filter_map(|i| IpAddr::from_str(i.trim()).ok())
+        .collect();
+    result.sort();
+    result.dedup();
+    result
+}
+
+pub(crate) fn next_free_addresses(
+    pool: &IpPool,
+    used: &[IpAddr],
+    count: usize,
+) -> Result<Vec<Ipv4Addr>, String> {
+    let (start, end) = parse_range(pool)?;
+    let result: Vec<Ipv4Addr> = (u32::from(start)..=u32::from(end))
+        .map(Ipv4Addr::from)
+        .filter(|i| !used.contains(&IpAddr::V4(*i)))
+        .take(count)
+        .collect();
+    if result.len() < count {
+        return Err(format!(
+            "IP pool of bridge [{}] has no free address left",
+            pool.bridge
+        ));
+    }
+    Ok(result)
+}
+
+/// Finds addresses which are used by more than one node of the given list.
+pub(crate) fn duplicates(addresses: &[&str]) -> Vec<String> {
+    let mut result: Vec<String> = addresses
+        .iter()
+        .enumerate()
+        .filter(|(index, address)| addresses[..*index].contains(address))
+        .map(|(_, address)| address.to_string())
+        .collect();
+    result.sort();
+    result.dedup();
+    result
+}
+
+/// Checks requested addresses of the nodes, assigns free addresses of the bridge IP pool to
+/// nodes without one and records the allocations.
+pub(crate) fn assign_ip_addresses(
+    repository: &Repository,
+    cluster_name: &str,
+    network: &Network,
+    nodes: &mut [ClusterNode],
+) -> crate::Result<()> {
+    pick_ip_addresses(repository, network, nodes)?;
+    repository.save_ip_allocations(
+        nodes
+            .iter()
+            .filter(|i| !network.is_dhcp_node(i))
+            .map(|i| IpAllocation {
+                ip_address: i.ip_address.trim().to_string(),
+                bridge: network.bridge.clone(),
+                cluster_name: cluster_name.to_string(),
+                node_name: i.name.clone(),
+            })
+            .collect(),
+    )?;
+    Ok(())
+}
+
+/// [assign_ip_addresses] without recording the allocations, used by dry runs.
+pub(crate) fn pick_ip_addresses(
+    repository: &Repository,
+    network: &Network,
+    nodes: &mut [ClusterNode],
+) -> crate::Result<()> {
+    let bridge = network.bridge.as_str();
+    let mut nodes = nodes
+        .iter_mut()
+        .filter(|i| !network.is_dhcp_node(i))
+        .collect::<Vec<&mut ClusterNode>>();
+    let mut used = used_addresses(&repository.get_clusters()?, &repository.ip_allocations()?);
+
+    let requested = nodes
+        .iter()
+        .map(|i| i.ip_address.trim())
+        .filter(|i| !i.is_empty())
+        .chain(nodes.iter().filter_map(|i| i.ip6_address.as_deref().map(|i| i.trim())))
+        .filter(|i| !i.is_empty())
+        .collect::<Vec<&str>>();
+    if let Some(duplicate) = duplicates(&requested).first() {
+        return Err(Error::Generic(format!(
+            "IP address [{}] is used by more than one node",
+            duplicate
+        )));
+    }
+    for address in requested {
+        let address = IpAddr::from_str(address)
+            .map_err(|_| Error::Generic(format!("Invalid IP address [{}]", address)))?;
+        if used.contains(&address) {
+            return Err(Error::Generic(format!(
+                "IP address [{}] is already used",
+                address
+            )));
+        }
+        used.push(address);
+    }
+
+    let missing = nodes
+        .iter()
+        .filter(|i| i.ip_address.trim().is_empty())
+        .count();
+    if missing > 0 {
+        let pool = repository
+            .ip_pools()?
+            .into_iter()
+            .find(|i| i.bridge == bridge)
+            .ok_or(Error::Generic(format!(
+                "Node IP address is empty and there is no IP pool for bridge [{}]",
+                bridge
+            )))?;
+        let mut free = next_free_addresses(&pool, &used, missing)?.into_iter();
+        for node in nodes
+            .iter_mut()
+            .filter(|i| i.ip_address.trim().is_empty())
+        {
+            node.ip_address = free.next().map(|i| i.to_string()).unwrap_or_default();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{IpAddr, Ipv4Addr};
+    use crate::ipam::{duplicates, next_free_addresses, parse_range, validate_pool};
+    use crate::model::IpPool;
+
+    fn pool(start: &str, end: &str) -> IpPool {
+        IpPool {
+            bridge: "vmbr0".to_string(),
+            range_start: start.to_string(),
+            range_end: end.to_string(),
+            gateway: "10.0.0.1".to_string(),
+            subnet_mask: 24,
+        }
+    }
+
+    #[test]
+    fn next_free_addresses_skips_used() {
+        let used = vec![
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 10)),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 12)),
+        ];
+        let result = next_free_addresses(&pool("10.0.0.10", "10.0.0.20"), &used, 2).unwrap();
+        assert_eq!(
+            vec![Ipv4Addr::new(10, 0, 0, 11), Ipv4Addr::new(10, 0, 0, 13)],
+            result
+        );
+    }
+
+    #[test]
+    fn next_free_addresses_crosses_octet_boundary() {
+        let result = next_free_addresses(&pool("10.0.0.255", "10.0.1.1"), &[], 2).unwrap();
+        assert_eq!(
+            vec![Ipv4Addr::new(10, 0, 0, 255), Ipv4Addr::new(10, 0, 1, 0)],
+            result
+        );
+    }
+
+    #[test]
+    fn next_free_addresses_fails_on_exhausted_pool() {
+        let used = vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 10))];
+        assert!(next_free_addresses(&pool("10.0.0.10", "10.0.0.11"), &used, 2).is_err());
+    }
+
+    #[test]
+    fn parse_range_rejects_reversed_range() {
+        assert!(parse_range(&pool("10.0.0.20", "10.0.0.10")).is_err());
+        assert!(parse_range(&pool("10.0.0.x", "10.0.0.10")).is_err());
+    }
+
+    #[test]
+    fn validate_pool_requires_range_inside_subnet() {
+        assert!(validate_pool(&pool("10.0.0.10", "10.0.0.20")).is_ok());
+        assert!(validate_pool(&pool("10.0.0.10", "10.0.1.20")).is_err());
+
+        let mut pool = pool("10.0.0.10", "10.0.1.20");
+        pool.subnet_mask = 16;
+        assert!(validate_pool(&pool).is_ok());
+        pool.subnet_mask = 33;
+        assert!(validate_pool(&pool).is_err());
+        pool.subnet_mask = 16;
+        pool.gateway = String::new();
+        assert!(validate_pool(&pool).is_err());
+    }
+
+    #[test]
+    fn duplicates_are_reported_once() {
+        let result = duplicates(&["10.0.0.1", "10.0.0.2", "10.0.0.1", "10.0.0.1"]);
+        assert_eq!(vec!["10.0.0.1".to_string()], result);
+    }
+}
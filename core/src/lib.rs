@@ -1,10 +1,31 @@
+mod addons;
+mod agent;
+mod autoscaler;
+mod certificates;
+mod cloud_init;
 mod dispatcher;
+mod distribution;
 mod error;
 mod event;
 mod generator;
+mod hardware;
+mod import;
+mod ipam;
+mod keys;
+mod network;
+mod node_metadata;
+mod node_pool;
 mod operator;
+mod os_image;
+mod preflight;
+mod recovery;
 mod repository;
 mod repository_json;
+mod tags;
+#[cfg(test)]
+mod test_fixtures;
+mod validation;
+pub mod backup;
 pub mod model;
 pub mod supported;
 
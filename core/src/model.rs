@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
@@ -33,10 +34,11 @@ pub struct ClusterResource {
 }
 
 #[typeshare]
-#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub enum ClusterNodeType {
     Master,
+    #[default]
     Worker,
 }
 
@@ -59,7 +61,71 @@ pub enum ClusterNodeLock {
 }
 
 #[typeshare]
-#[derive(Clone, Serialize, Deserialize, Debug)]
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ScsiController {
+    #[default]
+    VirtioScsiPci,
+    VirtioScsiSingle,
+    Lsi,
+    Megasas,
+    Pvscsi,
+}
+
+#[typeshare]
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DiskCache {
+    None,
+    Writethrough,
+    Writeback,
+    Directsync,
+    Unsafe,
+}
+
+impl Display for DiskCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiskCache::None => write!(f, "none"),
+            DiskCache::Writethrough => write!(f, "writethrough"),
+            DiskCache::Writeback => write!(f, "writeback"),
+            DiskCache::Directsync => write!(f, "directsync"),
+            DiskCache::Unsafe => write!(f, "unsafe"),
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HardwareProfile {
+    #[doc = "Emulated CPU type, e.g. 'host' or 'x86-64-v2-AES', Proxmox default when empty"]
+    #[serde(default)]
+    pub cpu_type: Option<String>,
+    #[serde(default)]
+    pub sockets: Option<u16>,
+    #[serde(default)]
+    pub numa: bool,
+    #[doc = "Minimum memory of the balloon device, 0 disables ballooning. Unit: MiB"]
+    #[serde(default)]
+    pub balloon: Option<u32>,
+    #[doc = "QEMU machine type, e.g. 'q35'"]
+    #[serde(default)]
+    pub machine: Option<String>,
+    #[serde(default)]
+    pub scsi_controller: ScsiController,
+    #[doc = "Options of the system disk, they are applied when the VM is created"]
+    #[serde(default)]
+    pub disk_cache: Option<DiskCache>,
+    #[serde(default)]
+    pub disk_discard: bool,
+    #[doc = "Requires VirtioScsiSingle controller"]
+    #[serde(default)]
+    pub disk_iothread: bool,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ClusterNode {
     pub vm_id: u32,
@@ -67,10 +133,113 @@ pub struct ClusterNode {
     pub cores: u16,
     #[doc="Unit: MiB"]
     pub memory: u32,
+    #[doc = "Address Makoon connects to, IPv4 or IPv6 for IPv6-only network"]
     pub ip_address: String,
     pub storage_pool: String,
     pub node_type: ClusterNodeType,
     pub lock: Option<ClusterNodeLock>,
+    #[doc = "Overrides DHCP mode of the cluster network for this node"]
+    #[serde(default)]
+    pub dhcp: Option<bool>,
+    #[doc = "Per-node settings of additional interfaces, matched by position with Network.interfaces"]
+    #[serde(default)]
+    pub interfaces: Vec<NodeNetworkInterface>,
+    #[doc = "IPv6 address of dual-stack node, IPv6-only node uses ipAddress instead"]
+    #[serde(default)]
+    pub ip6_address: Option<String>,
+    #[serde(default)]
+    pub hardware: HardwareProfile,
+    #[doc = "Node pool the node belongs to, empty for nodes managed one by one"]
+    #[serde(default)]
+    pub pool: Option<String>,
+    #[doc = "Overrides disk size of the cluster. Unit: GiB"]
+    #[serde(default)]
+    pub disk_size: Option<u32>,
+    #[doc = "Kubernetes labels of the node, added to the default and pool labels"]
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[doc = "Kubernetes taints of the node, e.g. 'dedicated=db:NoSchedule'"]
+    #[serde(default)]
+    pub taints: Vec<String>,
+    #[doc = "Kubernetes node name of imported node which isn't named '<cluster>-<node>'"]
+    #[serde(default)]
+    pub host_name: Option<String>,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NodePool {
+    pub name: String,
+    pub node_type: ClusterNodeType,
+    pub cores: u16,
+    #[doc = "Unit: MiB"]
+    pub memory: u32,
+    #[doc = "Overrides disk size of the cluster. Unit: GiB"]
+    #[serde(default)]
+    pub disk_size: Option<u32>,
+    pub storage_pool: String,
+    #[doc = "Kubernetes labels of the pool nodes"]
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[doc = "Kubernetes taints of the pool nodes, e.g. 'dedicated=gpu:NoSchedule'"]
+    #[serde(default)]
+    pub taints: Vec<String>,
+    pub desired_count: u16,
+    #[serde(default)]
+    pub hardware: HardwareProfile,
+}
+
+#[typeshare]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterAddon {
+    #[doc = "MicroK8s addon name, e.g. 'metallb'"]
+    pub name: String,
+    #[doc = "Arguments passed after ':', e.g. '10.0.0.100-10.0.0.120' for metallb"]
+    #[serde(default)]
+    pub arguments: Option<String>,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonStatus {
+    pub name: String,
+    pub repository: String,
+    pub description: String,
+    pub enabled: bool,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoscalingSettings {
+    pub enabled: bool,
+    #[doc = "Worker node pool which is scaled"]
+    pub pool_name: String,
+    pub min_nodes: u16,
+    pub max_nodes: u16,
+    #[doc = "Time a node has to stay idle before it's removed, also the minimum time after the last scale up. Unit: minutes"]
+    pub cooldown: u32,
+    #[doc = "Node is idle when its CPU and memory requests are below this percentage"]
+    pub scale_down_utilization: u8,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoscalingState {
+    #[serde(default)]
+    pub last_scale_up: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub last_scale_down: Option<NaiveDateTime>,
+    #[doc = "Nodes of the pool which are idle, with the time of the first idle check"]
+    #[serde(default)]
+    pub idle_since: HashMap<String, NaiveDateTime>,
+    #[doc = "Reason why the pool cannot be scaled up, e.g. Proxmox node is out of memory"]
+    #[serde(default)]
+    pub blocked_reason: Option<String>,
 }
 
 #[typeshare]
@@ -81,6 +250,115 @@ pub struct Network {
     pub subnet_mask: u8,
     pub dns: String,
     pub bridge: String,
+    #[doc = "Nodes get address from DHCP, static address, gateway and subnet mask are not used"]
+    #[serde(default)]
+    pub dhcp: bool,
+    #[serde(default)]
+    pub vlan_tag: Option<u16>,
+    #[serde(default)]
+    pub mtu: Option<u16>,
+    #[serde(default)]
+    pub firewall: bool,
+    #[doc = "Additional interfaces of node VMs, mapped to net1, net2, ..."]
+    #[serde(default)]
+    pub interfaces: Vec<NetworkInterface>,
+    #[doc = "IPv6 configuration of the primary interface when node address is IPv4 (dual-stack)"]
+    #[serde(default)]
+    pub ip6_mode: Ip6Mode,
+    #[serde(default)]
+    pub ip6_gateway: Option<String>,
+    #[serde(default)]
+    pub ip6_prefix_length: Option<u8>,
+    #[doc = "Additional DNS servers, e.g. IPv6 resolvers"]
+    #[serde(default)]
+    pub dns_servers: Vec<String>,
+    #[doc = "IPv6 pod CIDR, cluster is configured as dual-stack or IPv6-only when set"]
+    #[serde(default)]
+    pub ip6_cluster_cidr: Option<String>,
+    #[doc = "IPv6 service CIDR, cluster is configured as dual-stack or IPv6-only when set"]
+    #[serde(default)]
+    pub ip6_service_cidr: Option<String>,
+}
+
+#[typeshare]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Ip6Mode {
+    #[default]
+    Disabled,
+    #[doc = "Address is taken from ClusterNode.ip6Address"]
+    Static,
+    #[doc = "Stateless autoconfiguration (SLAAC)"]
+    Auto,
+    Dhcp,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkInterface {
+    pub bridge: String,
+    #[serde(default)]
+    pub vlan_tag: Option<u16>,
+    #[serde(default)]
+    pub mtu: Option<u16>,
+    #[serde(default)]
+    pub firewall: bool,
+    #[doc = "Nodes get address from DHCP, otherwise address is taken from node interfaces"]
+    #[serde(default)]
+    pub dhcp: bool,
+    #[serde(default)]
+    pub subnet_mask: Option<u8>,
+    #[serde(default)]
+    pub gateway: Option<String>,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeNetworkInterface {
+    #[doc = "Static address of the interface, required when the interface doesn't use DHCP"]
+    #[serde(default)]
+    pub ip_address: String,
+    #[serde(default)]
+    pub bridge: Option<String>,
+    #[serde(default)]
+    pub vlan_tag: Option<u16>,
+    #[serde(default)]
+    pub mtu: Option<u16>,
+    #[serde(default)]
+    pub dhcp: Option<bool>,
+    #[doc = "Overrides Proxmox firewall setting of the interface"]
+    #[serde(default)]
+    pub firewall: Option<bool>,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IpPool {
+    #[doc = "Bridge the pool belongs to, one pool per bridge"]
+    pub bridge: String,
+    #[doc = "First address of the range (IPv4)"]
+    pub range_start: String,
+    #[doc = "Last address of the range (IPv4), inclusive"]
+    pub range_end: String,
+    #[doc = "Gateway of the bridge subnet, the range has to be inside of it"]
+    #[serde(default)]
+    pub gateway: String,
+    #[doc = "Subnet mask of the bridge subnet, e.g. 24"]
+    #[serde(default)]
+    pub subnet_mask: u8,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IpAllocation {
+    pub ip_address: String,
+    pub bridge: String,
+    pub cluster_name: String,
+    pub node_name: String,
 }
 
 #[typeshare]
@@ -91,6 +369,116 @@ pub struct KeyPair {
     pub public_key: String,
 }
 
+#[typeshare]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Distribution {
+    #[default]
+    MicroK8s,
+    K3s,
+}
+
+impl Display for Distribution {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Distribution::MicroK8s => write!(f, "MicroK8s"),
+            Distribution::K3s => write!(f, "k3s"),
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OsFamily {
+    #[default]
+    Ubuntu,
+    Debian,
+}
+
+#[typeshare]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChecksumAlgorithm::Md5 => write!(f, "md5"),
+            ChecksumAlgorithm::Sha1 => write!(f, "sha1"),
+            ChecksumAlgorithm::Sha224 => write!(f, "sha224"),
+            ChecksumAlgorithm::Sha256 => write!(f, "sha256"),
+            ChecksumAlgorithm::Sha384 => write!(f, "sha384"),
+            ChecksumAlgorithm::Sha512 => write!(f, "sha512"),
+        }
+    }
+}
+
+/// Cloud image which can be used for the cluster nodes.
+#[typeshare]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OsImage {
+    pub name: String,
+    pub url: String,
+    #[doc = "File name on Proxmox storage (.img or .iso), taken from the URL when empty"]
+    #[serde(default)]
+    pub file_name: Option<String>,
+    #[serde(default)]
+    pub os_family: OsFamily,
+    #[doc = "Expected checksum of the downloaded file"]
+    #[serde(default)]
+    pub checksum: Option<String>,
+    #[serde(default)]
+    pub checksum_algorithm: Option<ChecksumAlgorithm>,
+    #[doc = "File with checksums of the image directory (e.g. SHA256SUMS), used when checksum is empty"]
+    #[serde(default)]
+    pub checksum_url: Option<String>,
+    #[doc = "Downloaded image older than this is downloaded again, for images published under a moving URL"]
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+}
+
+#[typeshare]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KubeVersion {
+    #[doc = "Snap channel of MicroK8s, release channel of k3s"]
+    pub version: String,
+    #[doc = "Version is kept for existing clusters, it shouldn't be used for new ones"]
+    #[serde(default)]
+    pub deprecated: bool,
+}
+
+/// Versions and images offered for new clusters, see [crate::supported::catalog].
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Catalog {
+    #[serde(default)]
+    pub microk8s_versions: Vec<KubeVersion>,
+    #[serde(default)]
+    pub k3s_versions: Vec<KubeVersion>,
+    #[serde(default)]
+    pub os_images: Vec<OsImage>,
+}
+
+#[typeshare]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SshKeyType {
+    #[default]
+    Ed25519,
+    Rsa,
+}
+
 #[typeshare]
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
@@ -101,6 +489,10 @@ pub enum ClusterStatus {
     Sync,
     OutOfSync,
     Destroying,
+    #[doc = "Snapshot is being created or the cluster is being rolled back"]
+    Snapshotting,
+    #[doc = "SSH key of the nodes is being replaced"]
+    RotatingSshKey,
     Error,
 }
 
@@ -122,6 +514,20 @@ pub struct LogEntry {
     pub level: ActionLogLevel,
 }
 
+impl ClusterNode {
+    pub fn host_name(&self, cluster_name: &str) -> String {
+        self.host_name
+            .clone()
+            .unwrap_or(format!("{}-{}", cluster_name, self.name))
+    }
+}
+
+impl Network {
+    pub fn is_dhcp_node(&self, node: &ClusterNode) -> bool {
+        node.dhcp.unwrap_or(self.dhcp)
+    }
+}
+
 impl LogEntry {
     pub fn info<T>(cluster_name: &str, message: T) -> Self
         where
@@ -147,6 +553,67 @@ impl LogEntry {
     }
 }
 
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupMode {
+    #[default]
+    Snapshot,
+    Suspend,
+    Stop,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSettings {
+    #[doc = "Proxmox storage with backup content, can be Proxmox Backup Server storage"]
+    pub storage: String,
+    #[doc = "Number of backups kept per VM"]
+    pub keep_last: u32,
+    #[doc = "Proxmox calendar event, e.g. 'daily' or 'sat 02:00', empty for manual backups only"]
+    #[serde(default)]
+    pub schedule: String,
+    #[serde(default)]
+    pub mode: BackupMode,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudInitSettings {
+    #[doc = "Proxmox storage with snippets content, the documents are uploaded there"]
+    pub storage: String,
+    #[doc = "cloud-init user-data (#cloud-config, #! script, ...), merged with the user-data of Makoon"]
+    #[serde(default)]
+    pub user_data: Option<String>,
+    #[doc = "cloud-init vendor-data, it's overridden by user-data"]
+    #[serde(default)]
+    pub vendor_data: Option<String>,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupVolume {
+    pub vm_id: u32,
+    pub volid: String,
+    #[doc = "Unit: MiB"]
+    pub size: u32,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSet {
+    pub id: String,
+    pub notes: String,
+    pub created: NaiveDateTime,
+    pub volumes: Vec<BackupVolume>,
+    #[doc = "Backup set contains every node of the cluster"]
+    pub complete: bool,
+}
+
 #[typeshare]
 #[derive(Clone, Serialize, Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
@@ -161,6 +628,12 @@ pub struct Cluster {
     pub kube_version: Option<String>,
     pub cluster_config: String,
     pub ssh_key: KeyPair,
+    #[doc = "Type of the key pair generated for the cluster, not used when own key pair was provided"]
+    #[serde(default)]
+    pub ssh_key_type: SshKeyType,
+    #[doc = "Additional public keys (OpenSSH format) authorized to log in to the nodes"]
+    #[serde(default)]
+    pub authorized_keys: Vec<String>,
     pub node_username: String,
     pub node_password: String,
     pub helm_apps: Vec<HelmApp>,
@@ -170,6 +643,67 @@ pub struct Cluster {
     pub nodes: Vec<ClusterNode>,
     pub network: Network,
     pub status: ClusterStatus,
+    #[serde(default)]
+    pub backup: Option<BackupSettings>,
+    #[serde(default)]
+    pub cloud_init: Option<CloudInitSettings>,
+    #[serde(default)]
+    pub node_pools: Vec<NodePool>,
+    #[serde(default)]
+    pub autoscaling: Option<AutoscalingSettings>,
+    #[serde(default)]
+    pub autoscaling_state: AutoscalingState,
+    #[doc = "MicroK8s addons enabled by Makoon"]
+    #[serde(default)]
+    pub addons: Vec<ClusterAddon>,
+    #[serde(default)]
+    pub distribution: Distribution,
+    #[serde(default)]
+    pub os_family: OsFamily,
+    #[doc = "Proxmox pool of the cluster VMs"]
+    #[serde(default)]
+    pub resource_pool: Option<String>,
+    #[doc = "Identifies the cluster in the metadata of its VMs"]
+    #[serde(default)]
+    pub cluster_id: String,
+    #[doc = "Secrets which couldn't be recovered from Proxmox and have to be entered again"]
+    #[serde(default)]
+    pub missing_secrets: Vec<ClusterSecret>,
+}
+
+#[typeshare]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum ClusterSecret {
+    SshKey,
+    NodePassword,
+}
+
+#[typeshare]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterSecretsRequest {
+    #[serde(default)]
+    pub ssh_key: Option<KeyPair>,
+    #[serde(default)]
+    pub node_password: Option<String>,
+}
+
+#[typeshare]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportClusterRequest {
+    #[doc = "Kubernetes node names have to be <clusterName>-<node name>"]
+    pub cluster_name: String,
+    #[doc = "Proxmox node of the VMs"]
+    pub node: String,
+    #[doc = "VMs of all nodes of the MicroK8s cluster"]
+    pub vm_ids: Vec<u32>,
+    pub node_username: String,
+    pub ssh_key: KeyPair,
+    #[doc = "Password of the user on nodes added by Makoon"]
+    #[serde(default)]
+    pub node_password: String,
 }
 
 #[typeshare]
@@ -181,7 +715,14 @@ pub struct ClusterRequest {
     pub kube_version: String,
     pub node: String,
     pub cluster_name: String,
+    #[doc = "Own key pair for the cluster, a new one is generated when the private key is empty"]
+    #[serde(default)]
     pub ssh_key: KeyPair,
+    #[serde(default)]
+    pub ssh_key_type: SshKeyType,
+    #[doc = "Additional public keys (OpenSSH format) authorized to log in to the nodes"]
+    #[serde(default)]
+    pub authorized_keys: Vec<String>,
     pub node_username: String,
     pub node_password: String,
     pub helm_apps: Vec<HelmApp>,
@@ -190,6 +731,27 @@ pub struct ClusterRequest {
     pub disk_size: u32,
     pub nodes: Vec<ClusterNode>,
     pub network: Network,
+    #[serde(default)]
+    pub cloud_init: Option<CloudInitSettings>,
+    #[doc = "MicroK8s addons enabled at creation, helm3 is always enabled. Not used by other distributions"]
+    #[serde(default = "default_addons")]
+    pub addons: Vec<ClusterAddon>,
+    #[doc = "Kubernetes distribution, kubeVersion is a version of its catalog"]
+    #[serde(default)]
+    pub distribution: Distribution,
+    #[doc = "Proxmox pool for the cluster VMs, created when it doesn't exist"]
+    #[serde(default)]
+    pub resource_pool: Option<String>,
+}
+
+pub(crate) fn default_addons() -> Vec<ClusterAddon> {
+    ["dns", "helm3"]
+        .iter()
+        .map(|i| ClusterAddon {
+            name: i.to_string(),
+            arguments: None,
+        })
+        .collect()
 }
 
 #[typeshare]
@@ -213,6 +775,111 @@ pub struct ClusterNodeStatus {
     pub status: KubeStatus,
 }
 
+#[typeshare]
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeDiagnostics {
+    pub node_name: String,
+    pub vm_id: u32,
+    #[doc = "QEMU guest agent responds, the rest of agent data is empty otherwise"]
+    pub agent_available: bool,
+    #[doc = "Output of 'cloud-init status --long'"]
+    pub cloud_init_status: Option<String>,
+    #[doc = "Last lines of /var/log/cloud-init-output.log"]
+    pub cloud_init_log: Option<String>,
+    #[doc = "Addresses reported by the guest agent, e.g. 'eth0 10.0.0.10/24'"]
+    pub ip_addresses: Vec<String>,
+    pub ssh_reachable: bool,
+    pub errors: Vec<String>,
+}
+
+#[typeshare]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationError {
+    #[doc = "Path of the invalid field in the request, e.g. 'nodes[1].vmId'"]
+    pub field: String,
+    pub message: String,
+}
+
+#[typeshare]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum PreflightSeverity {
+    #[default]
+    Ok,
+    #[doc = "Operation can run, e.g. with overcommitted resources"]
+    Warning,
+    #[doc = "Operation would fail, it's not started"]
+    Error,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightCheck {
+    #[doc = "Checked resource, e.g. 'memory' or 'storage local-lvm'"]
+    pub resource: String,
+    pub severity: PreflightSeverity,
+    pub message: String,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightReport {
+    #[doc = "The most severe result of the checks"]
+    pub status: PreflightSeverity,
+    pub checks: Vec<PreflightCheck>,
+}
+
+#[typeshare]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum PlanActionType {
+    #[doc = "Call of the Proxmox API, target is the Proxmox node"]
+    ProxmoxApi,
+    #[doc = "Command run over SSH, target is the host name of the cluster node"]
+    SshCommand,
+    #[doc = "File uploaded over SSH, target is the host name of the cluster node"]
+    UploadFile,
+    #[doc = "Makoon waits for the condition before it continues"]
+    Wait,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanAction {
+    pub action_type: PlanActionType,
+    pub target: String,
+    pub description: String,
+    #[doc = "Command, VM configuration or file content, passwords are hidden"]
+    pub details: Option<String>,
+}
+
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionPlan {
+    #[doc = "Operation of the plan, e.g. 'Create cluster [prod]'"]
+    pub operation: String,
+    #[doc = "Actions in the order they are run"]
+    pub actions: Vec<PlanAction>,
+}
+
+#[typeshare]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateStatus {
+    pub node_name: String,
+    pub file: String,
+    #[doc = "Empty when the expiry date cannot be read"]
+    pub expires: Option<NaiveDateTime>,
+    #[doc = "Reason why certificates of the node cannot be read, e.g. the node is unreachable"]
+    pub error: Option<String>,
+}
+
 #[typeshare]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -282,6 +949,9 @@ pub mod helm {
 }
 
 pub mod kube {
+    use std::collections::HashMap;
+    use std::fmt::{Display, Formatter};
+
     use serde::{Deserialize, Serialize};
 
     #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -291,20 +961,65 @@ pub mod kube {
         pub condition_type: String,
     }
 
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    pub struct NodeAddress {
+        #[serde(rename = "type")]
+        pub address_type: String,
+        pub address: String,
+    }
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct NodeInfo {
+        pub kubelet_version: String,
+    }
+
     #[derive(Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct Status {
         pub conditions: Vec<StatusCondition>,
+        #[serde(default)]
+        pub addresses: Vec<NodeAddress>,
+        #[serde(default)]
+        pub node_info: Option<NodeInfo>,
     }
 
-    #[derive(Debug, Deserialize, Serialize)]
+    #[derive(Debug, Deserialize, Serialize, Default)]
     pub struct Metadata {
         pub name: String,
+        #[serde(default)]
+        pub labels: HashMap<String, String>,
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct Taint {
+        pub key: String,
+        #[serde(default)]
+        pub value: Option<String>,
+        pub effect: String,
+    }
+
+    impl Display for Taint {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            match self.value.as_ref() {
+                Some(v) => write!(f, "{}={}:{}", self.key, v, self.effect),
+                None => write!(f, "{}:{}", self.key, self.effect),
+            }
+        }
+    }
+
+    #[derive(Debug, Deserialize, Serialize, Default)]
+    pub struct Spec {
+        #[serde(default)]
+        pub taints: Vec<Taint>,
     }
 
     #[derive(Debug, Deserialize, Serialize)]
     pub struct Item {
         pub status: Status,
         pub metadata: Metadata,
+        #[serde(default)]
+        pub spec: Spec,
     }
 
     #[derive(Debug, Deserialize, Serialize)]
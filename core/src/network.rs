@@ -0,0 +1,537 @@
+use std::net::Ipv6Addr;
+use std::str::FromStr;
+use proxmox_client::model::{AgentIpAddressType, AgentNetworkInterface, NetworkType, ParamBuilder};
+use proxmox_client::ClientOperations;
+
+use crate::model::{ClusterNode, Ip6Mode, Network};
+
+const MIN_MTU: u16 = 576;
+const MAX_MTU: u16 = 65520;
+const MAX_VLAN_TAG: u16 = 4094;
+const DEFAULT_IP6_PREFIX_LENGTH: u8 = 64;
+// MicroK8s defaults, they have to be repeated when the launch configuration is used
+const MICROK8S_IPV4_CLUSTER_CIDR: &str = "10.1.0.0/16";
+const MICROK8S_IPV4_SERVICE_CIDR: &str = "10.152.183.0/24";
+// k3s defaults, they have to be repeated for dual-stack cluster
+const K3S_IPV4_CLUSTER_CIDR: &str = "10.42.0.0/16";
+const K3S_IPV4_SERVICE_CIDR: &str = "10.43.0.0/16";
+
+/// Proxmox `netN` and `ipconfigN` values of one VM interface
+#[derive(Debug, PartialEq)]
+pub(crate) struct InterfaceParams {
+    pub(crate) index: usize,
+    pub(crate) net: String,
+    pub(crate) ipconfig: String,
+}
+
+fn net_param(bridge: &str, vlan_tag: Option<u16>, mtu: Option<u16>, firewall: bool) -> String {
+    let mut builder = ParamBuilder::default();
+    builder.add_param("model", "virtio").add_param("bridge", bridge);
+    if let Some(tag) = vlan_tag {
+        builder.add_param("tag", &tag.to_string());
+    }
+    if let Some(mtu) = mtu {
+        builder.add_param("mtu", &mtu.to_string());
+    }
+    if firewall {
+        builder.add_param("firewall", "1");
+    }
+    builder.build()
+}
+
+fn primary_ipconfig(network: &Network, node: &ClusterNode) -> Result<String, String> {
+    let mut builder = ParamBuilder::default();
+    if network.is_dhcp_node(node) {
+        builder.add_param("ip", "dhcp");
+    } else if is_ipv6(&node.ip_address) {
+        // IPv6-only node, subnet mask and gateway of the network are IPv6 as well
+        builder
+            .add_param(
+                "ip6",
+                format!("{}/{}", node.ip_address.trim(), network.subnet_mask).as_str(),
+            )
+            .add_param("gw6", network.gateway.as_str());
+        return Ok(builder.build());
+    } else {
+        builder
+            .add_param(
+                "ip",
+                format!("{}/{}", node.ip_address, network.subnet_mask).as_str(),
+            )
+            .add_param("gw", network.gateway.as_str());
+    }
+
+    match network.ip6_mode {
+        Ip6Mode::Disabled => {}
+        Ip6Mode::Auto => {
+            builder.add_param("ip6", "auto");
+        }
+        Ip6Mode::Dhcp => {
+            builder.add_param("ip6", "dhcp");
+        }
+        Ip6Mode::Static => {
+            let address = node
+                .ip6_address
+                .as_ref()
+                .filter(|i| is_ipv6(i))
+                .ok_or(format!("Node [{}] has no valid IPv6 address", node.name))?;
+            builder.add_param(
+                "ip6",
+                format!(
+                    "{}/{}",
+                    address.trim(),
+                    network.ip6_prefix_length.unwrap_or(DEFAULT_IP6_PREFIX_LENGTH)
+                )
+                .as_str(),
+            );
+            if let Some(gateway) = network.ip6_gateway.as_ref().filter(|i| !i.is_empty()) {
+                builder.add_param("gw6", gateway);
+            }
+        }
+    }
+    Ok(builder.build())
+}
+
+pub(crate) fn is_ipv6(address: &str) -> bool {
+    Ipv6Addr::from_str(address.trim()).is_ok()
+}
+
+/// Addresses of a DHCP node reported by the QEMU guest agent, the first one is used for
+/// connections. Loopback and IPv6 link-local addresses are skipped, IPv6 address is the
+/// first one on `ipv6_only` network.
+pub(crate) fn discovered_addresses(
+    interfaces: &[AgentNetworkInterface],
+    ipv6_only: bool,
+) -> Option<(String, Option<String>)> {
+    let addresses = interfaces
+        .iter()
+        .filter(|i| i.name != "lo")
+        .flat_map(|i| i.ip_addresses.iter())
+        .collect::<Vec<_>>();
+    let ip6_address = addresses
+        .iter()
+        .find(|i| {
+            i.ip_address_type == AgentIpAddressType::Ipv6
+                && !i.ip_address.to_lowercase().starts_with("fe80")
+        })
+        .map(|i| i.ip_address.clone());
+    let ip_address = if ipv6_only {
+        ip6_address.clone()
+    } else {
+        addresses
+            .iter()
+            .find(|i| i.ip_address_type == AgentIpAddressType::Ipv4)
+            .map(|i| i.ip_address.clone())
+    }?;
+    Some((
+        ip_address.clone(),
+        ip6_address.filter(|i| *i != ip_address),
+    ))
+}
+
+/// Value of cloud-init `nameserver`, servers are separated by space
+pub(crate) fn nameservers(network: &Network) -> String {
+    std::iter::once(network.dns.trim())
+        .chain(network.dns_servers.iter().map(|i| i.trim()))
+        .filter(|i| !i.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// MicroK8s launch configuration enabling IPv6 in Calico, it has to exist before the snap
+/// is installed. Nothing is returned for IPv4-only cluster.
+pub(crate) fn microk8s_launch_config(network: &Network, nodes: &[ClusterNode]) -> Option<String> {
+    let (cluster_cidr, service_cidr) = ip6_cidrs(network)?;
+    let ipv4 = has_ipv4(network, nodes);
+
+    let mut lines = vec![
+        "---".to_string(),
+        "version: 0.1.0".to_string(),
+        "extraCNIEnv:".to_string(),
+        format!("  IPv4_SUPPORT: {}", ipv4),
+    ];
+    if ipv4 {
+        lines.push(format!("  IPv4_CLUSTER_CIDR: {}", MICROK8S_IPV4_CLUSTER_CIDR));
+        lines.push(format!("  IPv4_SERVICE_CIDR: {}", MICROK8S_IPV4_SERVICE_CIDR));
+    }
+    lines.push("  IPv6_SUPPORT: true".to_string());
+    lines.push(format!("  IPv6_CLUSTER_CIDR: {}", cluster_cidr.trim()));
+    lines.push(format!("  IPv6_SERVICE_CIDR: {}", service_cidr.trim()));
+    Some(lines.join("\n") + "\n")
+}
+
+/// `k3s server` arguments of dual-stack or IPv6-only cluster, empty for IPv4-only cluster.
+pub(crate) fn k3s_cidr_args(network: &Network, nodes: &[ClusterNode]) -> Vec<String> {
+    let (cluster_cidr, service_cidr) = match ip6_cidrs(network) {
+        Some(v) => v,
+        None => return vec![],
+    };
+    if has_ipv4(network, nodes) {
+        vec![
+            format!("--cluster-cidr={},{}", K3S_IPV4_CLUSTER_CIDR, cluster_cidr.trim()),
+            format!("--service-cidr={},{}", K3S_IPV4_SERVICE_CIDR, service_cidr.trim()),
+        ]
+    } else {
+        vec![
+            format!("--cluster-cidr={}", cluster_cidr.trim()),
+            format!("--service-cidr={}", service_cidr.trim()),
+        ]
+    }
+}
+
+fn ip6_cidrs(network: &Network) -> Option<(&String, &String)> {
+    let cluster_cidr = network.ip6_cluster_cidr.as_ref().filter(|i| !i.is_empty())?;
+    let service_cidr = network.ip6_service_cidr.as_ref().filter(|i| !i.is_empty())?;
+    Some((cluster_cidr, service_cidr))
+}
+
+fn has_ipv4(network: &Network, nodes: &[ClusterNode]) -> bool {
+    nodes
+        .iter()
+        .any(|i| network.is_dhcp_node(i) || !is_ipv6(&i.ip_address))
+}
+
+/// Builds interfaces of the node VM, the primary one is `net0`, additional interfaces follow.
+pub(crate) fn interface_params(
+    network: &Network,
+    node: &ClusterNode,
+) -> Result<Vec<InterfaceParams>, String> {
+    let mut result = vec![InterfaceParams {
+        index: 0,
+        net: net_param(&network.bridge, network.vlan_tag, network.mtu, network.firewall),
+        ipconfig: primary_ipconfig(network, node)?,
+    }];
+
+    for (position, interface) in network.interfaces.iter().enumerate() {
+        let node_interface = node.interfaces.get(position).cloned().unwrap_or_default();
+        let index = position + 1;
+        let bridge = node_interface.bridge.unwrap_or(interface.bridge.clone());
+        let dhcp = node_interface.dhcp.unwrap_or(interface.dhcp);
+
+        let ipconfig = if dhcp {
+            ParamBuilder::default().add_param("ip", "dhcp").build()
+        } else {
+            if node_interface.ip_address.trim().is_empty() {
+                return Err(format!(
+                    "Node [{}] has no IP address for interface net{}",
+                    node.name, index
+                ));
+            }
+            let subnet_mask = interface.subnet_mask.unwrap_or(network.subnet_mask);
+            let mut builder = ParamBuilder::default();
+            builder.add_param(
+                "ip",
+                format!("{}/{}", node_interface.ip_address.trim(), subnet_mask).as_str(),
+            );
+            if let Some(gateway) = interface.gateway.as_ref().filter(|i| !i.is_empty()) {
+                builder.add_param("gw", gateway);
+            }
+            builder.build()
+        };
+
+        result.push(InterfaceParams {
+            index,
+            net: net_param(
+                &bridge,
+                node_interface.vlan_tag.or(interface.vlan_tag),
+                node_interface.mtu.or(interface.mtu),
+                node_interface.firewall.unwrap_or(interface.firewall),
+            ),
+            ipconfig,
+        });
+    }
+    Ok(result)
+}
+
+/// Bridges used by the node interfaces, the primary bridge first
+pub(crate) fn bridges(network: &Network, node: &ClusterNode) -> Vec<String> {
+    std::iter::once(network.bridge.clone())
+        .chain(network.interfaces.iter().enumerate().map(|(position, i)| {
+            node.interfaces
+                .get(position)
+                .and_then(|n| n.bridge.clone())
+                .unwrap_or(i.bridge.clone())
+        }))
+        .collect()
+}
+
+/// Names of bridges on the Proxmox node.
+pub(crate) fn proxmox_bridges(
+    proxmox_client: &ClientOperations,
+    proxmox_node: &str,
+) -> Result<Vec<String>, proxmox_client::Error> {
+    Ok(proxmox_client
+        .networks(proxmox_node, Some(NetworkType::AnyBridge))?
+        .into_iter()
+        .map(|i| i.iface)
+        .collect())
+}
+
+/// Bridges of the nodes have to exist on the Proxmox node, `available` are its bridges.
+pub(crate) fn validate_bridges(
+    network: &Network,
+    nodes: &[ClusterNode],
+    proxmox_node: &str,
+    available: &[String],
+) -> Result<(), String> {
+    for bridge in nodes.iter().flat_map(|i| bridges(network, i)) {
+        if !available.contains(&bridge) {
+            return Err(format!(
+                "Bridge [{}] doesn't exist on node [{}]",
+                bridge, proxmox_node
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn validate(network: &Network, nodes: &[ClusterNode]) -> Result<(), String> {
+    let vlan_tags = std::iter::once(network.vlan_tag)
+        .chain(network.interfaces.iter().map(|i| i.vlan_tag))
+        .chain(nodes.iter().flat_map(|n| n.interfaces.iter().map(|i| i.vlan_tag)))
+        .flatten();
+    for tag in vlan_tags {
+        if tag == 0 || tag > MAX_VLAN_TAG {
+            return Err(format!("VLAN tag [{}] is out of range 1-{}", tag, MAX_VLAN_TAG));
+        }
+    }
+    let mtus = std::iter::once(network.mtu)
+        .chain(network.interfaces.iter().map(|i| i.mtu))
+        .chain(nodes.iter().flat_map(|n| n.interfaces.iter().map(|i| i.mtu)))
+        .flatten();
+    for mtu in mtus {
+        // MTU 1 means the interface inherits MTU of the bridge
+        if mtu != 1 && !(MIN_MTU..=MAX_MTU).contains(&mtu) {
+            return Err(format!("MTU [{}] is out of range {}-{}", mtu, MIN_MTU, MAX_MTU));
+        }
+    }
+    for node in nodes.iter() {
+        if node.interfaces.len() > network.interfaces.len() {
+            return Err(format!(
+                "Node [{}] has more interfaces than the cluster network",
+                node.name
+            ));
+        }
+        if let Some(address) = node.ip6_address.as_ref().filter(|i| !i.trim().is_empty()) {
+            if !is_ipv6(address) {
+                return Err(format!("Invalid IPv6 address [{}]", address));
+            }
+        }
+        interface_params(network, node)?;
+    }
+    let cidrs = [&network.ip6_cluster_cidr, &network.ip6_service_cidr]
+        .iter()
+        .filter(|i| i.as_ref().map(|c| !c.trim().is_empty()).unwrap_or(false))
+        .count();
+    if cidrs == 1 {
+        return Err("Both IPv6 cluster CIDR and service CIDR have to be set".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::{
+        ClusterNode, ClusterNodeType, Ip6Mode, Network, NetworkInterface, NodeNetworkInterface,
+    };
+    use crate::network::{
+        discovered_addresses, interface_params, microk8s_launch_config, validate, validate_bridges,
+    };
+    use crate::test_fixtures;
+    use proxmox_client::model::AgentNetworkInterface;
+
+    fn network() -> Network {
+        Network {
+            gateway: "10.0.0.1".to_string(),
+            subnet_mask: 24,
+            dns: "10.0.0.1".to_string(),
+            bridge: "vmbr0".to_string(),
+            vlan_tag: Some(20),
+            mtu: Some(9000),
+            interfaces: vec![NetworkInterface {
+                bridge: "vmbr1".to_string(),
+                subnet_mask: Some(16),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn node(interfaces: Vec<NodeNetworkInterface>) -> ClusterNode {
+        ClusterNode {
+            interfaces,
+            ..test_fixtures::node("master-1", 100, "10.0.0.10", ClusterNodeType::Master)
+        }
+    }
+
+    #[test]
+    fn interface_params_maps_interfaces_to_net_and_ipconfig() {
+        let result = interface_params(
+            &network(),
+            &node(vec![NodeNetworkInterface {
+                ip_address: "172.16.0.10".to_string(),
+                vlan_tag: Some(30),
+                ..Default::default()
+            }]),
+        )
+        .unwrap();
+
+        assert_eq!(2, result.len());
+        assert_eq!("model=virtio,bridge=vmbr0,tag=20,mtu=9000", result[0].net);
+        assert_eq!("ip=10.0.0.10/24,gw=10.0.0.1", result[0].ipconfig);
+        assert_eq!(1, result[1].index);
+        assert_eq!("model=virtio,bridge=vmbr1,tag=30", result[1].net);
+        assert_eq!("ip=172.16.0.10/16", result[1].ipconfig);
+    }
+
+    #[test]
+    fn interface_params_requires_static_address() {
+        assert!(interface_params(&network(), &node(vec![])).is_err());
+    }
+
+    #[test]
+    fn interface_params_adds_ipv6_for_dual_stack() {
+        let mut network = network();
+        network.interfaces = vec![];
+        network.ip6_mode = Ip6Mode::Static;
+        network.ip6_gateway = Some("fd00::1".to_string());
+        let mut node = node(vec![]);
+        node.ip6_address = Some("fd00::10".to_string());
+
+        let result = interface_params(&network, &node).unwrap();
+        assert_eq!(
+            "ip=10.0.0.10/24,gw=10.0.0.1,ip6=fd00::10/64,gw6=fd00::1",
+            result[0].ipconfig
+        );
+    }
+
+    #[test]
+    fn interface_params_uses_ipv6_only_address() {
+        let mut network = network();
+        network.interfaces = vec![];
+        network.subnet_mask = 64;
+        network.gateway = "fd00::1".to_string();
+        let mut node = node(vec![]);
+        node.ip_address = "fd00::10".to_string();
+
+        let result = interface_params(&network, &node).unwrap();
+        assert_eq!("ip6=fd00::10/64,gw6=fd00::1", result[0].ipconfig);
+    }
+
+    #[test]
+    fn microk8s_launch_config_for_ipv6_only_cluster() {
+        let mut network = network();
+        network.ip6_cluster_cidr = Some("fd02::/64".to_string());
+        network.ip6_service_cidr = Some("fd99::/108".to_string());
+        let mut node = node(vec![]);
+        node.ip_address = "fd00::10".to_string();
+
+        let config = microk8s_launch_config(&network, &[node]).unwrap();
+        assert!(config.contains("IPv4_SUPPORT: false"));
+        assert!(!config.contains("IPv4_CLUSTER_CIDR"));
+        assert!(config.contains("IPv6_CLUSTER_CIDR: fd02::/64"));
+        assert!(config.contains("IPv6_SERVICE_CIDR: fd99::/108"));
+    }
+
+    #[test]
+    fn microk8s_launch_config_is_empty_for_ipv4_cluster() {
+        assert_eq!(None, microk8s_launch_config(&network(), &[node(vec![])]));
+    }
+
+    #[test]
+    fn validate_rejects_invalid_vlan_and_mtu() {
+        let nodes = vec![node(vec![NodeNetworkInterface {
+            dhcp: Some(true),
+            ..Default::default()
+        }])];
+        assert!(validate(&network(), &nodes).is_ok());
+
+        let mut invalid_vlan = network();
+        invalid_vlan.vlan_tag = Some(4095);
+        assert!(validate(&invalid_vlan, &nodes).is_err());
+
+        let mut invalid_mtu = network();
+        invalid_mtu.mtu = Some(100);
+        assert!(validate(&invalid_mtu, &nodes).is_err());
+    }
+
+    #[test]
+    fn interface_params_uses_firewall_of_node() {
+        let mut network = network();
+        network.interfaces[0].firewall = true;
+        let mut interface = NodeNetworkInterface {
+            ip_address: "172.16.0.10".to_string(),
+            ..Default::default()
+        };
+        let result = interface_params(&network, &node(vec![interface.clone()])).unwrap();
+        assert_eq!("model=virtio,bridge=vmbr1,firewall=1", result[1].net);
+
+        interface.firewall = Some(false);
+        let result = interface_params(&network, &node(vec![interface])).unwrap();
+        assert_eq!("model=virtio,bridge=vmbr1", result[1].net);
+    }
+
+    #[test]
+    fn validate_bridges_requires_bridges_of_proxmox_node() {
+        let nodes = vec![node(vec![NodeNetworkInterface {
+            bridge: Some("vmbr2".to_string()),
+            ..Default::default()
+        }])];
+        let available = vec!["vmbr0".to_string(), "vmbr1".to_string()];
+        assert!(validate_bridges(&network(), &[node(vec![])], "pve", &available).is_ok());
+        assert_eq!(
+            "Bridge [vmbr2] doesn't exist on node [pve]",
+            validate_bridges(&network(), &nodes, "pve", &available).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn interface_params_uses_dhcp_of_node() {
+        let mut network = network();
+        network.interfaces = vec![];
+        let mut node = node(vec![]);
+        assert!(!network.is_dhcp_node(&node));
+
+        node.dhcp = Some(true);
+        assert!(network.is_dhcp_node(&node));
+        assert_eq!("ip=dhcp", interface_params(&network, &node).unwrap()[0].ipconfig);
+
+        network.dhcp = true;
+        node.dhcp = Some(false);
+        assert!(!network.is_dhcp_node(&node));
+    }
+
+    fn agent_interfaces() -> Vec<AgentNetworkInterface> {
+        serde_json::from_value(serde_json::json!([
+            {
+                "name": "lo",
+                "ip-addresses": [
+                    {"ip-address-type": "ipv4", "ip-address": "127.0.0.1", "prefix": 8},
+                    {"ip-address-type": "ipv6", "ip-address": "::1", "prefix": 128}
+                ]
+            },
+            {
+                "name": "eth0",
+                "ip-addresses": [
+                    {"ip-address-type": "ipv6", "ip-address": "fe80::1", "prefix": 64},
+                    {"ip-address-type": "ipv6", "ip-address": "fd00::10", "prefix": 64},
+                    {"ip-address-type": "ipv4", "ip-address": "10.0.0.10", "prefix": 24}
+                ]
+            }
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn discovered_addresses_skip_loopback_and_link_local() {
+        assert_eq!(
+            Some(("10.0.0.10".to_string(), Some("fd00::10".to_string()))),
+            discovered_addresses(&agent_interfaces(), false)
+        );
+        assert_eq!(
+            Some(("fd00::10".to_string(), None)),
+            discovered_addresses(&agent_interfaces(), true)
+        );
+        assert_eq!(None, discovered_addresses(&agent_interfaces()[..1], false));
+    }
+}
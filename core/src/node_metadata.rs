@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use crate::model::kube;
+use crate::model::{Cluster, ClusterNode};
+use crate::node_pool;
+
+pub(crate) const ROLE_LABEL_PREFIX: &str = "node-role.kubernetes.io/";
+pub(crate) const ZONE_LABEL: &str = "topology.kubernetes.io/zone";
+
+pub(crate) fn validate(node: &ClusterNode) -> Result<(), String> {
+    for (key, value) in node.labels.iter() {
+        node_pool::validate_label(key, value)?;
+    }
+    for taint in node.taints.iter() {
+        node_pool::validate_taint(taint)?;
+    }
+    Ok(())
+}
+
+/// Labels Makoon keeps on the node: role and zone (Proxmox host of the cluster), then labels
+/// of the node pool and labels of the node, later ones win.
+pub(crate) fn labels(cluster: &Cluster, node: &ClusterNode) -> HashMap<String, String> {
+    let mut result = HashMap::from([
+        (format!("{}{}", ROLE_LABEL_PREFIX, node.node_type), "".to_string()),
+        (ZONE_LABEL.to_string(), cluster.node.clone()),
+    ]);
+    if let Some(pool) = pool(cluster, node) {
+        result.extend(pool.labels.clone());
+    }
+    result.extend(node.labels.clone());
+    result
+}
+
+/// Taints of the node pool and of the node.
+pub(crate) fn taints(cluster: &Cluster, node: &ClusterNode) -> Vec<String> {
+    let mut result = pool(cluster, node)
+        .map(|i| i.taints.clone())
+        .unwrap_or_default();
+    for taint in node.taints.iter() {
+        if !result.contains(taint) {
+            result.push(taint.clone());
+        }
+    }
+    result
+}
+
+/// `kubectl` arguments which bring the Kubernetes node in line with the desired labels and
+/// taints. Labels and taints which Makoon stopped managing are removed when the node has
+/// them, other labels and taints of the node are kept.
+pub(crate) fn sync_commands(
+    host_name: &str,
+    labels: &HashMap<String, String>,
+    taints: &[String],
+    removed_labels: &[String],
+    removed_taints: &[String],
+    actual: &kube::Item,
+) -> Vec<String> {
+    let actual_taints = actual
+        .spec
+        .taints
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<String>>();
+    let mut removed_labels = removed_labels
+        .iter()
+        .filter(|i| actual.metadata.labels.contains_key(*i) && !labels.contains_key(*i))
+        .collect::<Vec<&String>>();
+    removed_labels.sort();
+    let mut result = removed_labels
+        .into_iter()
+        .map(|i| format!("label node {} {}-", host_name, i))
+        .collect::<Vec<String>>();
+    result.extend(
+        removed_taints
+            .iter()
+            .filter(|i| actual_taints.contains(i) && !taints.contains(i))
+            .map(|i| format!("taint node {} {}-", host_name, i)),
+    );
+
+    let missing_labels = labels
+        .iter()
+        .filter(|(key, value)| actual.metadata.labels.get(*key) != Some(value))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect::<HashMap<String, String>>();
+    let missing_taints = taints
+        .iter()
+        .filter(|i| !actual_taints.contains(i))
+        .cloned()
+        .collect::<Vec<String>>();
+    result.extend(node_pool::node_metadata_commands(
+        host_name,
+        &missing_labels,
+        &missing_taints,
+    ));
+    result
+}
+
+fn pool<'a>(cluster: &'a Cluster, node: &ClusterNode) -> Option<&'a crate::model::NodePool> {
+    cluster
+        .node_pools
+        .iter()
+        .find(|i| Some(&i.name) == node.pool.as_ref())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::model::kube::{Item, Metadata, Spec, Status, Taint};
+    use crate::model::{Cluster, ClusterNode, ClusterNodeType, NodePool};
+    use crate::node_metadata::{labels, sync_commands, taints};
+
+    fn cluster() -> Cluster {
+        Cluster {
+            node: "pve1".to_string(),
+            cluster_name: "c1".to_string(),
+            node_pools: vec![NodePool {
+                name: "db".to_string(),
+                labels: HashMap::from([("tier".to_string(), "db".to_string())]),
+                taints: vec!["dedicated=db:NoSchedule".to_string()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn node_labels_override_pool_and_defaults() {
+        let node = ClusterNode {
+            name: "db-1".to_string(),
+            node_type: ClusterNodeType::Worker,
+            pool: Some("db".to_string()),
+            labels: HashMap::from([
+                ("tier".to_string(), "primary".to_string()),
+                ("topology.kubernetes.io/zone".to_string(), "rack-2".to_string()),
+            ]),
+            taints: vec!["dedicated=db:NoSchedule".to_string(), "ssd:NoExecute".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            HashMap::from([
+                ("node-role.kubernetes.io/worker".to_string(), "".to_string()),
+                ("topology.kubernetes.io/zone".to_string(), "rack-2".to_string()),
+                ("tier".to_string(), "primary".to_string()),
+            ]),
+            labels(&cluster(), &node)
+        );
+        assert_eq!(
+            vec!["dedicated=db:NoSchedule".to_string(), "ssd:NoExecute".to_string()],
+            taints(&cluster(), &node)
+        );
+    }
+
+    #[test]
+    fn sync_commands_fix_drift_only() {
+        let actual = Item {
+            status: Status {
+                conditions: vec![],
+                addresses: vec![],
+                node_info: None,
+            },
+            metadata: Metadata {
+                name: "c1-db-1".to_string(),
+                labels: HashMap::from([
+                    ("tier".to_string(), "db".to_string()),
+                    ("old".to_string(), "x".to_string()),
+                    ("kubernetes.io/os".to_string(), "linux".to_string()),
+                ]),
+            },
+            spec: Spec {
+                taints: vec![Taint {
+                    key: "ssd".to_string(),
+                    value: None,
+                    effect: "NoExecute".to_string(),
+                }],
+            },
+        };
+        let desired = HashMap::from([
+            ("tier".to_string(), "db".to_string()),
+            ("zone".to_string(), "a".to_string()),
+        ]);
+        assert_eq!(
+            vec![
+                "label node c1-db-1 old-".to_string(),
+                "taint node c1-db-1 ssd:NoExecute-".to_string(),
+                "label node c1-db-1 zone=a --overwrite".to_string(),
+                "taint node c1-db-1 dedicated=db:NoSchedule --overwrite".to_string(),
+            ],
+            sync_commands(
+                "c1-db-1",
+                &desired,
+                &["dedicated=db:NoSchedule".to_string()],
+                &["old".to_string(), "missing".to_string()],
+                &["ssd:NoExecute".to_string()],
+                &actual,
+            )
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use proxmox_client::ClientOperations;
+
+use crate::hardware;
+use crate::model::{Cluster, ClusterNode, NodePool};
+use crate::Repository;
+
+const MAX_POOL_NAME_LENGTH: usize = 20;
+const MAX_LABEL_VALUE_LENGTH: usize = 63;
+const TAINT_EFFECTS: [&str; 3] = ["NoSchedule", "PreferNoSchedule", "NoExecute"];
+
+pub(crate) fn validate(pool: &NodePool) -> Result<(), String> {
+    let valid_name = !pool.name.is_empty()
+        && pool.name.len() <= MAX_POOL_NAME_LENGTH
+        && pool
+            .name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid_name {
+        return Err(format!(
+            "Invalid pool name [{}], use up to {} lowercase letters, digits and '-'",
+            pool.name, MAX_POOL_NAME_LENGTH
+        ));
+    }
+    if pool.cores == 0 || pool.memory == 0 {
+        return Err("Cores and memory of the pool have to be greater than 0".to_string());
+    }
+    if pool.storage_pool.trim().is_empty() {
+        return Err("Storage pool of the pool is empty".to_string());
+    }
+    for (key, value) in pool.labels.iter() {
+        validate_label(key, value)?;
+    }
+    for taint in pool.taints.iter() {
+        validate_taint(taint)?;
+    }
+    hardware::validate(&pool.hardware, pool.memory)
+}
+
+pub(crate) fn validate_label(key: &str, value: &str) -> Result<(), String> {
+    let valid_key = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./".contains(c));
+    let valid_value = value.len() <= MAX_LABEL_VALUE_LENGTH
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if !valid_key || !valid_value {
+        return Err(format!("Invalid label [{}={}]", key, value));
+    }
+    Ok(())
+}
+
+/// Taint in `kubectl taint` format: `key[=value]:Effect`
+pub(crate) fn validate_taint(taint: &str) -> Result<(), String> {
+    let (key_value, effect) = taint
+        .rsplit_once(':')
+        .ok_or(format!("Taint [{}] has no effect", taint))?;
+    if !TAINT_EFFECTS.contains(&effect) {
+        return Err(format!(
+            "Invalid effect of taint [{}], use one of: {}",
+            taint,
+            TAINT_EFFECTS.join(", ")
+        ));
+    }
+    let (key, value) = key_value.split_once('=').unwrap_or((key_value, ""));
+    validate_label(key, value).map_err(|_| format!("Invalid taint [{}]", taint))
+}
+
+/// Names `<pool>-<index>` not used by any node of the cluster, lowest indexes first.
+pub(crate) fn node_names(pool_name: &str, existing: &[&str], count: usize) -> Vec<String> {
+    (1..)
+        .map(|i| format!("{}-{}", pool_name, i))
+        .filter(|i| !existing.contains(&i.as_str()))
+        .take(count)
+        .collect()
+}
+
+/// Free VM IDs from `start` up.
+pub(crate) fn vm_ids(start: u32, used: &[u32], count: usize) -> Vec<u32> {
+    (start..)
+        .filter(|i| !used.contains(i))
+        .take(count)
+        .collect()
+}
+
+/// New nodes of the pool with free names and VM IDs, VM IDs used by Makoon clusters and
+/// by any VM or container of the Proxmox node are skipped.
+pub(crate) fn new_nodes(
+    proxmox_client: &ClientOperations,
+    repository: &Repository,
+    cluster: &Cluster,
+    pool: &NodePool,
+    count: usize,
+) -> crate::Result<Vec<ClusterNode>> {
+    let existing_names = cluster
+        .nodes
+        .iter()
+        .map(|i| i.name.as_str())
+        .collect::<Vec<&str>>();
+    let names = node_names(&pool.name, &existing_names, count);
+    let mut used_vm_ids = repository
+        .get_clusters()?
+        .iter()
+        .flat_map(|c| c.nodes.iter().map(|i| i.vm_id))
+        .collect::<Vec<u32>>();
+    used_vm_ids.extend(
+        proxmox_client
+            .virtual_machines(&cluster.node, None)?
+            .iter()
+            .map(|i| i.vm_id),
+    );
+    used_vm_ids.extend(
+        proxmox_client
+            .lxc_containers(&cluster.node)?
+            .iter()
+            .map(|i| i.vm_id),
+    );
+    let start_vm_id = cluster.nodes.iter().map(|i| i.vm_id).min().unwrap_or(100);
+    let vm_ids = vm_ids(start_vm_id, &used_vm_ids, names.len());
+
+    Ok(names
+        .into_iter()
+        .zip(vm_ids)
+        .map(|(name, vm_id)| ClusterNode {
+            vm_id,
+            name,
+            cores: pool.cores,
+            memory: pool.memory,
+            storage_pool: pool.storage_pool.clone(),
+            node_type: pool.node_type.clone(),
+            hardware: pool.hardware.clone(),
+            pool: Some(pool.name.clone()),
+            disk_size: pool.disk_size,
+            ..Default::default()
+        })
+        .collect())
+}
+
+/// Number of pods per Kubernetes node from
+/// `kubectl get pods -A --no-headers -o custom-columns=NODE:.spec.nodeName`.
+pub(crate) fn pod_counts(output: &str) -> HashMap<String, usize> {
+    let mut result = HashMap::new();
+    for node in output
+        .lines()
+        .map(|i| i.trim())
+        .filter(|i| !i.is_empty() && *i != "<none>")
+    {
+        *result.entry(node.to_string()).or_insert(0) += 1;
+    }
+    result
+}
+
+/// Picks nodes of the pool to remove: the nodes with the fewest pods, newer nodes first
+/// when the pod count is equal.
+pub(crate) fn nodes_to_remove(
+    cluster_name: &str,
+    nodes: &[&ClusterNode],
+    pod_counts: &HashMap<String, usize>,
+    count: usize,
+) -> Vec<String> {
+    let mut candidates = nodes
+        .iter()
+        .map(|i| {
+            let host_name = i.host_name(cluster_name);
+            (
+                pod_counts.get(&host_name).cloned().unwrap_or(0),
+                i.vm_id,
+                i.name.clone(),
+            )
+        })
+        .collect::<Vec<(usize, u32, String)>>();
+    candidates.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    candidates
+        .into_iter()
+        .take(count)
+        .map(|(_, _, name)| name)
+        .collect()
+}
+
+/// `kubectl` arguments which put the labels and taints on the node, sorted by key.
+pub(crate) fn node_metadata_commands(
+    host_name: &str,
+    labels: &HashMap<String, String>,
+    taints: &[String],
+) -> Vec<String> {
+    let mut labels = labels.iter().collect::<Vec<(&String, &String)>>();
+    labels.sort();
+    let mut result = labels
+        .into_iter()
+        .map(|(key, value)| format!("label node {} {}={} --overwrite", host_name, key, value))
+        .collect::<Vec<String>>();
+    result.extend(
+        taints
+            .iter()
+            .map(|i| format!("taint node {} {} --overwrite", host_name, i)),
+    );
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::model::{ClusterNode, ClusterNodeType};
+    use crate::node_pool::{
+        node_metadata_commands, node_names, nodes_to_remove, pod_counts, validate_taint, vm_ids,
+    };
+    use crate::test_fixtures::node;
+
+    #[test]
+    fn node_names_and_vm_ids_skip_used() {
+        assert_eq!(
+            vec!["gpu-2".to_string(), "gpu-4".to_string()],
+            node_names("gpu", &["gpu-1", "gpu-3"], 2)
+        );
+        assert_eq!(vec![102, 104], vm_ids(101, &[101, 103], 2));
+    }
+
+    #[test]
+    fn nodes_with_fewest_pods_are_removed_first() {
+        let output = "c1-gpu-1\nc1-gpu-1\nc1-gpu-2\n<none>\nc1-gpu-1\n";
+        let counts = pod_counts(output);
+        let nodes = [
+            node("gpu-1", 101, "", ClusterNodeType::Worker),
+            node("gpu-2", 102, "", ClusterNodeType::Worker),
+            node("gpu-3", 103, "", ClusterNodeType::Worker),
+        ];
+        let nodes = nodes.iter().collect::<Vec<&ClusterNode>>();
+        assert_eq!(
+            vec!["gpu-3".to_string(), "gpu-2".to_string()],
+            nodes_to_remove("c1", &nodes, &counts, 2)
+        );
+    }
+
+    #[test]
+    fn validate_taint_requires_known_effect() {
+        assert!(validate_taint("dedicated=gpu:NoSchedule").is_ok());
+        assert!(validate_taint("dedicated:NoExecute").is_ok());
+        assert!(validate_taint("dedicated=gpu").is_err());
+        assert!(validate_taint("dedicated=gpu;rm:NoSchedule").is_err());
+    }
+
+    #[test]
+    fn node_metadata_commands_are_sorted() {
+        let labels = HashMap::from([
+            ("zone".to_string(), "a".to_string()),
+            ("role".to_string(), "gpu".to_string()),
+        ]);
+        assert_eq!(
+            vec![
+                "label node c1-gpu-1 role=gpu --overwrite".to_string(),
+                "label node c1-gpu-1 zone=a --overwrite".to_string(),
+                "taint node c1-gpu-1 dedicated=gpu:NoSchedule --overwrite".to_string(),
+            ],
+            node_metadata_commands(
+                "c1-gpu-1",
+                &labels,
+                &["dedicated=gpu:NoSchedule".to_string()]
+            )
+        );
+    }
+}
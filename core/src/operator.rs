@@ -1,32 +1,64 @@
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{Receiver, SyncSender};
-use std::sync::{mpsc, Arc};
-use std::time::Duration;
-use log::{error, info};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use log::{error, info, warn};
 
-use proxmox_client::model::AccessData;
+use proxmox_client::model::{AccessData, VmConfig, VmConfigDetails};
+use proxmox_client::ClientOperations;
 use crate::event::Event;
-use crate::{Dispatcher, Error, Repository};
-use crate::dispatcher::HELM_CMD;
-use crate::model::{AppStatus, AppStatusType, Cluster, ClusterHeader, ClusterNode, ClusterNodeLock, ClusterNodeStatus, ClusterNodeType, ClusterRequest, ClusterResource, ClusterStatus, HelmApp, kube, KubeStatus, LogEntry};
+use crate::{addons, agent, autoscaler, certificates, cloud_init, dispatcher, distribution, hardware, import, ipam, keys, network, node_metadata, node_pool, os_image, preflight, recovery, supported, tags, validation, DefaultClusterConfigurationGenerator, Dispatcher, Error, Repository};
+use crate::model::{AddonStatus, AppStatus, AppStatusType, AutoscalingSettings, BackupSettings, CertificateStatus, Cluster, ClusterAddon, ClusterHeader, ClusterNode, ClusterNodeLock, ClusterNodeStatus, ClusterNodeType, ClusterRequest, ClusterResource, ClusterSecret, ClusterSecretsRequest, ClusterStatus, Distribution, ExecutionPlan, HardwareProfile, HelmApp, ImportClusterRequest, IpAllocation, IpPool, KeyPair, kube, KubeVersion, KubeStatus, LogEntry, NodeDiagnostics, NodePool, PreflightReport};
 use crate::model::helm::InstalledRelease;
+use crate::preflight::RequestedVm;
+use crate::recovery::VmMetadata;
 
 
 pub struct Config {
     pub worker_thread_probe_duration: u64,
+    #[doc = "Interval of autoscaler checks. Unit: seconds"]
+    pub autoscaler_interval: u64,
+    #[doc = "Proxmox API token access of autoscaler, tickets of users who saved autoscaling settings are used without it"]
+    pub autoscaler_access: Option<AccessData>,
+    #[doc = "Interval of synchronization of node labels and taints. Unit: seconds"]
+    pub node_metadata_sync_interval: u64,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             worker_thread_probe_duration: 500,
+            autoscaler_interval: 60,
+            autoscaler_access: None,
+            node_metadata_sync_interval: 600,
         }
     }
 }
 
+// Proxmox tickets are valid for 2 hours
+const TICKET_RENEWAL_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// Ticket of a user isn't renewed longer than this after autoscaling settings have been saved
+const TICKET_MAX_LIFETIME: Duration = Duration::from_secs(8 * 60 * 60);
+
+/// Ticket of the user who saved autoscaling settings of the cluster.
+#[derive(Clone)]
+struct AutoscalerTicket {
+    access: AccessData,
+    #[doc = "Ticket of the web session, the access is dropped when the session logs out"]
+    session_ticket: String,
+    saved: Instant,
+    #[doc = "Time of the last renewal, `None` when the ticket hasn't been renewed yet"]
+    renewed: Option<Instant>,
+}
+
+type AutoscalerAccess = Arc<Mutex<HashMap<String, AutoscalerTicket>>>;
+
 pub struct Operator {
     executor: Option<std::thread::JoinHandle<()>>,
+    autoscaler: Option<std::thread::JoinHandle<()>>,
+    autoscaler_access: AutoscalerAccess,
     tx: SyncSender<Event>,
     shutdown: Arc<AtomicBool>,
     repository: Arc<Repository>,
@@ -40,6 +72,9 @@ impl Drop for Operator {
             Some(v) => v.join().expect("cannot join thread"),
             None => info!("Executor not exists"),
         }
+        if let Some(v) = self.autoscaler.take() {
+            v.join().expect("cannot join thread");
+        }
     }
 }
 
@@ -47,11 +82,52 @@ impl Operator {
     pub fn new(config: Config, dispatcher: Dispatcher, repository: Arc<Repository>) -> Self {
         let (tx, rx): (SyncSender<Event>, Receiver<Event>) = mpsc::sync_channel(10);
         let shutdown = Arc::new(AtomicBool::from(false));
+        let autoscaler_access: AutoscalerAccess = Arc::new(Mutex::new(HashMap::new()));
+
+        let autoscaler = {
+            let shutdown = shutdown.clone();
+            let repository = repository.clone();
+            let tx = tx.clone();
+            let access = autoscaler_access.clone();
+            let config_access = config.autoscaler_access.clone();
+            let probe_duration = Duration::from_millis(config.worker_thread_probe_duration);
+            let interval = Duration::from_secs(config.autoscaler_interval);
+            let sync_interval = Duration::from_secs(config.node_metadata_sync_interval);
+            std::thread::spawn(move || {
+                info!("Autoscaler thread has been started");
+                let mut last_check = Instant::now();
+                let mut last_sync = Instant::now();
+                loop {
+                    std::thread::sleep(probe_duration);
+                    if shutdown.load(Ordering::SeqCst) {
+                        info!("Autoscaler thread has been requested to shut down");
+                        return;
+                    }
+                    if last_sync.elapsed() >= sync_interval {
+                        last_sync = Instant::now();
+                        if let Err(e) = request_node_metadata_sync(&repository, &tx) {
+                            error!("Node metadata synchronization failed: [{}]", e);
+                        }
+                    }
+                    if last_check.elapsed() < interval {
+                        continue;
+                    }
+                    last_check = Instant::now();
+                    if let Err(e) =
+                        request_autoscaling(&repository, &tx, config_access.as_ref(), &access)
+                    {
+                        error!("Autoscaler check failed: [{}]", e);
+                    }
+                }
+            })
+        };
 
         Operator {
             repository,
             shutdown: shutdown.clone(),
             tx,
+            autoscaler: Some(autoscaler),
+            autoscaler_access,
             executor: Some(std::thread::spawn(move || {
                 info!("Operator worker thread has been started");
                 loop {
@@ -84,24 +160,130 @@ impl Operator {
 
     pub fn create_cluster(
         &self,
+        proxmox_client: ClientOperations,
         access: AccessData,
         cluster_request: ClusterRequest,
     ) -> crate::Result<()> {
         info!("Start creating cluster");
+        let mut cluster = self.new_cluster(&proxmox_client, cluster_request)?;
+        ipam::assign_ip_addresses(
+            &self.repository,
+            &cluster.cluster_name,
+            &cluster.network,
+            &mut cluster.nodes,
+        )?;
+        let cluster_name = cluster.cluster_name.clone();
+        self.repository.save_cluster(cluster)?;
+
+        self.tx.send(Event::CreateCluster {
+            access,
+            cluster_name,
+        })?;
+
+        Ok(())
+    }
+
+    /// Actions of the cluster creation, the request is validated the same way but nothing
+    /// is saved or run.
+    pub fn plan_create_cluster(
+        &self,
+        proxmox_client: &ClientOperations,
+        cluster_request: ClusterRequest,
+    ) -> crate::Result<ExecutionPlan> {
+        let mut cluster = self.new_cluster(proxmox_client, cluster_request)?;
+        ipam::pick_ip_addresses(&self.repository, &cluster.network, &mut cluster.nodes)?;
+        Ok(dispatcher::plan_create_cluster(&cluster)?)
+    }
+
+    /// Validated cluster of the request, IP addresses aren't assigned yet.
+    fn new_cluster(
+        &self,
+        proxmox_client: &ClientOperations,
+        cluster_request: ClusterRequest,
+    ) -> crate::Result<Cluster> {
         let cluster_name = cluster_request.cluster_name.clone();
 
         if (self.repository.get_cluster(&cluster_name)?).is_some() {
             return Err(Error::ResourceAlreadyExists);
         }
 
-        let cluster = Cluster {
+        let ssh_key = if cluster_request.ssh_key.private_key.trim().is_empty() {
+            KeyPair::default()
+        } else {
+            keys::validate_key_pair(&cluster_request.ssh_key).map_err(Error::Generic)?
+        };
+        for public_key in cluster_request.authorized_keys.iter() {
+            keys::validate_public_key(public_key).map_err(Error::Generic)?;
+        }
+        let proxmox_vm_ids = validation::proxmox_vm_ids(proxmox_client)?;
+        validation::into_result(validation::cluster_request(
+            &cluster_request,
+            &validation::Context {
+                clusters: &self.repository.get_clusters()?,
+                ip_pools: &self.repository.ip_pools()?,
+                proxmox_vm_ids: &proxmox_vm_ids,
+            },
+        ))?;
+
+        network::validate(&cluster_request.network, &cluster_request.nodes)?;
+        network::validate_bridges(
+            &cluster_request.network,
+            &cluster_request.nodes,
+            &cluster_request.node,
+            &network::proxmox_bridges(proxmox_client, &cluster_request.node)?,
+        )?;
+        for node in cluster_request.nodes.iter() {
+            hardware::validate(&node.hardware, node.memory).map_err(Error::Generic)?;
+            node_metadata::validate(node).map_err(Error::Generic)?;
+        }
+        if let Some(cloud_init) = cluster_request.cloud_init.as_ref() {
+            cloud_init::validate(cloud_init).map_err(Error::Generic)?;
+        }
+        let cloud_init = cloud_init::settings(
+            proxmox_client,
+            &cluster_request.node,
+            cluster_request.cloud_init.clone(),
+            &cluster_request.network,
+            &cluster_request.nodes,
+        )?;
+        let addons = match cluster_request.distribution {
+            Distribution::MicroK8s => addons::with_required(&cluster_request.addons),
+            _ => vec![],
+        };
+        for addon in addons.iter() {
+            addons::validate(addon).map_err(Error::Generic)?;
+        }
+        let resource_pool = cluster_request
+            .resource_pool
+            .as_ref()
+            .map(|i| i.trim().to_string())
+            .filter(|i| !i.is_empty());
+        if let Some(pool) = resource_pool.as_ref() {
+            tags::validate_resource_pool(pool).map_err(Error::Generic)?;
+        }
+        let catalog = supported::os_images();
+        let catalog_image = os_image::find(&catalog, &cluster_request.os_image);
+        os_image::file_name(&cluster_request.os_image, catalog_image).map_err(Error::Generic)?;
+        let os_family = catalog_image.map(|i| i.os_family.clone()).unwrap_or_default();
+        preflight::require_passed(
+            &self.preflight_create_cluster(proxmox_client, &cluster_request)?,
+        )?;
+
+        Ok(Cluster {
             node: cluster_request.node,
             cluster_name: cluster_request.cluster_name.clone(),
+            cluster_id: uuid::Uuid::new_v4().to_string(),
             kube_version: Some(cluster_request.kube_version),
             os_image: Some(cluster_request.os_image),
             os_image_storage: Some(cluster_request.os_image_storage),
             cluster_config: "".to_string(),
-            ssh_key: cluster_request.ssh_key,
+            ssh_key,
+            ssh_key_type: cluster_request.ssh_key_type,
+            authorized_keys: cluster_request
+                .authorized_keys
+                .iter()
+                .map(|i| i.trim().to_string())
+                .collect(),
             node_username: cluster_request.node_username,
             node_password: cluster_request.node_password,
             helm_apps: cluster_request
@@ -124,30 +306,46 @@ impl Operator {
             nodes: cluster_request.nodes,
             network: cluster_request.network,
             status: ClusterStatus::Pending,
-        };
-        self.repository.save_cluster(cluster)?;
-
-        self.tx.send(Event::CreateCluster {
-            access,
-            cluster_name,
-        })?;
-
-        Ok(())
+            backup: None,
+            cloud_init,
+            node_pools: vec![],
+            autoscaling: None,
+            autoscaling_state: Default::default(),
+            addons,
+            distribution: cluster_request.distribution,
+            os_family,
+            resource_pool,
+            missing_secrets: vec![],
+        })
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn change_node_resources(
         &self,
+        proxmox_client: ClientOperations,
         access: AccessData,
         cluster_name: String,
         node_name: String,
         cores: u16,
         memory: u32,
+        hardware: Option<HardwareProfile>,
     ) -> crate::Result<()> {
         info!("Start changing node resources");
         let mut cluster = self
             .repository
             .get_cluster(&cluster_name)?
             .ok_or(Error::Generic("Cannot get cluster".to_string()))?;
+        require_no_exclusive_operation(&cluster)?;
+        if let Some(hardware) = hardware.as_ref() {
+            hardware::validate(hardware, memory).map_err(Error::Generic)?;
+        }
+        preflight::require_passed(&self.preflight_change_node_resources(
+            &proxmox_client,
+            &cluster_name,
+            &node_name,
+            cores,
+            memory,
+        )?)?;
 
         for node in cluster.nodes.iter_mut() {
             if node.name == node_name {
@@ -163,12 +361,14 @@ impl Operator {
             node_name,
             cores,
             memory,
+            hardware,
         })?;
         Ok(())
     }
 
     pub fn add_node_cluster(
         &self,
+        proxmox_client: &ClientOperations,
         access: AccessData,
         cluster_name: String,
         node_request: ClusterNode,
@@ -178,8 +378,23 @@ impl Operator {
             .repository
             .get_cluster(&cluster_name)?
             .ok_or(Error::Generic("Cannot get cluster".to_string()))?;
-
-        let mut node_request = node_request;
+        require_no_exclusive_operation(&cluster)?;
+        self.validate_new_nodes(proxmox_client, &cluster, std::slice::from_ref(&node_request))?;
+        cluster.cloud_init = cloud_init::settings(
+            proxmox_client,
+            &cluster.node,
+            cluster.cloud_init.clone(),
+            &cluster.network,
+            std::slice::from_ref(&node_request),
+        )?;
+        let mut node_request = vec![node_request];
+        ipam::assign_ip_addresses(
+            &self.repository,
+            &cluster_name,
+            &cluster.network,
+            &mut node_request,
+        )?;
+        let mut node_request = node_request.remove(0);
         node_request.lock = Some(ClusterNodeLock::Create);
 
         cluster.nodes.push(node_request.clone());
@@ -201,65 +416,1033 @@ impl Operator {
         Ok(node_request)
     }
 
+    /// Actions of adding the node, the request is validated the same way but nothing
+    /// is saved or run.
+    pub fn plan_add_node_cluster(
+        &self,
+        proxmox_client: &ClientOperations,
+        cluster_name: &str,
+        node_request: ClusterNode,
+    ) -> crate::Result<ExecutionPlan> {
+        let mut cluster = self
+            .repository
+            .get_cluster(cluster_name)?
+            .ok_or(Error::Generic("Cannot get cluster".to_string()))?;
+        require_no_exclusive_operation(&cluster)?;
+        self.validate_new_nodes(proxmox_client, &cluster, std::slice::from_ref(&node_request))?;
+        cluster.cloud_init = cloud_init::settings(
+            proxmox_client,
+            &cluster.node,
+            cluster.cloud_init.clone(),
+            &cluster.network,
+            std::slice::from_ref(&node_request),
+        )?;
+        let mut nodes = vec![node_request];
+        ipam::pick_ip_addresses(&self.repository, &cluster.network, &mut nodes)?;
+        let node_name = nodes[0].name.clone();
+        cluster.nodes.extend(nodes);
+        Ok(dispatcher::plan_add_node_to_cluster(&cluster, &node_name)?)
+    }
+
+    fn validate_new_nodes(
+        &self,
+        proxmox_client: &ClientOperations,
+        cluster: &Cluster,
+        nodes: &[ClusterNode],
+    ) -> crate::Result<()> {
+        let proxmox_vm_ids = validation::proxmox_vm_ids(proxmox_client)?;
+        let clusters = self.repository.get_clusters()?;
+        let ip_pools = self.repository.ip_pools()?;
+        for node_request in nodes {
+            if cluster.nodes.iter().any(|i| i.name == node_request.name) {
+                return Err(Error::Generic(format!(
+                    "Node [{}] already exists",
+                    node_request.name
+                )));
+            }
+            validation::into_result(validation::new_node(
+                cluster,
+                node_request,
+                &validation::Context {
+                    clusters: &clusters,
+                    ip_pools: &ip_pools,
+                    proxmox_vm_ids: &proxmox_vm_ids,
+                },
+            ))?;
+            hardware::validate(&node_request.hardware, node_request.memory)
+                .map_err(Error::Generic)?;
+            node_metadata::validate(node_request).map_err(Error::Generic)?;
+        }
+        network::validate(&cluster.network, nodes)?;
+        network::validate_bridges(
+            &cluster.network,
+            nodes,
+            &cluster.node,
+            &network::proxmox_bridges(proxmox_client, &cluster.node)?,
+        )?;
+        preflight::require_passed(&self.preflight_add_nodes(
+            proxmox_client,
+            &cluster.cluster_name,
+            nodes,
+        )?)?;
+        Ok(())
+    }
+
+    /// Checks that the Proxmox node has capacity for VMs of the new cluster.
+    pub fn preflight_create_cluster(
+        &self,
+        proxmox_client: &ClientOperations,
+        cluster_request: &ClusterRequest,
+    ) -> crate::Result<PreflightReport> {
+        let requested = cluster_request
+            .nodes
+            .iter()
+            .map(|i| RequestedVm::new(&cluster_request.cluster_name, i, cluster_request.disk_size))
+            .collect::<Vec<RequestedVm>>();
+        Ok(preflight::run(
+            proxmox_client,
+            &cluster_request.node,
+            &[],
+            &requested,
+        )?)
+    }
+
+    pub fn preflight_add_nodes(
+        &self,
+        proxmox_client: &ClientOperations,
+        cluster_name: &str,
+        nodes: &[ClusterNode],
+    ) -> crate::Result<PreflightReport> {
+        let cluster = self
+            .repository
+            .get_cluster(cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        let requested = nodes
+            .iter()
+            .map(|i| RequestedVm::new(cluster_name, i, cluster.disk_size))
+            .collect::<Vec<RequestedVm>>();
+        Ok(preflight::run(proxmox_client, &cluster.node, &[], &requested)?)
+    }
+
+    /// Resized node doesn't allocate disk, its current resources are replaced.
+    pub fn preflight_change_node_resources(
+        &self,
+        proxmox_client: &ClientOperations,
+        cluster_name: &str,
+        node_name: &str,
+        cores: u16,
+        memory: u32,
+    ) -> crate::Result<PreflightReport> {
+        let cluster = self
+            .repository
+            .get_cluster(cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        let node = cluster
+            .nodes
+            .iter()
+            .find(|i| i.name == node_name)
+            .ok_or(Error::ResourceNotFound)?;
+        let requested = RequestedVm {
+            cores,
+            memory,
+            disk_size: 0,
+            ..RequestedVm::new(cluster_name, node, cluster.disk_size)
+        };
+        Ok(preflight::run(
+            proxmox_client,
+            &cluster.node,
+            &[node.vm_id],
+            &[requested],
+        )?)
+    }
+
+    /// Creates or updates the node pool and scales it to the desired count. New nodes get
+    /// names, VM IDs and IP addresses allocated, removed nodes are picked by the dispatcher.
+    pub fn save_node_pool(
+        &self,
+        proxmox_client: ClientOperations,
+        access: AccessData,
+        cluster_name: String,
+        pool: NodePool,
+    ) -> crate::Result<()> {
+        info!("Save node pool");
+        node_pool::validate(&pool).map_err(Error::Generic)?;
+        let mut cluster = self
+            .repository
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        require_no_exclusive_operation(&cluster)?;
+
+        let current = cluster
+            .nodes
+            .iter()
+            .filter(|i| i.pool.as_ref() == Some(&pool.name))
+            .count();
+        let desired = usize::from(pool.desired_count);
+        if desired < current && pool.node_type == ClusterNodeType::Master {
+            let masters = cluster
+                .nodes
+                .iter()
+                .filter(|i| i.node_type == ClusterNodeType::Master)
+                .count();
+            if masters <= current - desired {
+                return Err(Error::Generic(
+                    "Cluster has to keep at least one master node".to_string(),
+                ));
+            }
+        }
+        match cluster.node_pools.iter_mut().find(|i| i.name == pool.name) {
+            Some(v) => *v = pool.clone(),
+            None => cluster.node_pools.push(pool.clone()),
+        }
+
+        if desired > current {
+            let mut nodes = node_pool::new_nodes(
+                &proxmox_client,
+                &self.repository,
+                &cluster,
+                &pool,
+                desired - current,
+            )?;
+            self.validate_new_nodes(&proxmox_client, &cluster, &nodes)?;
+            cluster.cloud_init = cloud_init::settings(
+                &proxmox_client,
+                &cluster.node,
+                cluster.cloud_init.clone(),
+                &cluster.network,
+                &nodes,
+            )?;
+            ipam::assign_ip_addresses(
+                &self.repository,
+                &cluster_name,
+                &cluster.network,
+                &mut nodes,
+            )?;
+            for mut node in nodes {
+                node.lock = Some(ClusterNodeLock::Create);
+                cluster.nodes.push(node);
+            }
+            self.repository.save_cluster(cluster)?;
+            self.repository.save_log(LogEntry::info(
+                &cluster_name,
+                format!("Scaling up node pool [{}] has been started", pool.name),
+            ))?;
+            self.tx.send(Event::ScaleUpNodePool {
+                access,
+                cluster_name,
+                pool_name: pool.name,
+            })?;
+        } else if desired < current {
+            self.repository.save_cluster(cluster)?;
+            self.repository.save_log(LogEntry::info(
+                &cluster_name,
+                format!("Scaling down node pool [{}] has been started", pool.name),
+            ))?;
+            self.tx.send(Event::ScaleDownNodePool {
+                access,
+                cluster_name,
+                pool_name: pool.name,
+            })?;
+        } else {
+            self.repository.save_cluster(cluster)?;
+        }
+        Ok(())
+    }
+
+    /// Pool can be deleted when it has no nodes, nodes are removed by scaling it to 0.
+    pub fn delete_node_pool(&self, cluster_name: &str, pool_name: &str) -> crate::Result<()> {
+        info!("Delete node pool");
+        let mut cluster = self
+            .repository
+            .get_cluster(cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        if cluster
+            .nodes
+            .iter()
+            .any(|i| i.pool.as_deref() == Some(pool_name))
+        {
+            return Err(Error::Generic(format!(
+                "Node pool [{}] still has nodes, scale it to 0 first",
+                pool_name
+            )));
+        }
+        if cluster
+            .autoscaling
+            .as_ref()
+            .is_some_and(|i| i.pool_name == pool_name)
+        {
+            return Err(Error::Generic(format!(
+                "Node pool [{}] is autoscaled, delete autoscaling settings first",
+                pool_name
+            )));
+        }
+        cluster.node_pools.retain(|i| i.name != pool_name);
+        self.repository.save_cluster(cluster)?;
+        Ok(())
+    }
+
+    /// Saves autoscaling settings of the cluster. Autoscaler works with the Proxmox access of
+    /// the user and renews its ticket, the access is kept in memory only.
+    pub fn update_autoscaling(
+        &self,
+        access: AccessData,
+        cluster_name: &str,
+        settings: AutoscalingSettings,
+    ) -> crate::Result<()> {
+        info!("Update autoscaling settings");
+        let mut cluster = self
+            .repository
+            .get_cluster(cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        autoscaler::validate(&settings, &cluster.node_pools).map_err(Error::Generic)?;
+        if cluster
+            .autoscaling
+            .as_ref()
+            .is_some_and(|i| i.pool_name != settings.pool_name)
+        {
+            cluster.autoscaling_state = Default::default();
+        }
+        cluster.autoscaling = Some(settings);
+        self.repository.save_cluster(cluster)?;
+
+        let mut autoscaler_access = self
+            .autoscaler_access
+            .lock()
+            .map_err(|e| Error::Generic(e.to_string()))?;
+        autoscaler_access.insert(
+            cluster_name.to_string(),
+            AutoscalerTicket {
+                session_ticket: access.token.ticket.clone(),
+                access,
+                saved: Instant::now(),
+                renewed: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Drops autoscaler access which has been saved by the logged out session.
+    pub fn revoke_autoscaler_access(&self, access: &AccessData) -> crate::Result<()> {
+        self.autoscaler_access
+            .lock()
+            .map_err(|e| Error::Generic(e.to_string()))?
+            .retain(|_, i| i.session_ticket != access.token.ticket);
+        Ok(())
+    }
+
+    pub fn delete_autoscaling(&self, cluster_name: &str) -> crate::Result<()> {
+        info!("Delete autoscaling settings");
+        let mut cluster = self
+            .repository
+            .get_cluster(cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        cluster.autoscaling = None;
+        cluster.autoscaling_state = Default::default();
+        self.repository.save_cluster(cluster)?;
+        self.autoscaler_access
+            .lock()
+            .map_err(|e| Error::Generic(e.to_string()))?
+            .remove(cluster_name);
+        Ok(())
+    }
+
     pub fn delete_cluster(&self, access: AccessData, cluster_name: String) -> crate::Result<()> {
         info!("Start deleting cluster");
         let mut cluster = self
             .repository
             .get_cluster(&cluster_name)?
             .ok_or(Error::Generic("Cannot get cluster".to_string()))?;
+        require_no_exclusive_operation(&cluster)?;
         cluster.status = ClusterStatus::Destroying;
         self.repository.save_cluster(cluster)?;
         self.repository.save_log(LogEntry::info(
             &cluster_name,
-            "Cluster deletion started".to_string(),
+            "Cluster deletion started".to_string(),
+        ))?;
+
+        self.tx.send(Event::DeleteCluster {
+            access,
+            cluster_name,
+        })?;
+
+        Ok(())
+    }
+
+    pub fn plan_delete_cluster(&self, cluster_name: &str) -> crate::Result<ExecutionPlan> {
+        let cluster = self
+            .repository
+            .get_cluster(cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        require_no_exclusive_operation(&cluster)?;
+        Ok(dispatcher::plan_delete_cluster(&cluster))
+    }
+
+    pub fn delete_node_from_cluster(
+        &self,
+        access: AccessData,
+        cluster_name: String,
+        node_name: String,
+    ) -> crate::Result<ClusterNode> {
+        info!("Start deleting node from the cluster");
+        self.repository.save_log(LogEntry::info(
+            &cluster_name,
+            format!(
+                "Deleting node [{}] from cluster has been started",
+                node_name
+            ),
+        ))?;
+
+        let mut cluster = self
+            .repository
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        require_no_exclusive_operation(&cluster)?;
+        let node_to_delete = cluster
+            .nodes
+            .iter_mut()
+            .find(|i| i.name == node_name)
+            .ok_or(Error::ResourceNotFound)?;
+        node_to_delete.lock = Some(ClusterNodeLock::Delete);
+
+        let result = node_to_delete.clone();
+
+        self.repository.save_cluster(cluster)?;
+
+        self.tx.send(Event::DeleteNodeFromCluster {
+            access,
+            cluster_name,
+            node_name,
+        })?;
+        Ok(result)
+    }
+
+    pub fn plan_delete_node_from_cluster(
+        &self,
+        cluster_name: &str,
+        node_name: &str,
+    ) -> crate::Result<ExecutionPlan> {
+        let cluster = self
+            .repository
+            .get_cluster(cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        require_no_exclusive_operation(&cluster)?;
+        if !cluster.nodes.iter().any(|i| i.name == node_name) {
+            return Err(Error::ResourceNotFound);
+        }
+        Ok(dispatcher::plan_delete_node_from_cluster(&cluster, node_name)?)
+    }
+
+    pub fn create_cluster_snapshot(
+        &self,
+        access: AccessData,
+        cluster_name: String,
+        snapshot_name: String,
+        description: String,
+        freeze_fs: bool,
+    ) -> crate::Result<()> {
+        info!("Start creating cluster snapshot");
+        if !is_valid_snapshot_name(&snapshot_name) {
+            return Err(Error::Generic(format!(
+                "Invalid snapshot name [{}]",
+                snapshot_name
+            )));
+        }
+        let mut cluster = self
+            .repository
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        require_no_exclusive_operation(&cluster)?;
+        let previous_status = std::mem::replace(&mut cluster.status, ClusterStatus::Snapshotting);
+        self.repository.save_cluster(cluster)?;
+        self.repository.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Creating snapshot [{}] has been started", snapshot_name),
+        ))?;
+
+        self.tx.send(Event::CreateClusterSnapshot {
+            access,
+            cluster_name,
+            snapshot_name,
+            description,
+            freeze_fs,
+            previous_status,
+        })?;
+        Ok(())
+    }
+
+    pub fn rollback_cluster_snapshot(
+        &self,
+        access: AccessData,
+        cluster_name: String,
+        snapshot_name: String,
+    ) -> crate::Result<()> {
+        info!("Start rolling back cluster to snapshot");
+        let mut cluster = self
+            .repository
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        require_no_exclusive_operation(&cluster)?;
+        let previous_status = std::mem::replace(&mut cluster.status, ClusterStatus::Snapshotting);
+        self.repository.save_cluster(cluster)?;
+        self.repository.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Rollback to snapshot [{}] has been started", snapshot_name),
+        ))?;
+
+        self.tx.send(Event::RollbackClusterSnapshot {
+            access,
+            cluster_name,
+            snapshot_name,
+            previous_status,
+        })?;
+        Ok(())
+    }
+
+    pub fn delete_cluster_snapshot(
+        &self,
+        access: AccessData,
+        cluster_name: String,
+        snapshot_name: String,
+    ) -> crate::Result<()> {
+        info!("Start deleting cluster snapshot");
+        let cluster = self
+            .repository
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        require_no_exclusive_operation(&cluster)?;
+        self.repository.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Deleting snapshot [{}] has been started", snapshot_name),
+        ))?;
+
+        self.tx.send(Event::DeleteClusterSnapshot {
+            access,
+            cluster_name,
+            snapshot_name,
+        })?;
+        Ok(())
+    }
+
+    pub fn rotate_ssh_key(&self, access: AccessData, cluster_name: String) -> crate::Result<()> {
+        info!("Start rotating cluster SSH key");
+        let mut cluster = self
+            .repository
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        require_no_exclusive_operation(&cluster)?;
+        let previous_status = std::mem::replace(&mut cluster.status, ClusterStatus::RotatingSshKey);
+        self.repository.save_cluster(cluster)?;
+        self.repository.save_log(LogEntry::info(
+            &cluster_name,
+            "SSH key rotation has been started".to_string(),
+        ))?;
+
+        self.tx.send(Event::RotateSshKey {
+            access,
+            cluster_name,
+            previous_status,
+        })?;
+        Ok(())
+    }
+
+    pub fn refresh_certificates(&self, cluster_name: String, include_ca: bool) -> crate::Result<()> {
+        info!("Start refreshing cluster certificates");
+        let cluster = self
+            .repository
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        distribution::require_microk8s(&cluster, "Certificates").map_err(Error::Generic)?;
+        let master_count = cluster
+            .nodes
+            .iter()
+            .filter(|i| i.node_type == ClusterNodeType::Master)
+            .count();
+        if include_ca && master_count > 1 {
+            return Err(Error::Generic(
+                "CA can be refreshed only on cluster with single master node".to_string(),
+            ));
+        }
+        self.repository.save_log(LogEntry::info(
+            &cluster_name,
+            "Refreshing certificates has been started".to_string(),
+        ))?;
+
+        self.tx.send(Event::RefreshCertificates {
+            cluster_name,
+            include_ca,
+        })?;
+        Ok(())
+    }
+
+    /// Replaces labels and taints of the node, the ones which are not used anymore are
+    /// removed from the Kubernetes node.
+    pub fn update_node_metadata(
+        &self,
+        cluster_name: String,
+        node_name: String,
+        labels: HashMap<String, String>,
+        taints: Vec<String>,
+    ) -> crate::Result<()> {
+        info!("Update labels and taints of node");
+        let mut cluster = self
+            .repository
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        let node = cluster
+            .nodes
+            .iter()
+            .find(|i| i.name == node_name)
+            .cloned()
+            .ok_or(Error::ResourceNotFound)?;
+        let updated = ClusterNode {
+            labels,
+            taints,
+            ..node.clone()
+        };
+        node_metadata::validate(&updated).map_err(Error::Generic)?;
+
+        let labels = node_metadata::labels(&cluster, &updated);
+        let taints = node_metadata::taints(&cluster, &updated);
+        let removed_labels = node_metadata::labels(&cluster, &node)
+            .into_keys()
+            .filter(|i| !labels.contains_key(i))
+            .collect::<Vec<String>>();
+        let removed_taints = node_metadata::taints(&cluster, &node)
+            .into_iter()
+            .filter(|i| !taints.contains(i))
+            .collect::<Vec<String>>();
+        cluster
+            .nodes
+            .iter_mut()
+            .filter(|i| i.name == node_name)
+            .for_each(|i| *i = updated.clone());
+        self.repository.save_cluster(cluster)?;
+        self.repository.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Updating labels and taints of node [{}] has been started", node_name),
+        ))?;
+
+        self.tx.send(Event::SyncNodeMetadata {
+            cluster_name,
+            node_name: Some(node_name),
+            removed_labels,
+            removed_taints,
+            scheduled: false,
+        })?;
+        Ok(())
+    }
+
+    /// Re-applies labels and taints of all nodes which drifted in Kubernetes.
+    pub fn sync_node_metadata(&self, cluster_name: String) -> crate::Result<()> {
+        info!("Synchronize labels and taints of nodes");
+        self.repository
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        self.tx.send(Event::SyncNodeMetadata {
+            cluster_name,
+            node_name: None,
+            removed_labels: vec![],
+            removed_taints: vec![],
+            scheduled: false,
+        })?;
+        Ok(())
+    }
+
+    pub fn update_backup_settings(
+        &self,
+        access: AccessData,
+        cluster_name: String,
+        settings: Option<BackupSettings>,
+    ) -> crate::Result<()> {
+        info!("Update cluster backup settings");
+        if let Some(settings) = &settings {
+            if settings.storage.trim().is_empty() {
+                return Err(Error::Generic("Backup storage cannot be empty".to_string()));
+            }
+            if settings.keep_last == 0 {
+                return Err(Error::Generic(
+                    "At least one backup has to be kept".to_string(),
+                ));
+            }
+        }
+        let mut cluster = self
+            .repository
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        cluster.backup = settings;
+        self.repository.save_cluster(cluster)?;
+        self.repository.save_log(LogEntry::info(
+            &cluster_name,
+            "Backup settings have been updated".to_string(),
+        ))?;
+
+        self.tx.send(Event::ConfigureClusterBackup {
+            access,
+            cluster_name,
+        })?;
+        Ok(())
+    }
+
+    pub fn backup_cluster(&self, access: AccessData, cluster_name: String) -> crate::Result<()> {
+        info!("Start cluster backup");
+        let cluster = self
+            .repository
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        require_no_exclusive_operation(&cluster)?;
+        if cluster.backup.is_none() {
+            return Err(Error::Generic(
+                "Backup is not configured for the cluster".to_string(),
+            ));
+        }
+        self.repository.save_log(LogEntry::info(
+            &cluster_name,
+            "Cluster backup has been started".to_string(),
+        ))?;
+
+        self.tx.send(Event::BackupCluster {
+            access,
+            cluster_name,
+        })?;
+        Ok(())
+    }
+
+    pub fn restore_cluster_backup(
+        &self,
+        access: AccessData,
+        cluster_name: String,
+        backup_set_id: String,
+        node_name: Option<String>,
+    ) -> crate::Result<()> {
+        info!("Start restoring cluster from backup");
+        let cluster = self
+            .repository
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        require_no_exclusive_operation(&cluster)?;
+        if cluster.backup.is_none() {
+            return Err(Error::Generic(
+                "Backup is not configured for the cluster".to_string(),
+            ));
+        }
+        if let Some(node_name) = &node_name {
+            if !cluster.nodes.iter().any(|i| &i.name == node_name) {
+                return Err(Error::ResourceNotFound);
+            }
+        }
+        self.repository.save_log(LogEntry::info(
+            &cluster_name,
+            format!("Restore from backup [{}] has been started", backup_set_id),
         ))?;
 
-        self.tx.send(Event::DeleteCluster {
+        self.tx.send(Event::RestoreClusterBackup {
             access,
             cluster_name,
+            backup_set_id,
+            node_name,
         })?;
+        Ok(())
+    }
+
+    /// Rebuilds clusters missing in the repository from the metadata kept in the description
+    /// of node VMs. Recovered clusters stay out of sync until their secrets are entered again.
+    pub fn recover_clusters(&self, proxmox_client: ClientOperations) -> crate::Result<Vec<String>> {
+        info!("Recover clusters from Proxmox metadata");
+        let mut recovered: HashMap<String, (String, VmMetadata, VmConfigDetails, Vec<ClusterNode>)> =
+            HashMap::new();
+        for proxmox_node in proxmox_client.nodes()? {
+            for vm in proxmox_client.virtual_machines(&proxmox_node.node, None)? {
+                if !tags::parse(vm.tags.as_deref()).iter().any(|i| i == tags::MAKOON_TAG) {
+                    continue;
+                }
+                let config = proxmox_client.vm_config(&proxmox_node.node, vm.vm_id)?;
+                let metadata = match config
+                    .description
+                    .as_deref()
+                    .and_then(recovery::parse_description)
+                {
+                    Some(v) => v,
+                    None => {
+                        warn!("VM [{}] doesn't have Makoon metadata", vm.vm_id);
+                        continue;
+                    }
+                };
+                if self.repository.get_cluster(&metadata.cluster_name)?.is_some() {
+                    continue;
+                }
+                let mut node = recovery::node(vm.vm_id, &metadata, &config);
+                if node.ip_address.is_empty() {
+                    node.ip_address = proxmox_client
+                        .agent_network_interfaces(&proxmox_node.node, vm.vm_id)
+                        .ok()
+                        .and_then(|i| recovery::guest_ip_address(&i))
+                        .unwrap_or_default();
+                }
+                match recovered.get_mut(&metadata.cluster_id) {
+                    Some((_, master, master_config, nodes)) => {
+                        // network and settings of the cluster are read from a master node
+                        if master.node_type != ClusterNodeType::Master
+                            && metadata.node_type == ClusterNodeType::Master
+                        {
+                            *master = metadata;
+                            *master_config = config;
+                        }
+                        nodes.push(node);
+                    }
+                    None => {
+                        recovered.insert(
+                            metadata.cluster_id.clone(),
+                            (proxmox_node.node.clone(), metadata, config, vec![node]),
+                        );
+                    }
+                }
+            }
+        }
+
+        let mut result = vec![];
+        for (proxmox_node, metadata, config, nodes) in recovered.into_values() {
+            let cluster = recovery::cluster(&proxmox_node, &metadata, &config, nodes);
+            let cluster_name = cluster.cluster_name.clone();
+            let node_count = cluster.nodes.len();
+            self.repository.save_cluster(cluster)?;
+            self.repository.save_log(LogEntry::info(
+                &cluster_name,
+                format!(
+                    "Cluster has been recovered from [{}] VMs, SSH key and node password have to be entered again",
+                    node_count
+                ),
+            ))?;
+            result.push(cluster_name);
+        }
+        result.sort();
+        Ok(result)
+    }
 
+    /// Stores secrets of a recovered cluster, it's in sync once all of them are entered.
+    pub fn update_cluster_secrets(
+        &self,
+        cluster_name: &str,
+        secrets: ClusterSecretsRequest,
+    ) -> crate::Result<()> {
+        info!("Update cluster secrets");
+        let mut cluster = self
+            .repository
+            .get_cluster(cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        if let Some(ssh_key) = secrets.ssh_key {
+            cluster.ssh_key = keys::validate_key_pair(&ssh_key).map_err(Error::Generic)?;
+            cluster.missing_secrets.retain(|i| *i != ClusterSecret::SshKey);
+        }
+        if let Some(node_password) = secrets.node_password.filter(|i| !i.is_empty()) {
+            cluster.node_password = node_password;
+            cluster.missing_secrets.retain(|i| *i != ClusterSecret::NodePassword);
+        }
+        if cluster.missing_secrets.is_empty() && cluster.status == ClusterStatus::OutOfSync {
+            cluster.status = ClusterStatus::Sync;
+        }
+        self.repository.save_cluster(cluster)?;
+        self.repository.save_log(LogEntry::info(
+            cluster_name,
+            "Cluster secrets have been updated".to_string(),
+        ))?;
         Ok(())
     }
 
-    pub fn delete_node_from_cluster(
+    /// Takes over a MicroK8s cluster built outside of Makoon. Roles and version are read from
+    /// the cluster, the VMs get Makoon tags and metadata so they are managed like created ones.
+    pub fn import_cluster(
         &self,
-        access: AccessData,
-        cluster_name: String,
-        node_name: String,
-    ) -> crate::Result<ClusterNode> {
-        info!("Start deleting node from the cluster");
+        proxmox_client: ClientOperations,
+        request: ImportClusterRequest,
+    ) -> crate::Result<()> {
+        info!("Import cluster");
+        if self.repository.get_cluster(&request.cluster_name)?.is_some() {
+            return Err(Error::ResourceAlreadyExists);
+        }
+        if request.vm_ids.is_empty() {
+            return Err(Error::Generic("VMs of the cluster are missing".to_string()));
+        }
+        let ssh_key = keys::validate_key_pair(&request.ssh_key).map_err(Error::Generic)?;
+        validation::into_result(validation::imported_vm_ids(
+            &request.vm_ids,
+            &validation::Context {
+                clusters: &self.repository.get_clusters()?,
+                ip_pools: &[],
+                proxmox_vm_ids: &[],
+            },
+        ))?;
+
+        let mut vms = vec![];
+        for vm_id in request.vm_ids.iter() {
+            let config = proxmox_client.vm_config(&request.node, *vm_id)?;
+            let mut node = recovery::vm_node(*vm_id, &config);
+            if node.ip_address.is_empty() {
+                node.ip_address = proxmox_client
+                    .agent_network_interfaces(&request.node, *vm_id)
+                    .ok()
+                    .and_then(|i| recovery::guest_ip_address(&i))
+                    .ok_or(Error::Generic(format!(
+                        "Cannot find IP address of VM [{}]",
+                        vm_id
+                    )))?;
+            }
+            vms.push((node, config));
+        }
+
+        let mut ssh_client = ssh_client::Client::new();
+        ssh_client.connect(
+            &vms[0].0.ip_address,
+            &request.node_username,
+            &ssh_key.private_key,
+            &ssh_key.public_key,
+        )?;
+        import::check_running(&ssh_client.execute(import::STATUS_COMMAND)?)
+            .map_err(Error::Generic)?;
+        let distribution: &dyn distribution::KubernetesDistribution = &distribution::MicroK8s;
+        let kube_nodes: kube::Nodes = serde_json::from_str(&ssh_client.execute(
+            format!("sudo {} get nodes -o json", distribution.kubectl()).as_str(),
+        )?)?;
+
+        let mut nodes = vec![];
+        let mut kube_version = None;
+        for item in kube_nodes.items.iter() {
+            let (vm_node, _) = vms
+                .iter()
+                .find(|(node, config)| {
+                    import::internal_ip(item) == Some(node.ip_address.as_str())
+                        || config.name.as_deref() == Some(item.metadata.name.as_str())
+                })
+                .ok_or(Error::Generic(format!(
+                    "Kubernetes node [{}] is not one of the imported VMs",
+                    item.metadata.name
+                )))?;
+            let (name, host_name) = import::node_name(&request.cluster_name, &item.metadata.name);
+            nodes.push(ClusterNode {
+                name,
+                host_name,
+                node_type: import::node_type(item),
+                ..vm_node.clone()
+            });
+            if kube_version.is_none() {
+                kube_version = item
+                    .status
+                    .node_info
+                    .as_ref()
+                    .and_then(|i| import::kube_version(&i.kubelet_version));
+            }
+        }
+        if let Some((node, _)) = vms
+            .iter()
+            .find(|(node, _)| !nodes.iter().any(|i| i.vm_id == node.vm_id))
+        {
+            return Err(Error::Generic(format!(
+                "VM [{}] is not a node of the Kubernetes cluster",
+                node.vm_id
+            )));
+        }
+        let master = nodes
+            .iter()
+            .find(|i| i.node_type == ClusterNodeType::Master)
+            .cloned()
+            .ok_or(Error::Generic("Cluster doesn't have a master node".to_string()))?;
+        let master_config = &vms
+            .iter()
+            .find(|(node, _)| node.vm_id == master.vm_id)
+            .ok_or(Error::ResourceNotFound)?
+            .1;
+
+        let cluster_config = distribution.kubeconfig(
+            &ssh_client.execute(distribution.kubeconfig_command().as_str())?,
+            &master,
+        );
+        let installed_releases: Vec<InstalledRelease> = ssh_client.execute_to(
+            &helm_client::new(distribution.helm())
+                .sudo()
+                .list()
+                .all()
+                .json()
+                .build(),
+        )?;
+
+        let cluster = Cluster {
+            cluster_id: uuid::Uuid::new_v4().to_string(),
+            kube_version,
+            cluster_config,
+            ssh_key,
+            node_username: request.node_username,
+            node_password: request.node_password,
+            helm_apps: installed_releases.iter().map(import::helm_app).collect(),
+            status: ClusterStatus::Sync,
+            distribution: Distribution::MicroK8s,
+            ..recovery::cluster_of_vms(&request.node, &request.cluster_name, master_config, nodes)
+        };
+        for node in cluster.nodes.iter() {
+            let config = &vms
+                .iter()
+                .find(|(vm, _)| vm.vm_id == node.vm_id)
+                .ok_or(Error::ResourceNotFound)?
+                .1;
+            let description = match config.description.as_deref().filter(|i| !i.is_empty()) {
+                Some(v) => format!("{}\n\n{}", v, recovery::description(&cluster, node)),
+                None => recovery::description(&cluster, node),
+            };
+            proxmox_client.update_config(VmConfig {
+                vm_id: node.vm_id,
+                node: cluster.node.clone(),
+                tags: Some(import::merge_tags(
+                    config.tags.as_deref(),
+                    &tags::vm_tags(&cluster, node),
+                )),
+                description: Some(description),
+                ..Default::default()
+            })?;
+        }
+
+        let cluster_name = cluster.cluster_name.clone();
+        let node_count = cluster.nodes.len();
+        self.repository.save_cluster(cluster)?;
         self.repository.save_log(LogEntry::info(
             &cluster_name,
-            format!(
-                "Deleting node [{}] from cluster has been started",
-                node_name
-            ),
+            format!("Cluster with [{}] nodes has been imported", node_count),
         ))?;
+        Ok(())
+    }
 
-        let mut cluster = self
-            .repository
-            .get_cluster(&cluster_name)?
-            .ok_or(Error::ResourceNotFound)?;
-        let node_to_delete = cluster
-            .nodes
-            .iter_mut()
-            .find(|i| i.name == node_name)
-            .ok_or(Error::ResourceNotFound)?;
-        node_to_delete.lock = Some(ClusterNodeLock::Delete);
+    pub fn ip_pools(&self) -> crate::Result<Vec<IpPool>> {
+        Ok(self.repository.ip_pools()?)
+    }
 
-        let result = node_to_delete.clone();
+    pub fn save_ip_pool(&self, pool: IpPool) -> crate::Result<()> {
+        info!("Save IP pool");
+        if pool.bridge.trim().is_empty() {
+            return Err(Error::Generic("Bridge cannot be empty".to_string()));
+        }
+        ipam::validate_pool(&pool)?;
+        self.repository.save_ip_pool(pool)?;
+        Ok(())
+    }
 
-        self.repository.save_cluster(cluster)?;
+    pub fn delete_ip_pool(&self, bridge: &str) -> crate::Result<()> {
+        info!("Delete IP pool");
+        self.repository.delete_ip_pool(bridge)?;
+        Ok(())
+    }
 
-        self.tx.send(Event::DeleteNodeFromCluster {
-            access,
-            cluster_name,
-            node_name,
-        })?;
-        Ok(result)
+    pub fn ip_allocations(&self) -> crate::Result<Vec<IpAllocation>> {
+        Ok(self.repository.ip_allocations()?)
+    }
+
+    pub fn generate_cluster_configuration(
+        &self,
+        proxmox_client: ClientOperations,
+    ) -> crate::Result<ClusterRequest> {
+        DefaultClusterConfigurationGenerator::new(proxmox_client, self.repository.clone())
+            .generate()
     }
 
+    /// Checks addresses of the nodes against all addresses already used by Makoon and assigns
+    /// free addresses from the IP pool of the bridge to nodes without an address.
+    /// Nodes using DHCP are skipped, their address is discovered after VM start.
     pub fn get_clusters(&self) -> crate::Result<Vec<ClusterHeader>> {
         info!("Get clusters");
         let repo = self.repository.clone();
@@ -277,7 +1460,11 @@ impl Operator {
                         name: i.cluster_name.clone(),
                         cores_sum: cores.unwrap_or(0),
                         memory_sum: memory.unwrap_or(0),
-                        disk_size_sum: node_count * i.disk_size,
+                        disk_size_sum: i
+                            .nodes
+                            .iter()
+                            .map(|n| n.disk_size.unwrap_or(i.disk_size))
+                            .sum(),
                         nodes_count: u16::try_from(node_count).unwrap_or_default(),
                         status: i.status,
                     }
@@ -304,7 +1491,7 @@ impl Operator {
             .nodes
             .iter()
             .map(|i| ClusterNodeStatus {
-                name: format!("{}-{}", cluster.cluster_name, i.name),
+                name: i.host_name(&cluster.cluster_name),
                 status: KubeStatus::Unknown,
             })
             .collect();
@@ -323,7 +1510,13 @@ impl Operator {
         } else {
         };
         let nodes_status =
-            ssh_client.execute("sudo microk8s kubectl get nodes -o json --request-timeout='5s'");
+            ssh_client.execute(
+                format!(
+                    "sudo {} get nodes -o json --request-timeout='5s'",
+                    distribution::of(&cluster).kubectl()
+                )
+                .as_str(),
+            );
         let nodes_status = match nodes_status {
             Ok(v) => v,
             Err(_) => return Ok(result),
@@ -347,6 +1540,113 @@ impl Operator {
         Ok(result)
     }
 
+    pub fn certificates_status(&self, cluster_name: &str) -> crate::Result<Vec<CertificateStatus>> {
+        info!("Get certificates status");
+        let cluster = self
+            .get_cluster(cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        distribution::require_microk8s(&cluster, "Certificates").map_err(Error::Generic)?;
+
+        let mut result: Vec<CertificateStatus> = Vec::new();
+        for node in cluster
+            .nodes
+            .iter()
+            .filter(|i| i.node_type == ClusterNodeType::Master)
+        {
+            let mut ssh_client = ssh_client::Client::new();
+            let output = ssh_client
+                .connect(
+                    &node.ip_address,
+                    &cluster.node_username,
+                    &cluster.ssh_key.private_key,
+                    &cluster.ssh_key.public_key,
+                )
+                .and_then(|_| ssh_client.execute(&certificates::expiry_command()));
+            match output {
+                Ok(output) => result.extend(certificates::parse_expiry(&node.name, &output)),
+                Err(e) => result.push(certificates::unreadable(&node.name, e)),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Collects node state through the QEMU guest agent, so it works even when SSH
+    /// or networking of the node is broken.
+    pub fn node_diagnostics(
+        &self,
+        proxmox_client: ClientOperations,
+        cluster_name: &str,
+        node_name: &str,
+    ) -> crate::Result<NodeDiagnostics> {
+        info!("Get node diagnostics");
+        let cluster = self
+            .get_cluster(cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        let node = cluster
+            .nodes
+            .iter()
+            .find(|i| i.name == node_name)
+            .ok_or(Error::ResourceNotFound)?;
+
+        let mut result = NodeDiagnostics {
+            node_name: node.name.clone(),
+            vm_id: node.vm_id,
+            ..Default::default()
+        };
+        match proxmox_client.agent_ping(&cluster.node, node.vm_id) {
+            Ok(_) => result.agent_available = true,
+            Err(e) => result.errors.push(format!("QEMU guest agent is not available: {}", e)),
+        }
+        if result.agent_available {
+            match agent::execute(
+                &proxmox_client,
+                &cluster.node,
+                node.vm_id,
+                &["cloud-init", "status", "--long"],
+                Duration::from_secs(30),
+            ) {
+                Ok(v) => result.cloud_init_status = Some(v),
+                Err(e) => result.errors.push(e),
+            }
+            match agent::read_file_tail(
+                &proxmox_client,
+                &cluster.node,
+                node.vm_id,
+                agent::CLOUD_INIT_LOG,
+                100,
+            ) {
+                Ok(v) => result.cloud_init_log = Some(v),
+                Err(e) => result.errors.push(format!("Cannot read cloud-init log: {}", e)),
+            }
+            match proxmox_client.agent_network_interfaces(&cluster.node, node.vm_id) {
+                Ok(v) => {
+                    result.ip_addresses = v
+                        .iter()
+                        .flat_map(|i| {
+                            i.ip_addresses.iter().map(move |a| match a.prefix {
+                                Some(prefix) => format!("{} {}/{}", i.name, a.ip_address, prefix),
+                                None => format!("{} {}", i.name, a.ip_address),
+                            })
+                        })
+                        .collect()
+                }
+                Err(e) => result.errors.push(format!("Cannot read network interfaces: {}", e)),
+            }
+        }
+
+        let mut ssh_client = ssh_client::Client::new();
+        match ssh_client.connect(
+            &node.ip_address,
+            &cluster.node_username,
+            &cluster.ssh_key.private_key,
+            &cluster.ssh_key.public_key,
+        ) {
+            Ok(_) => result.ssh_reachable = true,
+            Err(e) => result.errors.push(format!("Cannot connect over SSH: {}", e)),
+        }
+        Ok(result)
+    }
+
     pub fn apps_status(&self, cluster_name: &str) -> crate::Result<Vec<AppStatus>> {
         info!("Get apps status");
         let cluster = self
@@ -382,7 +1682,14 @@ impl Operator {
             return Ok(result);
         }
         let installed_releases =
-            ssh_client.execute_to(&helm_client::new(HELM_CMD).sudo().list().all().json().build());
+            ssh_client.execute_to(
+                &helm_client::new(distribution::of(&cluster).helm())
+                    .sudo()
+                    .list()
+                    .all()
+                    .json()
+                    .build(),
+            );
 
         let installed_releases: Vec<InstalledRelease> = match installed_releases {
             Ok(v) => v,
@@ -407,6 +1714,104 @@ impl Operator {
             .collect())
     }
 
+    /// MicroK8s channels available on the first master node, for versions missing in the catalog.
+    pub fn discover_kube_versions(&self, cluster_name: &str) -> crate::Result<Vec<KubeVersion>> {
+        info!("Discover Kubernetes versions");
+        let cluster = self
+            .get_cluster(cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        distribution::require_microk8s(&cluster, "Version discovery").map_err(Error::Generic)?;
+        let master_node = cluster
+            .nodes
+            .iter()
+            .find(|i| i.node_type == ClusterNodeType::Master)
+            .ok_or(Error::ResourceNotFound)?;
+
+        let mut ssh_client = ssh_client::Client::new();
+        ssh_client.connect(
+            &master_node.ip_address,
+            &cluster.node_username,
+            &cluster.ssh_key.private_key,
+            &cluster.ssh_key.public_key,
+        )?;
+        let output = ssh_client.execute("snap info microk8s")?;
+        Ok(supported::parse_snap_channels(
+            &output,
+            &supported::kube_versions(&cluster.distribution),
+        ))
+    }
+
+    /// Available MicroK8s addons with their state, read from the first master node.
+    pub fn addons_status(&self, cluster_name: &str) -> crate::Result<Vec<AddonStatus>> {
+        info!("Get addons status");
+        let cluster = self
+            .get_cluster(cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        distribution::require_microk8s(&cluster, "Addons").map_err(Error::Generic)?;
+        let master_node = cluster
+            .nodes
+            .iter()
+            .find(|i| i.node_type == ClusterNodeType::Master)
+            .ok_or(Error::ResourceNotFound)?;
+
+        let mut ssh_client = ssh_client::Client::new();
+        ssh_client.connect(
+            &master_node.ip_address,
+            &cluster.node_username,
+            &cluster.ssh_key.private_key,
+            &cluster.ssh_key.public_key,
+        )?;
+        let output = ssh_client.execute(addons::STATUS_CMD)?;
+        Ok(addons::parse_status(&output)?)
+    }
+
+    pub fn enable_addon(&self, cluster_name: String, addon: ClusterAddon) -> crate::Result<()> {
+        info!("Enable addon");
+        addons::validate(&addon).map_err(Error::Generic)?;
+        let mut cluster = self
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        distribution::require_microk8s(&cluster, "Addons").map_err(Error::Generic)?;
+        match cluster.addons.iter_mut().find(|i| i.name == addon.name) {
+            Some(v) => *v = addon.clone(),
+            None => cluster.addons.push(addon.clone()),
+        }
+        self.repository.save_cluster(cluster)?;
+
+        self.tx.send(Event::ChangeAddon {
+            cluster_name,
+            addon,
+            enabled: true,
+        })?;
+        Ok(())
+    }
+
+    pub fn disable_addon(&self, cluster_name: String, name: String) -> crate::Result<()> {
+        info!("Disable addon");
+        if addons::REQUIRED_ADDONS.contains(&name.as_str()) {
+            return Err(Error::Generic(format!(
+                "Addon [{}] is required by Makoon",
+                name
+            )));
+        }
+        let mut cluster = self
+            .get_cluster(&cluster_name)?
+            .ok_or(Error::ResourceNotFound)?;
+        distribution::require_microk8s(&cluster, "Addons").map_err(Error::Generic)?;
+        cluster.addons.retain(|i| i.name != name);
+        self.repository.save_cluster(cluster)?;
+
+        self.tx.send(Event::ChangeAddon {
+            cluster_name,
+            addon: ClusterAddon {
+                name,
+                arguments: None,
+            },
+            enabled: false,
+        })?;
+        Ok(())
+    }
+
     pub fn logs_for_cluster(&self, name: &str) -> crate::Result<Vec<LogEntry>> {
         info!("Get logs for the cluster");
         Ok(self.repository.logs(name)?)
@@ -509,7 +1914,7 @@ impl Operator {
             &cluster.ssh_key.public_key,
         )?;
 
-        crate::dispatcher::install_helm_app(&ssh_client, app)?;
+        crate::dispatcher::install_helm_app(&ssh_client, distribution::of(&cluster).helm(), app)?;
         Ok(())
     }
     pub fn uninstall_helm_app(&self, cluster_name: &str, app_id: &str) -> crate::Result<()> {
@@ -541,7 +1946,7 @@ impl Operator {
         )?;
 
         ssh_client.execute(
-            &helm_client::new(HELM_CMD)
+            &helm_client::new(distribution::of(&cluster).helm())
                 .sudo()
                 .uninstall(&app.release_name)
                 .namespace(&app.namespace)
@@ -618,7 +2023,11 @@ impl Operator {
             &cluster.ssh_key.public_key,
         )?;
 
-        crate::dispatcher::install_cluster_resource(&ssh_client, res)?;
+        crate::dispatcher::install_cluster_resource(
+            &ssh_client,
+            distribution::of(&cluster).kubectl(),
+            res,
+        )?;
         Ok(())
     }
     pub fn uninstall_cluster_workload(&self, cluster_name: &str, res_id: &str) -> crate::Result<()> {
@@ -655,7 +2064,14 @@ impl Operator {
             res.content.as_str(),
         )?;
         ssh_client
-            .execute(format!("sudo microk8s.kubectl delete -f /tmp/{}.yaml", file_name).as_str())?;
+            .execute(
+                format!(
+                    "sudo {} delete -f /tmp/{}.yaml",
+                    distribution::of(&cluster).kubectl(),
+                    file_name
+                )
+                .as_str(),
+            )?;
         ssh_client.execute(format!("sudo rm /tmp/{}.yaml", file_name).as_str())?;
         Ok(())
     }
@@ -673,3 +2089,239 @@ impl Operator {
         Ok(())
     }
 }
+
+/// Sends autoscale event for every cluster with enabled autoscaling. API token access of the
+/// config is used when it's set, otherwise the ticket saved with autoscaling settings of the
+/// cluster, renewed before it expires. Clusters without access are blocked.
+fn request_autoscaling(
+    repository: &Repository,
+    tx: &SyncSender<Event>,
+    config_access: Option<&AccessData>,
+    access: &AutoscalerAccess,
+) -> crate::Result<()> {
+    let clusters = repository
+        .get_clusters()?
+        .into_iter()
+        .filter(|i| i.autoscaling.as_ref().is_some_and(|a| a.enabled))
+        .filter(|i| i.status == ClusterStatus::Sync)
+        .collect::<Vec<Cluster>>();
+
+    for mut cluster in clusters {
+        let cluster_access = match config_access {
+            Some(v) => Ok(v.clone()),
+            None => cluster_access(access, &cluster.cluster_name)?,
+        };
+        let cluster_access = match cluster_access {
+            Ok(v) => v,
+            Err(reason) => {
+                if cluster.autoscaling_state.blocked_reason.as_ref() != Some(&reason) {
+                    warn!("Autoscaling of cluster [{}] is blocked: {}", cluster.cluster_name, reason);
+                    repository.save_log(LogEntry::error(&cluster.cluster_name, &reason))?;
+                    cluster.autoscaling_state.blocked_reason = Some(reason);
+                    repository.save_cluster(cluster)?;
+                }
+                continue;
+            }
+        };
+        match tx.try_send(Event::Autoscale {
+            access: cluster_access,
+            cluster_name: cluster.cluster_name,
+        }) {
+            Ok(_) => {}
+            Err(TrySendError::Full(_)) => {
+                info!("Event queue is full, autoscaling is postponed");
+                return Ok(());
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                return Err(Error::Generic("Event queue is disconnected".to_string()))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Sends node metadata synchronization event for every cluster in sync, so labels and taints
+/// which drifted in Kubernetes are re-applied without user action.
+fn request_node_metadata_sync(repository: &Repository, tx: &SyncSender<Event>) -> crate::Result<()> {
+    for cluster in repository
+        .get_clusters()?
+        .into_iter()
+        .filter(|i| i.status == ClusterStatus::Sync)
+    {
+        match tx.try_send(Event::SyncNodeMetadata {
+            cluster_name: cluster.cluster_name,
+            node_name: None,
+            removed_labels: vec![],
+            removed_taints: vec![],
+            scheduled: true,
+        }) {
+            Ok(_) => {}
+            Err(TrySendError::Full(_)) => {
+                info!("Event queue is full, node metadata synchronization is postponed");
+                return Ok(());
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                return Err(Error::Generic("Event queue is disconnected".to_string()))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Ticket saved with autoscaling settings of the cluster, renewed when it's older than
+/// [TICKET_RENEWAL_INTERVAL] and dropped after [TICKET_MAX_LIFETIME]. The lock isn't held
+/// during renewal. Inner error is the reason why the cluster has no access.
+fn cluster_access(
+    access: &AutoscalerAccess,
+    cluster_name: &str,
+) -> crate::Result<Result<AccessData, String>> {
+    let lock = || access.lock().map_err(|e| Error::Generic(e.to_string()));
+    let ticket = match lock()?.get(cluster_name) {
+        Some(v) => v.clone(),
+        None => {
+            return Ok(Err(
+                "Autoscaler has no Proxmox access, save autoscaling settings again or configure an API token"
+                    .to_string(),
+            ))
+        }
+    };
+    if ticket.saved.elapsed() > TICKET_MAX_LIFETIME {
+        lock()?.remove(cluster_name);
+        return Ok(Err(
+            "Proxmox ticket of autoscaler has expired, save autoscaling settings again or configure an API token"
+                .to_string(),
+        ));
+    }
+    if ticket
+        .renewed
+        .is_some_and(|i| i.elapsed() < TICKET_RENEWAL_INTERVAL)
+    {
+        return Ok(Ok(ticket.access));
+    }
+
+    let renewed = proxmox_client::Client::new().renew_ticket(ticket.access.clone());
+    let mut access = lock()?;
+    // Settings may have been saved again or the session logged out during the renewal
+    let current = access
+        .get_mut(cluster_name)
+        .filter(|i| i.session_ticket == ticket.session_ticket && i.saved == ticket.saved);
+    match (renewed, current) {
+        (Ok(v), Some(current)) => {
+            current.access = v.clone();
+            current.renewed = Some(Instant::now());
+            Ok(Ok(v))
+        }
+        (Ok(_), None) => Ok(Err(
+            "Autoscaler has no Proxmox access, save autoscaling settings again or configure an API token"
+                .to_string(),
+        )),
+        (Err(e), current) => {
+            if current.is_some() {
+                access.remove(cluster_name);
+            }
+            Ok(Err(format!("Renewing Proxmox ticket of autoscaler failed: {}", e)))
+        }
+    }
+}
+
+/// Snapshot, rollback and SSH key rotation change all VMs of the cluster, other operations
+/// have to wait for them.
+fn require_no_exclusive_operation(cluster: &Cluster) -> crate::Result<()> {
+    let operation = match cluster.status {
+        ClusterStatus::Snapshotting => "Snapshot operation",
+        ClusterStatus::RotatingSshKey => "SSH key rotation",
+        _ => return Ok(()),
+    };
+    Err(Error::Generic(format!(
+        "{} of cluster [{}] is in progress",
+        operation, cluster.cluster_name
+    )))
+}
+
+/// Proxmox accepts snapshot names starting with a letter and containing only
+/// letters, digits, `-` and `_`, up to 40 characters.
+fn is_valid_snapshot_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => (),
+        _ => return false,
+    }
+    name.len() <= 40 && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use proxmox_client::model::AccessData;
+
+    use crate::model::{Cluster, ClusterStatus};
+    use crate::operator::{
+        cluster_access, is_valid_snapshot_name, require_no_exclusive_operation, AutoscalerAccess,
+        AutoscalerTicket, TICKET_MAX_LIFETIME,
+    };
+
+    fn ticket(saved: Instant) -> AutoscalerTicket {
+        AutoscalerTicket {
+            access: AccessData::from_api_token(
+                "pve".to_string(),
+                8006,
+                "/api2/json".to_string(),
+                "root@pam!makoon=secret".to_string(),
+            ),
+            session_ticket: "PVE:root@pam:1".to_string(),
+            saved,
+            renewed: Some(Instant::now()),
+        }
+    }
+
+    #[test]
+    fn snapshot_name_must_start_with_letter() {
+        assert!(is_valid_snapshot_name("before-upgrade_1"));
+        assert!(!is_valid_snapshot_name("1-before-upgrade"));
+        assert!(!is_valid_snapshot_name(""));
+    }
+
+    #[test]
+    fn snapshot_name_rejects_invalid_characters_and_length() {
+        assert!(!is_valid_snapshot_name("before upgrade"));
+        assert!(!is_valid_snapshot_name("before.upgrade"));
+        assert!(!is_valid_snapshot_name(&"a".repeat(41)));
+    }
+
+    #[test]
+    fn exclusive_operation_blocks_cluster() {
+        let mut cluster = Cluster::default();
+        assert!(require_no_exclusive_operation(&cluster).is_ok());
+        cluster.status = ClusterStatus::RotatingSshKey;
+        assert!(require_no_exclusive_operation(&cluster).is_err());
+        cluster.status = ClusterStatus::Snapshotting;
+        assert!(require_no_exclusive_operation(&cluster).is_err());
+    }
+
+    #[test]
+    fn autoscaler_access_is_kept_per_cluster() {
+        let access: AutoscalerAccess = Arc::new(Mutex::new(HashMap::new()));
+        access
+            .lock()
+            .unwrap()
+            .insert("c1".to_string(), ticket(Instant::now()));
+
+        assert!(cluster_access(&access, "c1").unwrap().is_ok());
+        assert!(cluster_access(&access, "c2").unwrap().is_err());
+    }
+
+    #[test]
+    fn autoscaler_access_expires() {
+        let access: AutoscalerAccess = Arc::new(Mutex::new(HashMap::new()));
+        let saved = Instant::now()
+            .checked_sub(TICKET_MAX_LIFETIME + Duration::from_secs(1))
+            .unwrap();
+        access.lock().unwrap().insert("c1".to_string(), ticket(saved));
+
+        assert!(cluster_access(&access, "c1").unwrap().is_err());
+        assert!(access.lock().unwrap().is_empty());
+    }
+}
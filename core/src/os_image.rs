@@ -0,0 +1,156 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::model::{ChecksumAlgorithm, OsFamily, OsImage};
+
+const CHECKSUM_TIMEOUT: u64 = 30;
+
+pub(crate) fn find<'a>(catalog: &'a [OsImage], url: &str) -> Option<&'a OsImage> {
+    catalog.iter().find(|i| i.url == url)
+}
+
+/// Name of the image file on Proxmox storage, `image` is the catalog entry of the URL.
+pub(crate) fn file_name(url: &str, image: Option<&OsImage>) -> Result<String, String> {
+    let file_name = match image.and_then(|i| i.file_name.clone()) {
+        Some(v) => v,
+        None => url_file_name(url)?,
+    };
+    if !file_name.ends_with(".img") && !file_name.ends_with(".iso") {
+        return Err(format!(
+            "OS image file [{}] has to have .img or .iso extension",
+            file_name
+        ));
+    }
+    Ok(file_name)
+}
+
+fn url_file_name(url: &str) -> Result<String, String> {
+    Path::new(url)
+        .file_name()
+        .map(|i| i.to_string_lossy().to_string())
+        .ok_or("Cannot extract file name for path".to_string())
+}
+
+/// Checksum of the file from `SHA256SUMS` like list, lines are `<checksum> [*]<file>`.
+pub(crate) fn parse_checksum(sums: &str, file_name: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let mut columns = line.split_whitespace();
+        let checksum = columns.next()?;
+        let name = columns.next()?.trim_start_matches('*');
+        (name == file_name).then(|| checksum.to_lowercase())
+    })
+}
+
+/// Checksum the download is verified with, read from the checksum list when the catalog
+/// doesn't pin it. `None` when the image has no checksum.
+pub(crate) fn checksum(image: &OsImage) -> Result<Option<(String, ChecksumAlgorithm)>, String> {
+    let algorithm = match image.checksum_algorithm.clone() {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    if let Some(checksum) = image.checksum.as_ref().filter(|i| !i.trim().is_empty()) {
+        return Ok(Some((checksum.trim().to_lowercase(), algorithm)));
+    }
+    let checksum_url = match image.checksum_url.as_ref() {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let sums = reqwest::blocking::Client::new()
+        .get(checksum_url)
+        .timeout(Duration::from_secs(CHECKSUM_TIMEOUT))
+        .send()
+        .and_then(|i| i.error_for_status())
+        .and_then(|i| i.text())
+        .map_err(|e| format!("Cannot download checksums [{}]: {}", checksum_url, e))?;
+    let file_name = url_file_name(&image.url)?;
+    parse_checksum(&sums, &file_name)
+        .map(|i| Some((i, algorithm)))
+        .ok_or(format!(
+            "Checksum of [{}] is missing in [{}]",
+            file_name, checksum_url
+        ))
+}
+
+/// Downloaded image is refreshed when it's older than the max age of the catalog entry.
+/// `created` and `now` in seconds since the UNIX Epoch.
+pub(crate) fn is_expired(image: Option<&OsImage>, created: Option<u64>, now: u64) -> bool {
+    match (image.and_then(|i| i.max_age_days), created) {
+        (Some(days), Some(created)) => now.saturating_sub(created) > u64::from(days) * 86400,
+        _ => false,
+    }
+}
+
+/// Prepares a fresh VM for Kubernetes, Debian cloud images don't ship snapd and iSCSI.
+pub(crate) fn setup_commands(family: &OsFamily) -> Vec<String> {
+    match family {
+        OsFamily::Ubuntu => vec![
+            "sudo systemctl enable iscsid".to_string(),
+            "sudo DEBIAN_FRONTEND=noninteractive apt-get install -y qemu-guest-agent \
+            && sudo systemctl start qemu-guest-agent"
+                .to_string(),
+        ],
+        OsFamily::Debian => vec![
+            "sudo apt-get update".to_string(),
+            "sudo DEBIAN_FRONTEND=noninteractive apt-get install -y qemu-guest-agent open-iscsi snapd \
+            && sudo systemctl start qemu-guest-agent"
+                .to_string(),
+            "sudo systemctl enable iscsid".to_string(),
+            "sudo snap install core".to_string(),
+            "echo 'Defaults secure_path=\"/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin:/snap/bin\"' \
+            | sudo tee /etc/sudoers.d/makoon-snap"
+                .to_string(),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::{ChecksumAlgorithm, OsFamily, OsImage};
+    use crate::os_image::{file_name, is_expired, parse_checksum};
+
+    fn image() -> OsImage {
+        OsImage {
+            name: "Debian 12".to_string(),
+            url: "https://cloud.debian.org/images/cloud/bookworm/latest/debian-12-genericcloud-amd64.qcow2".to_string(),
+            file_name: Some("debian-12-genericcloud-amd64.img".to_string()),
+            os_family: OsFamily::Debian,
+            checksum: None,
+            checksum_algorithm: Some(ChecksumAlgorithm::Sha512),
+            checksum_url: None,
+            max_age_days: Some(30),
+        }
+    }
+
+    #[test]
+    fn checksum_is_found_by_file_name() {
+        let sums = "1a2B  debian-12-generic-amd64.qcow2
+3c4d *jammy-server-cloudimg-amd64.img
+5e6f  debian-12-genericcloud-amd64.qcow2
+";
+        assert_eq!(
+            Some("3c4d".to_string()),
+            parse_checksum(sums, "jammy-server-cloudimg-amd64.img")
+        );
+        assert_eq!(
+            Some("5e6f".to_string()),
+            parse_checksum(sums, "debian-12-genericcloud-amd64.qcow2")
+        );
+        assert_eq!(None, parse_checksum(sums, "noble-server-cloudimg-amd64.img"));
+    }
+
+    #[test]
+    fn file_name_and_age_follow_catalog() {
+        let image = image();
+        assert_eq!(
+            Ok("debian-12-genericcloud-amd64.img".to_string()),
+            file_name(&image.url, Some(&image))
+        );
+        assert!(file_name(&image.url, None).is_err());
+
+        let day = 86400;
+        assert!(!is_expired(Some(&image), Some(day), 30 * day));
+        assert!(is_expired(Some(&image), Some(day), 32 * day));
+        assert!(!is_expired(None, Some(day), 365 * day));
+        assert!(!is_expired(Some(&image), None, 365 * day));
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+
+use proxmox_client::model::{Node, Storage, VirtualMachine, VmStatus};
+use proxmox_client::ClientOperations;
+
+use crate::model::{ClusterNode, PreflightCheck, PreflightReport, PreflightSeverity};
+
+const MIB: u64 = 1024 * 1024;
+const GIB: u64 = 1024 * MIB;
+// Thin provisioned storage runs out of space as disks fill up
+const STORAGE_WARNING_FRACTION: f64 = 0.9;
+
+/// VM to be created or resized. `memory` in MiB, `disk_size` in GiB, 0 when the disk
+/// isn't allocated by the operation.
+pub(crate) struct RequestedVm {
+    pub(crate) name: String,
+    pub(crate) cores: u16,
+    pub(crate) memory: u32,
+    pub(crate) storage_pool: String,
+    pub(crate) disk_size: u32,
+}
+
+impl RequestedVm {
+    pub(crate) fn new(cluster_name: &str, node: &ClusterNode, disk_size: u32) -> Self {
+        RequestedVm {
+            name: node.host_name(cluster_name),
+            cores: node.cores,
+            memory: node.memory,
+            storage_pool: node.storage_pool.clone(),
+            disk_size: node.disk_size.unwrap_or(disk_size),
+        }
+    }
+}
+
+fn add(report: &mut PreflightReport, resource: &str, severity: PreflightSeverity, message: String) {
+    report.status = report.status.clone().max(severity.clone());
+    report.checks.push(PreflightCheck {
+        resource: resource.to_string(),
+        severity,
+        message,
+    });
+}
+
+/// Compares requested VMs with the capacity of the Proxmox node. `vms` are the VMs of the
+/// node without the resized ones, only running VMs count as allocated.
+pub(crate) fn check(
+    node: &Node,
+    vms: &[VirtualMachine],
+    storage: &[Storage],
+    requested: &[RequestedVm],
+) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    let running = vms
+        .iter()
+        .filter(|i| i.status == VmStatus::Running)
+        .collect::<Vec<&VirtualMachine>>();
+
+    if let Some(max_cpu) = node.max_cpu {
+        for vm in requested.iter().filter(|i| i.cores > max_cpu) {
+            add(
+                &mut report,
+                "cpu",
+                PreflightSeverity::Error,
+                format!(
+                    "[{}] needs {} cores, Proxmox node [{}] has {} CPUs",
+                    vm.name, vm.cores, node.node, max_cpu
+                ),
+            );
+        }
+        let allocated = running.iter().filter_map(|i| i.cpus).map(u32::from).sum::<u32>()
+            + requested.iter().map(|i| u32::from(i.cores)).sum::<u32>();
+        match allocated > u32::from(max_cpu) {
+            true => add(
+                &mut report,
+                "cpu",
+                PreflightSeverity::Warning,
+                format!(
+                    "CPUs of Proxmox node [{}] will be overcommitted, {} cores allocated on {} CPUs",
+                    node.node, allocated, max_cpu
+                ),
+            ),
+            false => add(
+                &mut report,
+                "cpu",
+                PreflightSeverity::Ok,
+                format!("{} of {} CPUs allocated", allocated, max_cpu),
+            ),
+        }
+    }
+
+    if let Some(max_mem) = node.max_mem {
+        let memory = requested.iter().map(|i| u64::from(i.memory) * MIB).sum::<u64>();
+        let free = max_mem.saturating_sub(node.mem.unwrap_or(0));
+        let allocated = running.iter().filter_map(|i| i.max_mem).sum::<u64>() + memory;
+        if memory > free {
+            add(
+                &mut report,
+                "memory",
+                PreflightSeverity::Error,
+                format!(
+                    "Proxmox node [{}] has {} MiB of free memory, {} MiB requested",
+                    node.node,
+                    free / MIB,
+                    memory / MIB
+                ),
+            );
+        } else if allocated > max_mem {
+            add(
+                &mut report,
+                "memory",
+                PreflightSeverity::Warning,
+                format!(
+                    "Memory of Proxmox node [{}] will be overcommitted, {} MiB allocated on {} MiB",
+                    node.node,
+                    allocated / MIB,
+                    max_mem / MIB
+                ),
+            );
+        } else {
+            add(
+                &mut report,
+                "memory",
+                PreflightSeverity::Ok,
+                format!("{} of {} MiB allocated", allocated / MIB, max_mem / MIB),
+            );
+        }
+    }
+
+    let mut disks: BTreeMap<&str, u64> = BTreeMap::new();
+    for vm in requested.iter().filter(|i| i.disk_size > 0) {
+        *disks.entry(vm.storage_pool.as_str()).or_default() += u64::from(vm.disk_size) * GIB;
+    }
+    for (storage_pool, size) in disks {
+        let resource = format!("storage {}", storage_pool);
+        let storage = storage.iter().find(|i| i.storage == storage_pool);
+        let avail = match storage.and_then(|i| i.avail) {
+            Some(v) => v,
+            None => {
+                add(
+                    &mut report,
+                    &resource,
+                    PreflightSeverity::Error,
+                    format!(
+                        "Storage [{}] is not available on Proxmox node [{}]",
+                        storage_pool, node.node
+                    ),
+                );
+                continue;
+            }
+        };
+        let total = storage.and_then(|i| i.total).unwrap_or(0);
+        let used = storage.and_then(|i| i.used).unwrap_or(0);
+        if size > avail {
+            add(
+                &mut report,
+                &resource,
+                PreflightSeverity::Error,
+                format!(
+                    "Storage [{}] has {} GiB of free space, {} GiB requested",
+                    storage_pool,
+                    avail / GIB,
+                    size / GIB
+                ),
+            );
+        } else if (used + size) as f64 > total as f64 * STORAGE_WARNING_FRACTION {
+            add(
+                &mut report,
+                &resource,
+                PreflightSeverity::Warning,
+                format!(
+                    "Storage [{}] will be over {}% full",
+                    storage_pool,
+                    (STORAGE_WARNING_FRACTION * 100.0) as u8
+                ),
+            );
+        } else {
+            add(
+                &mut report,
+                &resource,
+                PreflightSeverity::Ok,
+                format!(
+                    "{} of {} GiB free space requested",
+                    size / GIB,
+                    avail / GIB
+                ),
+            );
+        }
+    }
+    report
+}
+
+/// Reads the capacity of the Proxmox node and checks the requested VMs, `replaced` VMs are
+/// resized and don't count as allocated.
+pub(crate) fn run(
+    proxmox_client: &ClientOperations,
+    node: &str,
+    replaced: &[u32],
+    requested: &[RequestedVm],
+) -> Result<PreflightReport, String> {
+    let proxmox_node = proxmox_client
+        .nodes()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|i| i.node == node)
+        .ok_or(format!("Proxmox node [{}] not found", node))?;
+    let vms = proxmox_client
+        .virtual_machines(node, None)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|i| !replaced.contains(&i.vm_id))
+        .collect::<Vec<VirtualMachine>>();
+    let storage = proxmox_client
+        .storage(node, None)
+        .map_err(|e| e.to_string())?;
+    Ok(check(&proxmox_node, &vms, &storage, requested))
+}
+
+/// Blocking errors of the report as an error, the operation is not started then.
+pub(crate) fn require_passed(report: &PreflightReport) -> Result<(), String> {
+    if report.status != PreflightSeverity::Error {
+        return Ok(());
+    }
+    Err(format!(
+        "Pre-flight check failed: {}",
+        report
+            .checks
+            .iter()
+            .filter(|i| i.severity == PreflightSeverity::Error)
+            .map(|i| i.message.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use proxmox_client::model::{Node, Storage, VirtualMachine};
+    use serde_json::json;
+
+    use crate::model::PreflightSeverity;
+    use crate::preflight::{check, RequestedVm};
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn vm(name: &str, cores: u16, memory: u32, disk_size: u32) -> RequestedVm {
+        RequestedVm {
+            name: name.to_string(),
+            cores,
+            memory,
+            storage_pool: "local-lvm".to_string(),
+            disk_size,
+        }
+    }
+
+    #[test]
+    fn overcommit_warns_and_missing_capacity_blocks() {
+        let node: Node = serde_json::from_value(json!({
+            "node": "pve",
+            "status": "online",
+            "maxcpu": 8,
+            "maxmem": 16 * GIB,
+            "mem": 4 * GIB
+        }))
+        .unwrap();
+        let running: VirtualMachine = serde_json::from_value(json!({
+            "status": "running",
+            "vmid": 100,
+            "cpus": 6,
+            "maxmem": 8 * GIB
+        }))
+        .unwrap();
+        let storage = || -> Storage {
+            serde_json::from_value(json!({
+                "storage": "local-lvm",
+                "content": "images",
+                "type": "lvmthin",
+                "avail": 100 * GIB,
+                "total": 200 * GIB,
+                "used": 100 * GIB
+            }))
+            .unwrap()
+        };
+
+        let report = check(&node, &[running], &[storage()], &[vm("c1-master-1", 4, 4096, 20)]);
+        assert_eq!(PreflightSeverity::Warning, report.status);
+        assert_eq!(PreflightSeverity::Warning, report.checks[0].severity);
+        assert_eq!(PreflightSeverity::Ok, report.checks[1].severity);
+        assert_eq!(PreflightSeverity::Ok, report.checks[2].severity);
+
+        let report = check(&node, &[], &[storage()], &[vm("c1-master-1", 16, 16384, 120)]);
+        assert_eq!(PreflightSeverity::Error, report.status);
+        assert_eq!(
+            3,
+            report
+                .checks
+                .iter()
+                .filter(|i| i.severity == PreflightSeverity::Error)
+                .count()
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use proxmox_client::model::{AgentIpAddressType, AgentNetworkInterface, VmConfigDetails};
+
+use crate::model::{
+    Cluster, ClusterNode, ClusterNodeType, ClusterSecret, ClusterStatus, Distribution, Network,
+};
+
+const HEADER: &str = "Managed by Makoon, the lines below are used to recover the cluster.";
+const PREFIX: &str = "makoon-";
+
+/// Cluster metadata Makoon keeps in the description of every node VM.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct VmMetadata {
+    pub(crate) cluster_id: String,
+    pub(crate) cluster_name: String,
+    pub(crate) node_name: String,
+    pub(crate) node_type: ClusterNodeType,
+    pub(crate) pool: Option<String>,
+    pub(crate) distribution: Distribution,
+    pub(crate) kube_version: Option<String>,
+}
+
+/// VM description with [VmMetadata] of the node.
+pub(crate) fn description(cluster: &Cluster, node: &ClusterNode) -> String {
+    let mut lines = vec![
+        HEADER.to_string(),
+        String::new(),
+        format!("{}cluster-id: {}", PREFIX, cluster_id(cluster)),
+        format!("{}cluster: {}", PREFIX, cluster.cluster_name),
+        format!("{}node: {}", PREFIX, node.name),
+        format!("{}role: {}", PREFIX, node.node_type),
+        format!("{}distribution: {}", PREFIX, distribution_value(&cluster.distribution)),
+    ];
+    if let Some(pool) = node.pool.as_ref() {
+        lines.push(format!("{}node-pool: {}", PREFIX, pool));
+    }
+    if let Some(version) = cluster.kube_version.as_ref().filter(|i| !i.is_empty()) {
+        lines.push(format!("{}kube-version: {}", PREFIX, version));
+    }
+    // Proxmox shows the description as Markdown, trailing spaces keep the line breaks
+    lines.join("  \n")
+}
+
+/// Clusters created before cluster ids were introduced are identified by name.
+pub(crate) fn cluster_id(cluster: &Cluster) -> &str {
+    match cluster.cluster_id.is_empty() {
+        true => &cluster.cluster_name,
+        false => &cluster.cluster_id,
+    }
+}
+
+fn distribution_value(distribution: &Distribution) -> &'static str {
+    match distribution {
+        Distribution::MicroK8s => "microk8s",
+        Distribution::K3s => "k3s",
+    }
+}
+
+pub(crate) fn parse_description(description: &str) -> Option<VmMetadata> {
+    let values = description
+        .lines()
+        .filter_map(|i| i.trim().strip_prefix(PREFIX))
+        .filter_map(|i| i.split_once(':'))
+        .map(|(key, value)| (key.trim(), value.trim().to_string()))
+        .collect::<HashMap<&str, String>>();
+    let value = |key: &str| values.get(key).cloned().filter(|i| !i.is_empty());
+    Some(VmMetadata {
+        cluster_id: value("cluster-id")?,
+        cluster_name: value("cluster")?,
+        node_name: value("node")?,
+        node_type: match value("role")?.as_str() {
+            "master" => ClusterNodeType::Master,
+            "worker" => ClusterNodeType::Worker,
+            _ => return None,
+        },
+        pool: value("node-pool"),
+        distribution: match value("distribution").as_deref() {
+            Some("k3s") => Distribution::K3s,
+            _ => Distribution::MicroK8s,
+        },
+        kube_version: value("kube-version"),
+    })
+}
+
+/// Proxmox property string `key=value,key=value`, a value without key is stored under
+/// its position, e.g. storage of a disk.
+fn properties(value: &str) -> HashMap<String, String> {
+    value
+        .split(',')
+        .enumerate()
+        .map(|(position, i)| match i.split_once('=') {
+            Some((key, value)) => (key.to_string(), value.to_string()),
+            None => (position.to_string(), i.to_string()),
+        })
+        .collect()
+}
+
+/// Disk size in GiB from `size` property like `20G`.
+fn disk_size(value: &str) -> Option<u32> {
+    let (number, unit) = value.split_at(value.len().checked_sub(1)?);
+    let number = f64::from_str(number).ok()?;
+    let size = match unit {
+        "T" => number * 1024.0,
+        "G" => number,
+        "M" => number / 1024.0,
+        _ => return None,
+    };
+    Some(size.ceil() as u32)
+}
+
+/// Node of the VM named after the VM, address is empty for DHCP node and `ip_address`
+/// of `ipconfig0` otherwise.
+pub(crate) fn vm_node(vm_id: u32, config: &VmConfigDetails) -> ClusterNode {
+    let ipconfig = properties(config.option("ipconfig0").unwrap_or_default());
+    let disk = properties(config.option("scsi0").unwrap_or_default());
+    let ip_address = ipconfig
+        .get("ip")
+        .or(ipconfig.get("ip6"))
+        .filter(|i| *i != "dhcp" && *i != "auto")
+        .and_then(|i| i.split('/').next())
+        .unwrap_or_default()
+        .to_string();
+    ClusterNode {
+        vm_id,
+        name: config.name.clone().unwrap_or(vm_id.to_string()),
+        cores: config.cores.and_then(|i| u16::try_from(i).ok()).unwrap_or(1),
+        memory: config.memory.and_then(|i| u32::try_from(i).ok()).unwrap_or_default(),
+        ip_address,
+        storage_pool: disk
+            .get("0")
+            .and_then(|i| i.split(':').next())
+            .unwrap_or_default()
+            .to_string(),
+        disk_size: disk.get("size").and_then(|i| disk_size(i)),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn node(vm_id: u32, metadata: &VmMetadata, config: &VmConfigDetails) -> ClusterNode {
+    ClusterNode {
+        name: metadata.node_name.clone(),
+        node_type: metadata.node_type.clone(),
+        pool: metadata.pool.clone(),
+        ..vm_node(vm_id, config)
+    }
+}
+
+/// Address of a DHCP node reported by the guest agent, loopback is skipped.
+pub(crate) fn guest_ip_address(interfaces: &[AgentNetworkInterface]) -> Option<String> {
+    interfaces
+        .iter()
+        .filter(|i| i.name != "lo")
+        .flat_map(|i| i.ip_addresses.iter())
+        .find(|i| i.ip_address_type == AgentIpAddressType::Ipv4)
+        .map(|i| i.ip_address.clone())
+}
+
+/// Primary network of the cluster from the configuration of one of its VMs.
+pub(crate) fn network(config: &VmConfigDetails) -> Network {
+    let net = properties(config.option("net0").unwrap_or_default());
+    let ipconfig = properties(config.option("ipconfig0").unwrap_or_default());
+    let mut nameservers = config
+        .option("nameserver")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|i| i.to_string());
+    let address = ipconfig.get("ip").or(ipconfig.get("ip6"));
+    Network {
+        gateway: ipconfig
+            .get("gw")
+            .or(ipconfig.get("gw6"))
+            .cloned()
+            .unwrap_or_default(),
+        subnet_mask: address
+            .and_then(|i| i.split_once('/'))
+            .and_then(|(_, mask)| mask.parse::<u8>().ok())
+            .unwrap_or_default(),
+        dns: nameservers.next().unwrap_or_default(),
+        dns_servers: nameservers.collect(),
+        bridge: net.get("bridge").cloned().unwrap_or_default(),
+        dhcp: address.map(|i| i.as_str()) == Some("dhcp"),
+        vlan_tag: net.get("tag").and_then(|i| i.parse().ok()),
+        mtu: net.get("mtu").and_then(|i| i.parse().ok()),
+        firewall: net.get("firewall").map(|i| i.as_str()) == Some("1"),
+        ..Default::default()
+    }
+}
+
+/// Cluster record of existing VMs, the largest disk becomes the disk size of the cluster.
+pub(crate) fn cluster_of_vms(
+    proxmox_node: &str,
+    cluster_name: &str,
+    config: &VmConfigDetails,
+    mut nodes: Vec<ClusterNode>,
+) -> Cluster {
+    nodes.sort_by_key(|i| i.vm_id);
+    let disk_size = nodes
+        .iter()
+        .filter_map(|i| i.disk_size)
+        .max()
+        .unwrap_or_default();
+    for node in nodes.iter_mut() {
+        node.disk_size = node.disk_size.filter(|i| *i != disk_size);
+    }
+    Cluster {
+        node: proxmox_node.to_string(),
+        cluster_name: cluster_name.to_string(),
+        node_username: config.ci_user.clone().unwrap_or_default(),
+        disk_size,
+        network: network(config),
+        nodes,
+        ..Default::default()
+    }
+}
+
+/// Cluster record of recovered nodes, secrets have to be entered again.
+pub(crate) fn cluster(
+    proxmox_node: &str,
+    metadata: &VmMetadata,
+    config: &VmConfigDetails,
+    nodes: Vec<ClusterNode>,
+) -> Cluster {
+    Cluster {
+        cluster_id: metadata.cluster_id.clone(),
+        kube_version: metadata.kube_version.clone(),
+        distribution: metadata.distribution.clone(),
+        status: ClusterStatus::OutOfSync,
+        missing_secrets: vec![ClusterSecret::SshKey, ClusterSecret::NodePassword],
+        ..cluster_of_vms(proxmox_node, &metadata.cluster_name, config, nodes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use proxmox_client::model::VmConfigDetails;
+
+    use crate::model::{Cluster, ClusterNode, ClusterNodeType, Distribution};
+    use crate::recovery::{description, node, parse_description};
+
+    #[test]
+    fn metadata_is_recovered_from_description() {
+        let cluster = Cluster {
+            cluster_id: "4f1c".to_string(),
+            cluster_name: "prod".to_string(),
+            kube_version: Some("v1.30".to_string()),
+            distribution: Distribution::K3s,
+            ..Default::default()
+        };
+        let node = ClusterNode {
+            name: "db-1".to_string(),
+            node_type: ClusterNodeType::Worker,
+            pool: Some("db".to_string()),
+            ..Default::default()
+        };
+        let description = format!("Edited by admin\n\n{}", description(&cluster, &node));
+        let metadata = parse_description(&description).unwrap();
+        assert_eq!("4f1c", metadata.cluster_id);
+        assert_eq!("prod", metadata.cluster_name);
+        assert_eq!("db-1", metadata.node_name);
+        assert_eq!(ClusterNodeType::Worker, metadata.node_type);
+        assert_eq!(Some("db".to_string()), metadata.pool);
+        assert_eq!(Distribution::K3s, metadata.distribution);
+        assert_eq!(Some("v1.30".to_string()), metadata.kube_version);
+
+        assert_eq!(None, parse_description("Web server"));
+    }
+
+    #[test]
+    fn node_is_recovered_from_vm_config() {
+        let metadata =
+            parse_description("makoon-cluster-id: 1\nmakoon-cluster: c1\nmakoon-node: master-1\nmakoon-role: master")
+                .unwrap();
+        let config = VmConfigDetails {
+            cores: Some(2),
+            memory: Some(4096),
+            options: HashMap::from([
+                ("ipconfig0".to_string(), "ip=10.0.0.11/24,gw=10.0.0.1".into()),
+                ("scsi0".to_string(), "local-lvm:vm-101-disk-0,size=20G".into()),
+            ]),
+            ..Default::default()
+        };
+        let node = node(101, &metadata, &config);
+        assert_eq!("10.0.0.11", node.ip_address);
+        assert_eq!("local-lvm", node.storage_pool);
+        assert_eq!(Some(20), node.disk_size);
+        assert_eq!(4096, node.memory);
+    }
+}
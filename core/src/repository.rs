@@ -1,5 +1,5 @@
 use std::fmt::{Debug, Display, Formatter};
-use crate::model::{Cluster, LogEntry};
+use crate::model::{Cluster, IpAllocation, IpPool, LogEntry};
 use crate::repository_json::JsonRepository;
 
 
@@ -64,4 +64,28 @@ impl Repository {
         self.inner.delete_logs(cluster_name)?;
         Ok(())
     }
+
+    pub fn ip_pools(&self) -> Result<Vec<IpPool>> {
+        self.inner.ip_pools()
+    }
+
+    pub fn save_ip_pool(&self, pool: IpPool) -> Result<()> {
+        self.inner.save_ip_pool(pool)
+    }
+
+    pub fn delete_ip_pool(&self, bridge: &str) -> Result<()> {
+        self.inner.delete_ip_pool(bridge)
+    }
+
+    pub fn ip_allocations(&self) -> Result<Vec<IpAllocation>> {
+        self.inner.ip_allocations()
+    }
+
+    pub fn save_ip_allocations(&self, allocations: Vec<IpAllocation>) -> Result<()> {
+        self.inner.save_ip_allocations(allocations)
+    }
+
+    pub fn release_ip_allocation(&self, cluster_name: &str, node_name: &str) -> Result<()> {
+        self.inner.release_ip_allocation(cluster_name, node_name)
+    }
 }
@@ -4,7 +4,7 @@ use std::sync::{Arc, Mutex};
 use log::{error, warn};
 
 use serde::{Deserialize, Serialize};
-use crate::model::{Cluster, LogEntry};
+use crate::model::{Cluster, IpAllocation, IpPool, LogEntry};
 use crate::repository::Error;
 
 
@@ -18,6 +18,10 @@ pub struct JsonRepository {
 pub(crate) struct DbData {
     pub(crate) clusters: Vec<Cluster>,
     pub(crate) action_log: Vec<LogEntry>,
+    #[serde(default)]
+    pub(crate) ip_pools: Vec<IpPool>,
+    #[serde(default)]
+    pub(crate) ip_allocations: Vec<IpAllocation>,
 }
 
 impl JsonRepository {
@@ -41,6 +45,8 @@ impl JsonRepository {
                     repo.save(DbData {
                         clusters: vec![],
                         action_log: vec![],
+                        ip_pools: vec![],
+                        ip_allocations: vec![],
                     })
                     .unwrap_or_else(|_| {
                         panic!("cannot save database to [{}], error: [{:?}]", path, e)
@@ -94,6 +100,7 @@ impl JsonRepository {
 
         data.clusters.retain(|e| e.cluster_name != name);
         data.action_log.retain(|e| e.cluster_name != name);
+        data.ip_allocations.retain(|e| e.cluster_name != name);
 
         self.save(data)
     }
@@ -147,4 +154,61 @@ impl JsonRepository {
         self.save(data)?;
         Ok(())
     }
+
+    pub fn ip_pools(&self) -> crate::repository::Result<Vec<IpPool>> {
+        self.load().map(|v| v.ip_pools)
+    }
+
+    pub fn save_ip_pool(&self, pool: IpPool) -> crate::repository::Result<()> {
+        let mut data = self.load()?;
+        data.ip_pools.retain(|i| i.bridge != pool.bridge);
+        data.ip_pools.push(pool);
+        self.save(data)
+    }
+
+    pub fn delete_ip_pool(&self, bridge: &str) -> crate::repository::Result<()> {
+        let mut data = self.load()?;
+        data.ip_pools.retain(|i| i.bridge != bridge);
+        self.save(data)
+    }
+
+    pub fn ip_allocations(&self) -> crate::repository::Result<Vec<IpAllocation>> {
+        self.load().map(|v| v.ip_allocations)
+    }
+
+    pub fn save_ip_allocations(
+        &self,
+        allocations: Vec<IpAllocation>,
+    ) -> crate::repository::Result<()> {
+        let mut data = self.load()?;
+        for allocation in allocations {
+            let taken = data.ip_allocations.iter().any(|i| {
+                i.ip_address == allocation.ip_address
+                    && (i.cluster_name != allocation.cluster_name
+                        || i.node_name != allocation.node_name)
+            });
+            if taken {
+                return Err(Error::DB(format!(
+                    "IP address [{}] is already allocated",
+                    allocation.ip_address
+                )));
+            }
+            data.ip_allocations.retain(|i| {
+                i.cluster_name != allocation.cluster_name || i.node_name != allocation.node_name
+            });
+            data.ip_allocations.push(allocation);
+        }
+        self.save(data)
+    }
+
+    pub fn release_ip_allocation(
+        &self,
+        cluster_name: &str,
+        node_name: &str,
+    ) -> crate::repository::Result<()> {
+        let mut data = self.load()?;
+        data.ip_allocations
+            .retain(|i| i.cluster_name != cluster_name || i.node_name != node_name);
+        self.save(data)
+    }
 }
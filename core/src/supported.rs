@@ -1,20 +1,231 @@
-use std::collections::HashMap;
+use std::fs;
 
-pub fn kube_versions() -> Vec<String> {
+use log::error;
+
+use crate::model::{Catalog, ChecksumAlgorithm, Distribution, KubeVersion, OsFamily, OsImage};
+
+/// Path of JSON file with [Catalog], sections missing in the file are taken from the built-in
+/// catalog. The file is read on every use, so it can be changed without restart.
+pub const CATALOG_PATH_ENV: &str = "MAKOON_CATALOG_PATH";
+
+pub fn catalog() -> Catalog {
+    let builtin = Catalog {
+        microk8s_versions: microk8s_versions(),
+        k3s_versions: k3s_versions(),
+        os_images: builtin_os_images(),
+    };
+    let path = match std::env::var(CATALOG_PATH_ENV) {
+        Ok(v) if !v.trim().is_empty() => v,
+        _ => return builtin,
+    };
+    match fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|i| parse_catalog(&i))
+    {
+        Ok(v) => merge(v, builtin),
+        Err(e) => {
+            error!("Cannot read catalog [{}], built-in one is used: {}", path, e);
+            builtin
+        }
+    }
+}
+
+pub(crate) fn parse_catalog(content: &str) -> Result<Catalog, String> {
+    let catalog: Catalog = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    for image in catalog.os_images.iter() {
+        if image.name.trim().is_empty() || image.url.trim().is_empty() {
+            return Err("OS image has to have a name and URL".to_string());
+        }
+        crate::os_image::file_name(&image.url, Some(image))?;
+    }
+    Ok(catalog)
+}
+
+fn merge(catalog: Catalog, builtin: Catalog) -> Catalog {
+    let or_builtin = |list: Vec<KubeVersion>, default: Vec<KubeVersion>| match list.is_empty() {
+        true => default,
+        false => list,
+    };
+    Catalog {
+        microk8s_versions: or_builtin(catalog.microk8s_versions, builtin.microk8s_versions),
+        k3s_versions: or_builtin(catalog.k3s_versions, builtin.k3s_versions),
+        os_images: match catalog.os_images.is_empty() {
+            true => builtin.os_images,
+            false => catalog.os_images,
+        },
+    }
+}
+
+/// Snap channels of MicroK8s, release channels of k3s. The first not deprecated version
+/// is the default one.
+pub fn kube_versions(distribution: &Distribution) -> Vec<KubeVersion> {
+    let catalog = catalog();
+    match distribution {
+        Distribution::MicroK8s => catalog.microk8s_versions,
+        Distribution::K3s => catalog.k3s_versions,
+    }
+}
+
+/// The first not deprecated version, the first version when all of them are deprecated.
+pub(crate) fn default_kube_version(distribution: &Distribution) -> Option<String> {
+    let versions = kube_versions(distribution);
+    versions
+        .iter()
+        .find(|i| !i.deprecated)
+        .or(versions.first())
+        .map(|i| i.version.clone())
+}
+
+/// The first image is the default one.
+pub fn os_images() -> Vec<OsImage> {
+    catalog().os_images
+}
+
+/// Stable channels from `snap info microk8s`, channels without a release are skipped.
+/// Channels deprecated in `catalog` stay deprecated.
+pub(crate) fn parse_snap_channels(output: &str, catalog: &[KubeVersion]) -> Vec<KubeVersion> {
+    output
+        .lines()
+        .skip_while(|i| !i.starts_with("channels:"))
+        .skip(1)
+        .take_while(|i| i.starts_with(' '))
+        .filter_map(|line| {
+            let (channel, release) = line.trim().split_once(':')?;
+            let release = release.split_whitespace().next()?;
+            let released = channel.ends_with("/stable")
+                && !channel.starts_with("latest/")
+                && release.starts_with('v');
+            released.then(|| KubeVersion {
+                version: channel.to_string(),
+                deprecated: catalog
+                    .iter()
+                    .any(|i| i.version == channel && i.deprecated),
+            })
+        })
+        .collect()
+}
+
+fn versions(current: &[&str], deprecated: &[&str]) -> Vec<KubeVersion> {
+    let version = |version: &&str, deprecated: bool| KubeVersion {
+        version: version.to_string(),
+        deprecated,
+    };
+    current
+        .iter()
+        .map(|i| version(i, false))
+        .chain(deprecated.iter().map(|i| version(i, true)))
+        .collect()
+}
+
+fn microk8s_versions() -> Vec<KubeVersion> {
+    versions(
+        &["1.32/stable", "1.31/stable", "1.30/stable", "1.29/stable"],
+        &["1.28/stable", "1.27/stable", "1.26/stable", "1.25/stable", "1.24/stable"],
+    )
+}
+
+fn k3s_versions() -> Vec<KubeVersion> {
+    versions(&["stable", "v1.32", "v1.31", "v1.30"], &["v1.29", "v1.28"])
+}
+
+/// Built-in image catalog, the first image is the default one.
+fn builtin_os_images() -> Vec<OsImage> {
     vec![
-        "1.28/stable".to_string(),
-        "1.27/stable".to_string(),
-        "1.26/stable".to_string(),
-        "1.25/stable".to_string(),
-        "1.24/stable".to_string(),
+        OsImage {
+            name: "Ubuntu Server 22.04 LTS - jammy-server-cloudimg-amd64.img".to_string(),
+            url: "https://cloud-images.ubuntu.com/jammy/current/jammy-server-cloudimg-amd64.img"
+                .to_string(),
+            file_name: None,
+            os_family: OsFamily::Ubuntu,
+            checksum: None,
+            checksum_algorithm: Some(ChecksumAlgorithm::Sha256),
+            checksum_url: Some("https://cloud-images.ubuntu.com/jammy/current/SHA256SUMS".to_string()),
+            max_age_days: Some(30),
+        },
+        OsImage {
+            name: "Ubuntu Server 24.04 LTS - noble-server-cloudimg-amd64.img".to_string(),
+            url: "https://cloud-images.ubuntu.com/noble/current/noble-server-cloudimg-amd64.img"
+                .to_string(),
+            file_name: None,
+            os_family: OsFamily::Ubuntu,
+            checksum: None,
+            checksum_algorithm: Some(ChecksumAlgorithm::Sha256),
+            checksum_url: Some("https://cloud-images.ubuntu.com/noble/current/SHA256SUMS".to_string()),
+            max_age_days: Some(30),
+        },
+        OsImage {
+            name: "Debian 12 - debian-12-genericcloud-amd64.qcow2".to_string(),
+            url: "https://cloud.debian.org/images/cloud/bookworm/latest/debian-12-genericcloud-amd64.qcow2"
+                .to_string(),
+            // Proxmox accepts only .img and .iso files as ISO content
+            file_name: Some("debian-12-genericcloud-amd64.img".to_string()),
+            os_family: OsFamily::Debian,
+            checksum: None,
+            checksum_algorithm: Some(ChecksumAlgorithm::Sha512),
+            checksum_url: Some(
+                "https://cloud.debian.org/images/cloud/bookworm/latest/SHA512SUMS".to_string(),
+            ),
+            max_age_days: Some(30),
+        },
     ]
 }
 
-pub fn os_images() -> HashMap<String, String> {
-    HashMap::from([
-        (
-            "Ubuntu Server 22.04 LTS - jammy-server-cloudimg-amd64.img".to_string(),
-            "https://cloud-images.ubuntu.com/jammy/current/jammy-server-cloudimg-amd64.img".to_string()
+#[cfg(test)]
+mod test {
+    use crate::model::{Distribution, KubeVersion};
+    use crate::supported::{default_kube_version, parse_catalog, parse_snap_channels};
+
+    #[test]
+    fn stable_snap_channels_are_discovered() {
+        let output = "name:      microk8s
+summary:   Kubernetes for workstations and appliances
+channels:
+  1.32/stable:      v1.32.3  2025-04-07 (7964) 172MB classic
+  1.32/candidate:   v1.32.3  2025-04-07 (7964) 172MB classic
+  latest/stable:    v1.32.3  2025-04-07 (7967) 172MB classic
+  1.31/stable:      v1.31.7  2025-04-01 (7870) 167MB classic
+  1.33/stable:      --
+  1.28/stable:      v1.28.15 2024-11-05 (7421) 159MB classic
+installed:          v1.31.7             (7870) 167MB classic
+";
+        let catalog = vec![KubeVersion {
+            version: "1.28/stable".to_string(),
+            deprecated: true,
+        }];
+        assert_eq!(
+            vec![
+                ("1.32/stable", false),
+                ("1.31/stable", false),
+                ("1.28/stable", true)
+            ],
+            parse_snap_channels(output, &catalog)
+                .iter()
+                .map(|i| (i.version.as_str(), i.deprecated))
+                .collect::<Vec<(&str, bool)>>()
+        );
+    }
+
+    #[test]
+    fn catalog_file_is_validated() {
+        let catalog = parse_catalog(
+            r#"{"microk8sVersions": [{"version": "1.33/stable"}],
+                "osImages": [{"name": "Ubuntu", "url": "https://example.com/ubuntu.img"}]}"#,
         )
-    ])
-}
\ No newline at end of file
+        .unwrap();
+        assert_eq!(1, catalog.microk8s_versions.len());
+        assert!(catalog.k3s_versions.is_empty());
+        assert!(parse_catalog(
+            r#"{"osImages": [{"name": "Debian", "url": "https://example.com/debian.qcow2"}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn default_kube_version_is_not_deprecated() {
+        assert_eq!(
+            Some("1.32/stable".to_string()),
+            default_kube_version(&Distribution::MicroK8s)
+        );
+        assert_eq!(Some("stable".to_string()), default_kube_version(&Distribution::K3s));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::model::{Cluster, ClusterNode};
+
+/// Tag of every VM created by Makoon.
+pub(crate) const MAKOON_TAG: &str = "makoon";
+
+/// Proxmox tags are lowercase and allow only `a-z 0-9 _ - + .`
+fn sanitize(value: &str) -> String {
+    value
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '_' | '-' | '+' | '.' => c,
+            _ => '-',
+        })
+        .collect()
+}
+
+pub(crate) fn cluster_tag(cluster_name: &str) -> String {
+    format!("{}-{}", MAKOON_TAG, sanitize(cluster_name))
+}
+
+/// Value of VM `tags` option: Makoon, cluster and role of the node.
+pub(crate) fn vm_tags(cluster: &Cluster, node: &ClusterNode) -> String {
+    [
+        MAKOON_TAG.to_string(),
+        cluster_tag(&cluster.cluster_name),
+        sanitize(&node.node_type.to_string()),
+    ]
+    .join(";")
+}
+
+/// Tags of the VM listing, Proxmox accepts `;`, `,` and spaces as separators.
+pub(crate) fn parse(tags: Option<&str>) -> Vec<String> {
+    tags.unwrap_or_default()
+        .split([';', ',', ' '])
+        .filter(|i| !i.is_empty())
+        .map(|i| i.to_string())
+        .collect()
+}
+
+/// VM is a node of the cluster when it has the cluster tag. VMs created before Makoon tagged
+/// them are recognized by `<cluster>-<node>` name.
+pub(crate) fn is_cluster_vm(
+    cluster: &Cluster,
+    node: &ClusterNode,
+    vm_name: &str,
+    vm_tags: Option<&str>,
+) -> bool {
+    let tags = parse(vm_tags);
+    if tags.iter().any(|i| i == MAKOON_TAG) {
+        return tags.contains(&cluster_tag(&cluster.cluster_name));
+    }
+    vm_name == node.host_name(&cluster.cluster_name)
+}
+
+pub(crate) fn validate_resource_pool(pool: &str) -> Result<(), String> {
+    let valid = !pool.is_empty()
+        && pool
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    match valid {
+        true => Ok(()),
+        false => Err(format!(
+            "Invalid Proxmox pool [{}], allowed characters are letters, digits, '-', '_' and '.'",
+            pool
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::{Cluster, ClusterNode, ClusterNodeType};
+    use crate::tags::{is_cluster_vm, vm_tags};
+
+    #[test]
+    fn cluster_vm_is_found_by_tag_or_legacy_name() {
+        let cluster = Cluster {
+            cluster_name: "Prod_1".to_string(),
+            ..Default::default()
+        };
+        let node = ClusterNode {
+            name: "master-1".to_string(),
+            node_type: ClusterNodeType::Master,
+            ..Default::default()
+        };
+        assert_eq!("makoon;makoon-prod_1;master", vm_tags(&cluster, &node));
+
+        assert!(is_cluster_vm(&cluster, &node, "renamed", Some("makoon;makoon-prod_1;master")));
+        assert!(!is_cluster_vm(&cluster, &node, "Prod_1-master-1", Some("makoon;makoon-dev")));
+        assert!(is_cluster_vm(&cluster, &node, "Prod_1-master-1", None));
+        assert!(!is_cluster_vm(&cluster, &node, "other", Some("web")));
+    }
+}
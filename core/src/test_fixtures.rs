@@ -0,0 +1,20 @@
+use crate::model::{ClusterNode, ClusterNodeType};
+
+/// Node with the resources of a small VM, tests override the fields they check.
+pub(crate) fn node(
+    name: &str,
+    vm_id: u32,
+    ip_address: &str,
+    node_type: ClusterNodeType,
+) -> ClusterNode {
+    ClusterNode {
+        name: name.to_string(),
+        vm_id,
+        ip_address: ip_address.to_string(),
+        node_type,
+        cores: 2,
+        memory: 4096,
+        storage_pool: "local-lvm".to_string(),
+        ..Default::default()
+    }
+}
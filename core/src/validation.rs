@@ -0,0 +1,525 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use proxmox_client::model::NodeStatus;
+use proxmox_client::ClientOperations;
+
+use crate::model::{
+    Cluster, ClusterNode, ClusterNodeType, ClusterRequest, IpPool, Network, ValidationError,
+};
+
+const MAX_LABEL_LENGTH: usize = 63;
+const MIN_VM_ID: u32 = 100;
+const MAX_VM_ID: u32 = 999_999_999;
+
+/// State the request is checked against: clusters managed by Makoon and VM IDs used on Proxmox.
+pub(crate) struct Context<'a> {
+    pub(crate) clusters: &'a [Cluster],
+    pub(crate) ip_pools: &'a [IpPool],
+    pub(crate) proxmox_vm_ids: &'a [u32],
+}
+
+fn error(errors: &mut Vec<ValidationError>, field: String, message: String) {
+    errors.push(ValidationError { field, message });
+}
+
+/// Kubernetes node names are RFC 1123 labels: lowercase letters, digits and `-`.
+fn is_dns_label(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_LABEL_LENGTH
+        && !value.starts_with('-')
+        && !value.ends_with('-')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Maximum prefix length of the address family of the gateway.
+fn max_prefix(gateway: &IpAddr) -> u8 {
+    match gateway {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+/// Addresses of different families are never in the same subnet.
+fn in_subnet(address: IpAddr, gateway: IpAddr, prefix: u8) -> bool {
+    match (address, gateway) {
+        (IpAddr::V4(address), IpAddr::V4(gateway)) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            u32::from(address) & mask == u32::from(gateway) & mask
+        }
+        (IpAddr::V6(address), IpAddr::V6(gateway)) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            u128::from(address) & mask == u128::from(gateway) & mask
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn cluster_request(request: &ClusterRequest, context: &Context) -> Vec<ValidationError> {
+    let mut errors = vec![];
+    if !is_dns_label(&request.cluster_name) {
+        error(
+            &mut errors,
+            "clusterName".to_string(),
+            format!(
+                "[{}] is not a valid DNS label, use up to {} lowercase letters, digits and '-'",
+                request.cluster_name, MAX_LABEL_LENGTH
+            ),
+        );
+    }
+    if !request
+        .nodes
+        .iter()
+        .any(|i| i.node_type == ClusterNodeType::Master)
+    {
+        error(
+            &mut errors,
+            "nodes".to_string(),
+            "Cluster has to have at least one master node".to_string(),
+        );
+    }
+    validate_network(&request.network, &mut errors);
+    errors.extend(nodes(
+        &request.cluster_name,
+        &request.network,
+        &request.nodes,
+        &[],
+        context,
+        |index, field| format!("nodes[{}].{}", index, field),
+    ));
+    errors
+}
+
+/// Node added to the existing cluster, fields are relative to the node.
+pub(crate) fn new_node(
+    cluster: &Cluster,
+    node: &ClusterNode,
+    context: &Context,
+) -> Vec<ValidationError> {
+    nodes(
+        &cluster.cluster_name,
+        &cluster.network,
+        std::slice::from_ref(node),
+        &cluster.nodes,
+        context,
+        |_, field| field.to_string(),
+    )
+}
+
+/// IPv6-only network has IPv6 gateway and the subnet mask is its prefix length.
+fn validate_network(network: &Network, errors: &mut Vec<ValidationError>) {
+    if network.dhcp {
+        return;
+    }
+    let gateway = IpAddr::from_str(network.gateway.trim()).ok();
+    match gateway {
+        Some(gateway) => {
+            if network.subnet_mask == 0 || network.subnet_mask > max_prefix(&gateway) {
+                error(
+                    errors,
+                    "network.subnetMask".to_string(),
+                    format!(
+                        "Subnet mask [{}] is out of range 1-{}",
+                        network.subnet_mask,
+                        max_prefix(&gateway)
+                    ),
+                );
+            }
+        }
+        None => error(
+            errors,
+            "network.gateway".to_string(),
+            format!("Gateway [{}] is not a valid IP address", network.gateway),
+        ),
+    }
+}
+
+fn nodes(
+    cluster_name: &str,
+    network: &Network,
+    nodes: &[ClusterNode],
+    existing: &[ClusterNode],
+    context: &Context,
+    path: impl Fn(usize, &str) -> String,
+) -> Vec<ValidationError> {
+    let mut errors = vec![];
+    let gateway = IpAddr::from_str(network.gateway.trim()).ok();
+    let pool_exists = context.ip_pools.iter().any(|i| i.bridge == network.bridge);
+    let other_clusters = context
+        .clusters
+        .iter()
+        .filter(|i| i.cluster_name != cluster_name)
+        .collect::<Vec<&Cluster>>();
+    let mut names: HashMap<&str, usize> = HashMap::new();
+    let mut vm_ids: HashMap<u32, usize> = HashMap::new();
+
+    for (index, node) in nodes.iter().enumerate() {
+        let host_name = node.host_name(cluster_name);
+        if !is_dns_label(&node.name) || host_name.len() > MAX_LABEL_LENGTH {
+            error(
+                &mut errors,
+                path(index, "name"),
+                format!(
+                    "Host name [{}] is not a valid DNS label, use up to {} lowercase letters, digits and '-'",
+                    host_name, MAX_LABEL_LENGTH
+                ),
+            );
+        }
+        if existing.iter().any(|i| i.name == node.name) {
+            error(
+                &mut errors,
+                path(index, "name"),
+                format!("Node [{}] already exists", node.name),
+            );
+        } else if let Some(first) = names.insert(&node.name, index) {
+            error(
+                &mut errors,
+                path(index, "name"),
+                format!("Node name [{}] is also used by {}", node.name, path(first, "name")),
+            );
+        }
+
+        if !(MIN_VM_ID..=MAX_VM_ID).contains(&node.vm_id) {
+            error(
+                &mut errors,
+                path(index, "vmId"),
+                format!("VM ID [{}] is out of range {}-{}", node.vm_id, MIN_VM_ID, MAX_VM_ID),
+            );
+        } else if let Some(first) = vm_ids.insert(node.vm_id, index) {
+            error(
+                &mut errors,
+                path(index, "vmId"),
+                format!("VM ID [{}] is also used by {}", node.vm_id, path(first, "vmId")),
+            );
+        } else if let Some(cluster) = other_clusters
+            .iter()
+            .find(|c| c.nodes.iter().any(|i| i.vm_id == node.vm_id))
+        {
+            error(
+                &mut errors,
+                path(index, "vmId"),
+                format!(
+                    "VM ID [{}] is used by cluster [{}]",
+                    node.vm_id, cluster.cluster_name
+                ),
+            );
+        } else if existing.iter().any(|i| i.vm_id == node.vm_id)
+            || context.proxmox_vm_ids.contains(&node.vm_id)
+        {
+            error(
+                &mut errors,
+                path(index, "vmId"),
+                format!("VM ID [{}] is already used on Proxmox", node.vm_id),
+            );
+        }
+
+        if network.is_dhcp_node(node) {
+            continue;
+        }
+        let ip_address = node.ip_address.trim();
+        if ip_address.is_empty() {
+            if !pool_exists {
+                error(
+                    &mut errors,
+                    path(index, "ipAddress"),
+                    format!(
+                        "IP address is empty and there is no IP pool for bridge [{}]",
+                        network.bridge
+                    ),
+                );
+            }
+            continue;
+        }
+        let address = match IpAddr::from_str(ip_address) {
+            Ok(v) => v,
+            Err(_) => {
+                error(
+                    &mut errors,
+                    path(index, "ipAddress"),
+                    format!("[{}] is not a valid IP address", ip_address),
+                );
+                continue;
+            }
+        };
+        if let Some(cluster) = other_clusters.iter().find(|c| {
+            c.nodes
+                .iter()
+                .any(|i| IpAddr::from_str(i.ip_address.trim()).ok() == Some(address))
+        }) {
+            error(
+                &mut errors,
+                path(index, "ipAddress"),
+                format!(
+                    "IP address [{}] is used by cluster [{}]",
+                    address, cluster.cluster_name
+                ),
+            );
+        }
+        if let Some(gateway) = gateway {
+            if (1..=max_prefix(&gateway)).contains(&network.subnet_mask)
+                && !in_subnet(address, gateway, network.subnet_mask)
+            {
+                error(
+                    &mut errors,
+                    "network.gateway".to_string(),
+                    format!(
+                        "Gateway [{}] is outside of subnet {}/{} of node [{}]",
+                        gateway, address, network.subnet_mask, node.name
+                    ),
+                );
+            }
+        }
+    }
+    errors
+}
+
+/// Imported VMs exist on Proxmox already, they only must not be nodes of other clusters.
+pub(crate) fn imported_vm_ids(vm_ids: &[u32], context: &Context) -> Vec<ValidationError> {
+    let mut errors = vec![];
+    for (index, vm_id) in vm_ids.iter().enumerate() {
+        if let Some(cluster) = context
+            .clusters
+            .iter()
+            .find(|c| c.nodes.iter().any(|i| i.vm_id == *vm_id))
+        {
+            error(
+                &mut errors,
+                format!("vmIds[{}]", index),
+                format!("VM ID [{}] is used by cluster [{}]", vm_id, cluster.cluster_name),
+            );
+        }
+    }
+    errors
+}
+
+/// IDs of VMs and containers on all online Proxmox nodes, they share one ID space.
+pub(crate) fn proxmox_vm_ids(proxmox_client: &ClientOperations) -> crate::Result<Vec<u32>> {
+    let mut result = vec![];
+    for node in proxmox_client.nodes()? {
+        if !matches!(node.status, NodeStatus::Online) {
+            continue;
+        }
+        result.extend(
+            proxmox_client
+                .virtual_machines(&node.node, None)?
+                .iter()
+                .map(|i| i.vm_id),
+        );
+        result.extend(
+            proxmox_client
+                .lxc_containers(&node.node)?
+                .iter()
+                .map(|i| i.vm_id),
+        );
+    }
+    Ok(result)
+}
+
+pub(crate) fn into_result(errors: Vec<ValidationError>) -> crate::Result<()> {
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(crate::Error::Validation(errors)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::IpAddr;
+    use std::str::FromStr;
+
+    use crate::model::{Cluster, ClusterNode, ClusterNodeType, ClusterRequest, Network};
+    use crate::test_fixtures;
+    use crate::validation::{
+        cluster_request, imported_vm_ids, in_subnet, is_dns_label, new_node, Context,
+    };
+
+    fn node(name: &str, vm_id: u32, ip_address: &str) -> ClusterNode {
+        test_fixtures::node(name, vm_id, ip_address, ClusterNodeType::Worker)
+    }
+
+    fn request(nodes: Vec<ClusterNode>) -> ClusterRequest {
+        serde_json::from_value::<ClusterRequest>(serde_json::json!({
+            "osImage": "",
+            "osImageStorage": "",
+            "kubeVersion": "",
+            "node": "pve",
+            "clusterName": "Prod_1",
+            "nodeUsername": "ubuntu",
+            "nodePassword": "",
+            "helmApps": [],
+            "clusterResources": [],
+            "diskSize": 20,
+            "nodes": [],
+            "network": Network {
+                gateway: "10.0.0.1".to_string(),
+                subnet_mask: 24,
+                bridge: "vmbr0".to_string(),
+                ..Default::default()
+            }
+        }))
+        .map(|mut i| {
+            i.nodes = nodes;
+            i
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn request_errors_have_field_paths() {
+        let other = Cluster {
+            cluster_name: "dev".to_string(),
+            nodes: vec![node("worker-1", 300, "10.0.0.30")],
+            ..Default::default()
+        };
+        let request = request(vec![
+            node("worker-1", 200, "10.0.0.20"),
+            node("worker-1", 200, ""),
+            node("worker-2", 300, "10.0.1.30"),
+            node("worker-3", 400, "10.0.0.30"),
+        ]);
+        let errors = cluster_request(
+            &request,
+            &Context {
+                clusters: &[other],
+                ip_pools: &[],
+                proxmox_vm_ids: &[400],
+            },
+        );
+        let fields = errors.iter().map(|i| i.field.as_str()).collect::<Vec<&str>>();
+        assert_eq!(
+            vec![
+                "clusterName",
+                "nodes",
+                "nodes[1].name",
+                "nodes[1].vmId",
+                "nodes[1].ipAddress",
+                "nodes[2].vmId",
+                "network.gateway",
+                "nodes[3].vmId",
+                "nodes[3].ipAddress",
+            ],
+            fields
+        );
+    }
+
+    fn fields(request: &ClusterRequest) -> Vec<String> {
+        cluster_request(
+            request,
+            &Context {
+                clusters: &[],
+                ip_pools: &[],
+                proxmox_vm_ids: &[],
+            },
+        )
+        .into_iter()
+        .map(|i| i.field)
+        .collect()
+    }
+
+    fn master(name: &str, vm_id: u32, ip_address: &str) -> ClusterNode {
+        ClusterNode {
+            node_type: ClusterNodeType::Master,
+            ..node(name, vm_id, ip_address)
+        }
+    }
+
+    #[test]
+    fn names_are_dns_labels() {
+        assert!(is_dns_label("prod-1"));
+        assert!(!is_dns_label("Prod"));
+        assert!(!is_dns_label("prod_1"));
+        assert!(!is_dns_label("-prod"));
+        assert!(!is_dns_label("prod-"));
+        assert!(!is_dns_label(""));
+        assert!(!is_dns_label(&"a".repeat(64)));
+
+        let mut request = request(vec![master(&"m".repeat(60), 200, "10.0.0.20")]);
+        request.cluster_name = "prod".to_string();
+        assert_eq!(vec!["nodes[0].name"], fields(&request));
+    }
+
+    #[test]
+    fn gateway_has_to_be_in_subnet_of_nodes() {
+        let mut request = request(vec![master("master-1", 200, "10.0.0.20")]);
+        request.cluster_name = "prod".to_string();
+        assert!(fields(&request).is_empty());
+
+        request.nodes[0].ip_address = "10.0.1.20".to_string();
+        assert_eq!(vec!["network.gateway"], fields(&request));
+
+        request.network.subnet_mask = 16;
+        assert!(fields(&request).is_empty());
+
+        request.network.subnet_mask = 33;
+        assert_eq!(vec!["network.subnetMask"], fields(&request));
+    }
+
+    #[test]
+    fn ipv6_only_network_uses_prefix_length() {
+        let mut request = request(vec![master("master-1", 200, "fd00::20")]);
+        request.cluster_name = "prod".to_string();
+        request.network.gateway = "fd00::1".to_string();
+        request.network.subnet_mask = 64;
+        assert!(fields(&request).is_empty());
+
+        request.nodes[0].ip_address = "fd01::20".to_string();
+        assert_eq!(vec!["network.gateway"], fields(&request));
+
+        request.network.subnet_mask = 129;
+        assert_eq!(vec!["network.subnetMask"], fields(&request));
+
+        let address = |value: &str| IpAddr::from_str(value).unwrap();
+        assert!(!in_subnet(address("10.0.0.20"), address("fd00::1"), 64));
+    }
+
+    #[test]
+    fn new_node_errors_are_relative_to_node() {
+        let cluster = Cluster {
+            cluster_name: "prod".to_string(),
+            network: Network {
+                gateway: "10.0.0.1".to_string(),
+                subnet_mask: 24,
+                bridge: "vmbr0".to_string(),
+                ..Default::default()
+            },
+            nodes: vec![master("master-1", 200, "10.0.0.20")],
+            ..Default::default()
+        };
+        let context = Context {
+            clusters: std::slice::from_ref(&cluster),
+            ip_pools: &[],
+            proxmox_vm_ids: &[200],
+        };
+        let fields = |node: ClusterNode| {
+            new_node(&cluster, &node, &context)
+                .into_iter()
+                .map(|i| i.field)
+                .collect::<Vec<String>>()
+        };
+        assert!(fields(node("worker-1", 201, "10.0.0.21")).is_empty());
+        assert_eq!(vec!["name", "vmId"], fields(node("master-1", 200, "10.0.0.21")));
+        assert_eq!(vec!["ipAddress"], fields(node("worker-1", 201, "")));
+    }
+
+    #[test]
+    fn imported_vms_must_not_belong_to_other_clusters() {
+        let other = Cluster {
+            cluster_name: "dev".to_string(),
+            nodes: vec![node("worker-1", 300, "10.0.0.30")],
+            ..Default::default()
+        };
+        let errors = imported_vm_ids(
+            &[200, 300],
+            &Context {
+                clusters: &[other],
+                ip_pools: &[],
+                proxmox_vm_ids: &[],
+            },
+        );
+        assert_eq!(1, errors.len());
+        assert_eq!("vmIds[1]", errors[0].field);
+    }
+}
@@ -48,6 +48,36 @@ impl Client {
             c => Err(Error::Generic(format!("Login to proxmox returned [{}] code", c)))
         }
     }
+    #[doc = "Gets a new ticket using the current one as password, tickets expire after 2 hours. API tokens are returned as they are."]
+    pub fn renew_ticket(&self, access: AccessData) -> Result<AccessData> {
+        if access.token.api_token.is_some() {
+            return Ok(access);
+        }
+        let http = HttpClient::new(
+            access.host.clone(),
+            access.port,
+            access.base_path.clone());
+
+        let response = http.client()
+            .post(format!("https://{}:{}{}/access/ticket", access.host, access.port, access.base_path))
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(format!(
+                "username={}&password={}",
+                to_url_encoded(&access.token.username),
+                to_url_encoded(&access.token.ticket)))
+            .timeout(Duration::from_secs(10))
+            .send()?;
+
+        match response.status().as_u16() {
+            200..=299 => {
+                let token = response.json::<Data<Token>>()?.data;
+                Ok(AccessData { token, ..access })
+            }
+            401 => Err(Error::CredentialsInvalid),
+            c => Err(Error::Generic(format!("Renewing proxmox ticket returned [{}] code", c)))
+        }
+    }
+
     pub fn operations(&self, access: AccessData) -> ClientOperations {
         let http = HttpClient::new(
             access.host,
@@ -66,6 +66,18 @@ impl ClientOperations {
             .data)
     }
 
+    #[doc = "Get the virtual machine configuration with pending configuration changes applied."]
+    #[doc = r#"Check: ["perm","/vms/{vmid}",["VM.Audit"]]"#]
+    pub fn vm_config(&self, node: &str, vm_id: u32) -> Result<VmConfigDetails> {
+        Ok(self
+            .http
+            .get::<Data<VmConfigDetails>>(
+                &self.token,
+                format!("/nodes/{}/qemu/{}/config", node, vm_id).as_str(),
+            )?
+            .data)
+    }
+
     #[doc = "Change user password."]
     ///Each user is allowed to change his own password. A user can change the password of another user if he has 'Realm.AllocateUser' (on the realm of user <userid>) and 'User.Modify' permission on /access/groups/<group> on a group where user <userid> is member of.
     /// Check: ["or",["userid-param","self"],["and",["userid-param","Realm.AllocateUser"],["userid-group",["User.Modify"]]]]
@@ -239,6 +251,50 @@ impl ClientOperations {
             .data)
     }
 
+    #[doc = "Upload templates, ISO images and snippets."]
+    #[doc = r#"Check: ["perm","/storage/{storage}",["Datastore.AllocateTemplate"]]"#]
+    pub fn upload_content(&self, req: UploadContent) -> Result<String> {
+        debug!("Upload [{}] to storage [{}]", req.filename, req.storage);
+        let part = reqwest::blocking::multipart::Part::bytes(req.data)
+            .file_name(req.filename.clone())
+            .mime_str("application/octet-stream")?;
+        let form = reqwest::blocking::multipart::Form::new()
+            .text("content", req.content.to_string())
+            .part("filename", part);
+        Ok(self
+            .http
+            .post_multipart::<Data<String>>(
+                &self.token,
+                format!("/nodes/{}/storage/{}/upload", req.node, req.storage).as_str(),
+                form,
+            )?
+            .data)
+    }
+
+    #[doc = "Delete volume"]
+    #[doc = "You need 'Datastore.Allocate' privilege on the storage (or 'Datastore.AllocateSpace' for backup volumes if you have VM.Backup privilege on the VM)."]
+    pub fn delete_storage_content(
+        &self,
+        node: &str,
+        storage: &str,
+        volid: &str,
+    ) -> Result<Option<String>> {
+        debug!("Delete volume [{}]", volid);
+        Ok(self
+            .http
+            .delete::<Data<Option<String>>>(
+                &self.token,
+                format!(
+                    "/nodes/{}/storage/{}/content/{}",
+                    node,
+                    storage,
+                    urlencoding::encode(volid)
+                )
+                .as_str(),
+            )?
+            .data)
+    }
+
     #[doc = "Get volume attributes"]
     #[doc = "You need read access for the volume."]
     pub fn storage_content_details(
@@ -255,4 +311,287 @@ impl ClientOperations {
             )?
             .data)
     }
+
+    #[doc = "List all snapshots."]
+    #[doc = r#"Check: ["perm","/vms/{vmid}",["VM.Audit"]]"#]
+    pub fn snapshots(&self, node: &str, vm_id: u32) -> Result<Vec<Snapshot>> {
+        debug!("Get snapshots for VM [{}]", vm_id);
+        Ok(self
+            .http
+            .get::<Data<Vec<Snapshot>>>(
+                &self.token,
+                format!("/nodes/{}/qemu/{}/snapshot", node, vm_id).as_str(),
+            )?
+            .data)
+    }
+
+    #[doc = "Snapshot a VM."]
+    #[doc = r#"Check: ["perm","/vms/{vmid}",["VM.Snapshot"]]"#]
+    pub fn create_snapshot(&self, req: CreateSnapshot) -> Result<String> {
+        debug!("Create snapshot [{}] for VM [{}]", req.snap_name, req.vm_id);
+        Ok(self
+            .http
+            .post::<CreateSnapshot, Data<String>>(
+                &self.token,
+                format!("/nodes/{}/qemu/{}/snapshot", req.node, req.vm_id).as_str(),
+                Some(req),
+            )?
+            .data)
+    }
+
+    #[doc = "Rollback VM state to specified snapshot."]
+    #[doc = r#"Check: ["or",["perm","/vms/{vmid}",["VM.Snapshot.Rollback"]],["perm","/vms/{vmid}",["VM.Snapshot"]]]"#]
+    pub fn rollback_snapshot(&self, node: &str, vm_id: u32, snap_name: &str) -> Result<String> {
+        debug!("Rollback VM [{}] to snapshot [{}]", vm_id, snap_name);
+        Ok(self
+            .http
+            .post::<(), Data<String>>(
+                &self.token,
+                format!("/nodes/{}/qemu/{}/snapshot/{}/rollback", node, vm_id, snap_name).as_str(),
+                None,
+            )?
+            .data)
+    }
+
+    #[doc = "Delete a VM snapshot."]
+    #[doc = r#"Check: ["perm","/vms/{vmid}",["VM.Snapshot"]]"#]
+    pub fn delete_snapshot(&self, node: &str, vm_id: u32, snap_name: &str) -> Result<String> {
+        debug!("Delete snapshot [{}] of VM [{}]", snap_name, vm_id);
+        Ok(self
+            .http
+            .delete::<Data<String>>(
+                &self.token,
+                format!("/nodes/{}/qemu/{}/snapshot/{}", node, vm_id, snap_name).as_str(),
+            )?
+            .data)
+    }
+
+    #[doc = "Freeze guest filesystems through the QEMU guest agent."]
+    #[doc = r#"Check: ["perm","/vms/{vmid}",["VM.Monitor"]]"#]
+    pub fn agent_fsfreeze_freeze(&self, node: &str, vm_id: u32) -> Result<()> {
+        debug!("Freeze filesystems of VM [{}]", vm_id);
+        self.http.post::<(), Data<serde_json::Value>>(
+            &self.token,
+            format!("/nodes/{}/qemu/{}/agent/fsfreeze-freeze", node, vm_id).as_str(),
+            None,
+        )?;
+        Ok(())
+    }
+
+    #[doc = "Thaw guest filesystems through the QEMU guest agent."]
+    #[doc = r#"Check: ["perm","/vms/{vmid}",["VM.Monitor"]]"#]
+    pub fn agent_fsfreeze_thaw(&self, node: &str, vm_id: u32) -> Result<()> {
+        debug!("Thaw filesystems of VM [{}]", vm_id);
+        self.http.post::<(), Data<serde_json::Value>>(
+            &self.token,
+            format!("/nodes/{}/qemu/{}/agent/fsfreeze-thaw", node, vm_id).as_str(),
+            None,
+        )?;
+        Ok(())
+    }
+
+    #[doc = "Execute network-get-interfaces through the QEMU guest agent."]
+    #[doc = r#"Check: ["perm","/vms/{vmid}",["VM.Monitor"]]"#]
+    pub fn agent_network_interfaces(
+        &self,
+        node: &str,
+        vm_id: u32,
+    ) -> Result<Vec<AgentNetworkInterface>> {
+        debug!("Get network interfaces of VM [{}]", vm_id);
+        Ok(self
+            .http
+            .get::<Data<AgentResult<Vec<AgentNetworkInterface>>>>(
+                &self.token,
+                format!("/nodes/{}/qemu/{}/agent/network-get-interfaces", node, vm_id).as_str(),
+            )?
+            .data
+            .result)
+    }
+
+    #[doc = "Execute ping through the QEMU guest agent."]
+    #[doc = r#"Check: ["perm","/vms/{vmid}",["VM.Monitor"]]"#]
+    pub fn agent_ping(&self, node: &str, vm_id: u32) -> Result<()> {
+        debug!("Ping guest agent of VM [{}]", vm_id);
+        self.http.post::<(), Data<serde_json::Value>>(
+            &self.token,
+            format!("/nodes/{}/qemu/{}/agent/ping", node, vm_id).as_str(),
+            None,
+        )?;
+        Ok(())
+    }
+
+    #[doc = "Executes the given command in the vm via the guest-agent and returns an object with the pid."]
+    #[doc = r#"Check: ["perm","/vms/{vmid}",["VM.Monitor"]]"#]
+    pub fn agent_exec(&self, node: &str, vm_id: u32, req: AgentExec) -> Result<AgentExecPid> {
+        debug!("Execute [{}] in VM [{}]", req.command.join(" "), vm_id);
+        Ok(self
+            .http
+            .post::<AgentExec, Data<AgentExecPid>>(
+                &self.token,
+                format!("/nodes/{}/qemu/{}/agent/exec", node, vm_id).as_str(),
+                Some(req),
+            )?
+            .data)
+    }
+
+    #[doc = "Gets the status of the given pid started by the guest-agent"]
+    #[doc = r#"Check: ["perm","/vms/{vmid}",["VM.Monitor"]]"#]
+    pub fn agent_exec_status(&self, node: &str, vm_id: u32, pid: u64) -> Result<AgentExecStatus> {
+        debug!("Get status of process [{}] in VM [{}]", pid, vm_id);
+        Ok(self
+            .http
+            .get::<Data<AgentExecStatus>>(
+                &self.token,
+                format!("/nodes/{}/qemu/{}/agent/exec-status?pid={}", node, vm_id, pid).as_str(),
+            )?
+            .data)
+    }
+
+    #[doc = "Reads the given file via guest agent. Is limited to 16777215 bytes."]
+    #[doc = r#"Check: ["perm","/vms/{vmid}",["VM.Monitor"]]"#]
+    pub fn agent_file_read(&self, node: &str, vm_id: u32, file: &str) -> Result<AgentFileRead> {
+        debug!("Read file [{}] of VM [{}]", file, vm_id);
+        Ok(self
+            .http
+            .get::<Data<AgentFileRead>>(
+                &self.token,
+                format!(
+                    "/nodes/{}/qemu/{}/agent/file-read?file={}",
+                    node,
+                    vm_id,
+                    urlencoding::encode(file)
+                )
+                .as_str(),
+            )?
+            .data)
+    }
+
+    #[doc = "Read task status."]
+    #[doc = "The user needs 'Sys.Audit' permissions on '/nodes/<node>' if they aren't the owner of the task."]
+    pub fn task_status(&self, node: &str, upid: &str) -> Result<TaskStatus> {
+        Ok(self
+            .http
+            .get::<Data<TaskStatus>>(
+                &self.token,
+                format!("/nodes/{}/tasks/{}/status", node, upid).as_str(),
+            )?
+            .data)
+    }
+
+    #[doc = "Create backup."]
+    #[doc = "The user needs 'VM.Backup' permissions on any VM, and 'Datastore.AllocateSpace' on the backup storage."]
+    pub fn vzdump(&self, req: Vzdump) -> Result<String> {
+        debug!("Backup VMs [{}]", req.vm_id);
+        Ok(self
+            .http
+            .post::<Vzdump, Data<String>>(
+                &self.token,
+                format!("/nodes/{}/vzdump", req.node).as_str(),
+                Some(req),
+            )?
+            .data)
+    }
+
+    #[doc = "List backup volumes, optionally only for one guest."]
+    #[doc = r#"Check: ["perm","/storage/{storage}",["Datastore.Audit","Datastore.AllocateSpace"],"any",1]"#]
+    pub fn backups(
+        &self,
+        node: &str,
+        storage: &str,
+        vm_id: Option<u32>,
+    ) -> Result<Vec<StorageContent>> {
+        debug!("Get backups");
+        let url = format!(
+            "/nodes/{}/storage/{}/content?content={}",
+            node,
+            storage,
+            StorageContentType::Backup
+        );
+        let url = match vm_id {
+            Some(v) => format!("{}&vmid={}", url, v),
+            None => url,
+        };
+        Ok(self
+            .http
+            .get::<Data<Vec<StorageContent>>>(&self.token, url.as_str())?
+            .data)
+    }
+
+    #[doc = "Restore a virtual machine from a backup archive."]
+    #[doc = "For restore, it is enough if the user has 'VM.Backup' permission and the VM already exists."]
+    pub fn restore_virtual_machine(&self, req: RestoreVirtualMachine) -> Result<String> {
+        debug!("Restore VM [{}] from [{}]", req.vm_id, req.archive);
+        Ok(self
+            .http
+            .post::<RestoreVirtualMachine, Data<String>>(
+                &self.token,
+                format!("/nodes/{}/qemu", req.node).as_str(),
+                Some(req),
+            )?
+            .data)
+    }
+
+    #[doc = "List vzdump backup schedule."]
+    #[doc = r#"Check: ["perm","/",["Sys.Audit"]]"#]
+    pub fn backup_jobs(&self) -> Result<Vec<BackupJob>> {
+        debug!("Get backup jobs");
+        Ok(self
+            .http
+            .get::<Data<Vec<BackupJob>>>(&self.token, "/cluster/backup")?
+            .data)
+    }
+
+    #[doc = "Create new vzdump backup job."]
+    #[doc = r#"Check: ["perm","/",["Sys.Modify"]]"#]
+    pub fn create_backup_job(&self, req: BackupJob) -> Result<Option<String>> {
+        debug!("Create backup job [{}]", req.id);
+        Ok(self
+            .http
+            .post::<BackupJob, Data<Option<String>>>(&self.token, "/cluster/backup", Some(req))?
+            .data)
+    }
+
+    #[doc = "Update vzdump backup job definition."]
+    #[doc = r#"Check: ["perm","/",["Sys.Modify"]]"#]
+    pub fn update_backup_job(&self, req: BackupJob) -> Result<Option<String>> {
+        debug!("Update backup job [{}]", req.id);
+        Ok(self
+            .http
+            .put::<BackupJob, Data<Option<String>>>(
+                &self.token,
+                format!("/cluster/backup/{}", req.id).as_str(),
+                Some(req),
+            )?
+            .data)
+    }
+
+    #[doc = "Delete vzdump backup job definition."]
+    #[doc = r#"Check: ["perm","/",["Sys.Modify"]]"#]
+    pub fn delete_backup_job(&self, id: &str) -> Result<Option<String>> {
+        debug!("Delete backup job [{}]", id);
+        Ok(self
+            .http
+            .delete::<Data<Option<String>>>(&self.token, format!("/cluster/backup/{}", id).as_str())?
+            .data)
+    }
+
+    #[doc = "List pools."]
+    #[doc = r#"Check: List pools where you have Pool.Audit permissions on /pool/<pool>"#]
+    pub fn pools(&self) -> Result<Vec<ResourcePool>> {
+        debug!("Get pools");
+        Ok(self
+            .http
+            .get::<Data<Vec<ResourcePool>>>(&self.token, "/pools")?
+            .data)
+    }
+
+    #[doc = "Create new pool."]
+    #[doc = r#"Check: ["perm","/pool/{poolid}",["Pool.Allocate"]]"#]
+    pub fn create_pool(&self, req: ResourcePool) -> Result<Option<String>> {
+        debug!("Create pool [{}]", req.pool_id);
+        Ok(self
+            .http
+            .post::<ResourcePool, Data<Option<String>>>(&self.token, "/pools", Some(req))?
+            .data)
+    }
 }
@@ -1,7 +1,7 @@
 use log::trace;
 
 use reqwest::blocking::RequestBuilder;
-use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE};
 use serde::{Deserialize, Serialize};
 
 use crate::model::Token;
@@ -79,6 +79,19 @@ impl HttpClient {
         self.do_request(req, token)
     }
 
+    pub fn post_multipart<T>(
+        &self,
+        token: &Token,
+        path: &str,
+        form: reqwest::blocking::multipart::Form,
+    ) -> Result<T>
+    where
+        for<'a> T: Deserialize<'a>,
+    {
+        let req = self.client.post(self.url(path)).multipart(form);
+        self.do_request(req, token)
+    }
+
     fn url(&self, path: &str) -> String {
         format!(
             "https://{}:{}{}{}",
@@ -90,13 +103,16 @@ impl HttpClient {
     where
         for<'a> T: Deserialize<'a>,
     {
-        let request = request
-            .header(ACCEPT, "application/json")
-            .header("CSRFPreventionToken", token.csrf_prevention_token.clone())
-            .header(
-                reqwest::header::COOKIE,
-                format!("PVEAuthCookie={}", token.ticket.clone()),
-            );
+        let request = request.header(ACCEPT, "application/json");
+        let request = match token.api_token.as_ref() {
+            Some(v) => request.header(AUTHORIZATION, format!("PVEAPIToken={}", v)),
+            None => request
+                .header("CSRFPreventionToken", token.csrf_prevention_token.clone())
+                .header(
+                    reqwest::header::COOKIE,
+                    format!("PVEAuthCookie={}", token.ticket.clone()),
+                ),
+        };
 
         trace!("Request: {:#?}", request);
         let response = request.send()?;
@@ -21,12 +21,32 @@ pub struct AccessData {
     pub token: Token,
 }
 
+impl AccessData {
+    #[doc = "Access through an API token `USER@REALM!TOKENID=SECRET`, API tokens don't expire."]
+    pub fn from_api_token(host: String, port: u16, base_path: String, api_token: String) -> Self {
+        let username = api_token.split('!').next().unwrap_or_default().to_string();
+        AccessData {
+            host,
+            base_path,
+            port,
+            token: Token {
+                csrf_prevention_token: String::new(),
+                ticket: String::new(),
+                username,
+                api_token: Some(api_token),
+            },
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Token {
     #[serde(rename = "CSRFPreventionToken")]
     pub csrf_prevention_token: String,
     pub ticket: String,
     pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_token: Option<String>,
 }
 
 //------------- Proxmox API ----------------
@@ -399,7 +419,7 @@ pub enum OsType {
     Solaris,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum ScsiHw {
     #[serde(rename = "lsi")]
     Lsi,
@@ -455,6 +475,30 @@ pub struct CreateVirtualMachine {
     #[serde(flatten)]
     pub net: HashMap<String, String>,
 
+    #[doc = "Enable/disable communication with the QEMU Guest Agent and its properties."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+
+    #[doc = "Emulated CPU type."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<String>,
+
+    #[doc = "The number of CPU sockets."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sockets: Option<u16>,
+
+    #[doc = "Enable/disable NUMA."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub numa: Option<u8>,
+
+    #[doc = "Amount of target RAM for the VM in MiB. Using zero disables the ballon driver."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balloon: Option<u64>,
+
+    #[doc = "Specifies the QEMU machine type."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub machine: Option<String>,
+
     #[doc = "SCSI controller model"]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub scsihw: Option<ScsiHw>,
@@ -520,6 +564,84 @@ pub struct CreateVirtualMachine {
     #[serde(rename = "sshkeys")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ssh_keys: Option<String>,
+
+    /// cloud-init: Specify custom files to replace the automatically generated ones at start.
+    /// Format: [meta=<volume>] [,network=<volume>] [,user=<volume>] [,vendor=<volume>]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cicustom: Option<String>,
+
+    #[doc = "Tags of the VM. This is only meta information (semicolon separated list)."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+
+    #[doc = "Add the VM to the specified pool."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool: Option<String>,
+
+    #[doc = "Description for the VM. Shown in the web-interface VM's summary."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct VmConfigDetails {
+    #[doc = "Set a name for the VM. Only used on the configuration web interface."]
+    #[serde(default)]
+    pub name: Option<String>,
+
+    #[doc = "Description for the VM. Shown in the web-interface VM's summary."]
+    #[serde(default)]
+    pub description: Option<String>,
+
+    #[doc = "Tags of the VM. This is only meta information."]
+    #[serde(default)]
+    pub tags: Option<String>,
+
+    #[doc = "The number of cores per socket."]
+    #[serde(default, deserialize_with = "number_or_string")]
+    pub cores: Option<u64>,
+
+    #[doc = "Amount of RAM for the VM in MiB."]
+    #[serde(default, deserialize_with = "number_or_string")]
+    pub memory: Option<u64>,
+
+    #[doc = "cloud-init: User name to change ssh keys and password for instead of the image's configured default user."]
+    #[serde(rename = "ciuser")]
+    #[serde(default)]
+    pub ci_user: Option<String>,
+
+    #[doc = "Other options like net[n], ipconfig[n] or scsi[n]."]
+    #[serde(flatten)]
+    pub options: HashMap<String, serde_json::Value>,
+}
+
+impl VmConfigDetails {
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).and_then(|i| i.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ResourcePool {
+    #[doc = "The pool identifier."]
+    #[serde(rename = "poolid")]
+    pub pool_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct UploadContent {
+    #[doc = "The cluster node name."]
+    pub node: String,
+    #[doc = "The storage identifier."]
+    pub storage: String,
+    #[doc = "Content type."]
+    pub content: StorageContentType,
+    #[doc = "The name of the file to create. Caution: This will be normalized!"]
+    pub filename: String,
+    pub data: Vec<u8>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -535,7 +657,7 @@ pub struct ResizeDisk {
     pub size: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Default, Serialize, Deserialize, Debug)]
 pub struct VmConfig {
     #[doc = "The (unique) ID of the VM."]
     #[serde(rename = "vmid")]
@@ -544,10 +666,49 @@ pub struct VmConfig {
     pub node: String,
 
     #[doc = "The number of cores per socket."]
-    pub cores: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cores: Option<u16>,
 
     #[doc = "Amount of RAM for the VM in MiB. This is the maximum available memory when you use the balloon device."]
-    pub memory: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<u64>,
+
+    #[doc = "Emulated CPU type."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<String>,
+
+    #[doc = "The number of CPU sockets."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sockets: Option<u16>,
+
+    #[doc = "Enable/disable NUMA."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub numa: Option<u8>,
+
+    #[doc = "Amount of target RAM for the VM in MiB. Using zero disables the ballon driver."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balloon: Option<u64>,
+
+    #[doc = "Specifies the QEMU machine type."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub machine: Option<String>,
+
+    #[doc = "SCSI controller model"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scsihw: Option<ScsiHw>,
+
+    #[doc = "cloud-init: Setup public SSH keys (one key per line, OpenSSH format)."]
+    #[serde(rename = "sshkeys")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_keys: Option<String>,
+
+    #[doc = "Tags of the VM. This is only meta information."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+
+    #[doc = "Description for the VM. Shown in the web-interface VM's summary."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -655,6 +816,267 @@ pub struct StorageContentDetails {
     pub protected: Option<u8>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateSnapshot {
+    #[doc = "The (unique) ID of the VM."]
+    #[serde(rename = "vmid")]
+    pub vm_id: u32,
+    #[doc = "The cluster node name."]
+    pub node: String,
+    #[doc = "The name of the snapshot."]
+    #[serde(rename = "snapname")]
+    pub snap_name: String,
+    #[doc = "A textual description or comment."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[doc = "Save the vmstate"]
+    #[serde(rename = "vmstate")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vm_state: Option<u8>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Snapshot {
+    #[doc = "Snapshot identifier. Value 'current' identifies the current VM."]
+    pub name: String,
+
+    #[doc = "Snapshot description."]
+    #[serde(default)]
+    pub description: String,
+
+    #[doc = "Parent snapshot identifier."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+
+    #[doc = "Snapshot creation time"]
+    #[serde(rename = "snaptime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snap_time: Option<u64>,
+
+    #[doc = "Snapshot includes RAM."]
+    #[serde(rename = "vmstate")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vm_state: Option<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskState {
+    Running,
+    Stopped,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaskStatus {
+    #[doc = "Unique task identifier."]
+    pub upid: String,
+
+    #[doc = "Task state."]
+    pub status: TaskState,
+
+    #[doc = "Exit status of finished task, 'OK' when task has been finished successfully."]
+    #[serde(rename = "exitstatus")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_status: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupMode {
+    #[default]
+    Snapshot,
+    Suspend,
+    Stop,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Vzdump {
+    #[doc = "Only run if executed on this node."]
+    pub node: String,
+
+    #[doc = "The ID of the guest system you want to backup (comma separated list)."]
+    #[serde(rename = "vmid")]
+    pub vm_id: String,
+
+    #[doc = "Store resulting file to this storage."]
+    pub storage: String,
+
+    #[doc = "Backup mode."]
+    pub mode: BackupMode,
+
+    #[doc = "Compress dump file (0 | 1 | gzip | lzo | zstd)."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compress: Option<String>,
+
+    #[doc = "Use these retention options instead of those from the storage configuration, e.g. 'keep-last=3'."]
+    #[serde(rename = "prune-backups")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prune_backups: Option<String>,
+
+    #[doc = "Template string for generating notes for the backup(s)."]
+    #[serde(rename = "notes-template")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes_template: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BackupJob {
+    #[doc = "Job ID."]
+    pub id: String,
+
+    #[doc = "Only run if executed on this node."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+
+    #[doc = "Backup schedule. The format is a subset of `systemd` calendar events."]
+    #[serde(default)]
+    pub schedule: String,
+
+    #[doc = "The ID of the guest system you want to backup (comma separated list)."]
+    #[serde(rename = "vmid")]
+    #[serde(default)]
+    pub vm_id: String,
+
+    #[doc = "Store resulting file to this storage."]
+    #[serde(default)]
+    pub storage: String,
+
+    #[doc = "Backup mode."]
+    #[serde(default)]
+    pub mode: BackupMode,
+
+    #[doc = "Use these retention options instead of those from the storage configuration, e.g. 'keep-last=3'."]
+    #[serde(rename = "prune-backups")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prune_backups: Option<String>,
+
+    #[doc = "Template string for generating notes for the backup(s)."]
+    #[serde(rename = "notes-template")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes_template: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RestoreVirtualMachine {
+    #[doc = "The cluster node name."]
+    pub node: String,
+
+    #[doc = "The (unique) ID of the VM."]
+    #[serde(rename = "vmid")]
+    pub vm_id: u32,
+
+    #[doc = "The backup archive. Either the file system path to a .tar or .vma file or a proxmox storage backup volume identifier."]
+    pub archive: String,
+
+    #[doc = "Allow to overwrite existing VM."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force: Option<u8>,
+
+    #[doc = "Start VM after it was created successfully."]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentIpAddressType {
+    Ipv4,
+    Ipv6,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentIpAddress {
+    #[serde(rename = "ip-address-type")]
+    pub ip_address_type: AgentIpAddressType,
+    #[serde(rename = "ip-address")]
+    pub ip_address: String,
+    pub prefix: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentNetworkInterface {
+    pub name: String,
+    #[serde(rename = "hardware-address")]
+    pub hardware_address: Option<String>,
+    #[serde(rename = "ip-addresses")]
+    #[serde(default)]
+    pub ip_addresses: Vec<AgentIpAddress>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AgentResult<T> {
+    pub result: T,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AgentExec {
+    #[doc = "The command as a list of program + arguments."]
+    pub command: Vec<String>,
+    #[doc = "Data to pass as 'input-data' to the guest. Usually treated as STDIN to 'command'."]
+    #[serde(rename = "input-data")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_data: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AgentExecPid {
+    pub pid: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AgentExecStatus {
+    #[doc = "Flag if process has exited, guest agent reports it as bool or number."]
+    #[serde(deserialize_with = "bool_or_number")]
+    pub exited: bool,
+    #[doc = "Process exit code if it was normally terminated."]
+    pub exitcode: Option<i64>,
+    #[doc = "Signal number or exception code if the process was abnormally terminated."]
+    pub signal: Option<i64>,
+    #[doc = "stdout of the process"]
+    #[serde(rename = "out-data")]
+    pub out_data: Option<String>,
+    #[doc = "stderr of the process"]
+    #[serde(rename = "err-data")]
+    pub err_data: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AgentFileRead {
+    #[doc = "The content of the file, maximum 16777215"]
+    pub content: String,
+    #[doc = "If set to 1, the output is truncated and not complete"]
+    #[serde(default)]
+    pub truncated: Option<u8>,
+}
+
+fn bool_or_number<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Bool(v) => Ok(v),
+        serde_json::Value::Number(v) => Ok(v.as_u64().unwrap_or_default() != 0),
+        v => Err(serde::de::Error::custom(format!("Expected bool, got [{}]", v))),
+    }
+}
+
+/// Newer Proxmox returns some numbers as strings, memory also as `current=<number>`.
+fn number_or_string<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Number(v) => Ok(v.as_u64()),
+        serde_json::Value::String(v) => v
+            .trim_start_matches("current=")
+            .parse::<u64>()
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        serde_json::Value::Null => Ok(None),
+        v => Err(serde::de::Error::custom(format!("Expected number, got [{}]", v))),
+    }
+}
+
 pub type VmCurrentStatus = VirtualMachine;
 
 pub struct ParamBuilder {
@@ -693,7 +1115,7 @@ impl ParamBuilder {
 
 #[cfg(test)]
 mod tests {
-    use crate::model::ParamBuilder;
+    use crate::model::{AccessData, AgentExecStatus, ParamBuilder, VmConfigDetails};
 
     #[test]
     fn test_param_builder() {
@@ -712,4 +1134,36 @@ mod tests {
         builder.add_param("name2", "value2");
         assert_eq!(builder.build(), "name:value,name2=value2")
     }
+
+    #[test]
+    fn test_agent_exec_status_accepts_numeric_flag() {
+        let status: AgentExecStatus =
+            serde_json::from_str(r#"{"exited":1,"exitcode":0,"out-data":"done"}"#).unwrap();
+        assert!(status.exited);
+        let status: AgentExecStatus = serde_json::from_str(r#"{"exited":false}"#).unwrap();
+        assert!(!status.exited);
+    }
+
+    #[test]
+    fn test_vm_config_accepts_numbers_as_strings() {
+        let config: VmConfigDetails = serde_json::from_str(
+            r#"{"name":"c1-master-1","cores":2,"memory":"4096","ipconfig0":"ip=dhcp","digest":"x"}"#,
+        )
+        .unwrap();
+        assert_eq!(Some(2), config.cores);
+        assert_eq!(Some(4096), config.memory);
+        assert_eq!(Some("ip=dhcp"), config.option("ipconfig0"));
+    }
+
+    #[test]
+    fn test_access_from_api_token_keeps_user() {
+        let access = AccessData::from_api_token(
+            "pve".to_string(),
+            8006,
+            "/api2/json".to_string(),
+            "makoon@pve!autoscaler=secret".to_string(),
+        );
+        assert_eq!("makoon@pve", access.token.username);
+        assert_eq!(Some("makoon@pve!autoscaler=secret"), access.token.api_token.as_deref());
+    }
 }
@@ -35,7 +35,7 @@ impl Client {
         private_key: &str,
         public_key: &str,
     ) -> Result<(), String> {
-        let tcp = TcpStream::connect(format!("{}:{}", ip_address, self.port))
+        let tcp = TcpStream::connect((ip_address.trim(), self.port))
             .map_err(|e| e.to_string())?;
         let mut session = ssh2::Session::new().map_err(|e| e.to_string())?;
         session.set_tcp_stream(tcp);
@@ -0,0 +1,16 @@
+import axios from "axios";
+import { AddonStatus, ClusterAddon } from "@/api/model";
+
+export namespace addons {
+    export function getAddons(clusterName: string): Promise<AddonStatus[]> {
+        return axios.get(`/api/v1/clusters/${clusterName}/addons`).then(e => e.data);
+    }
+
+    export function enableAddon(clusterName: string, addon: ClusterAddon): Promise<void> {
+        return axios.put(`/api/v1/clusters/${clusterName}/addons/${addon.name}`, addon);
+    }
+
+    export function disableAddon(clusterName: string, addonName: string): Promise<void> {
+        return axios.delete(`/api/v1/clusters/${clusterName}/addons/${addonName}`);
+    }
+}
@@ -0,0 +1,24 @@
+import { BackupSet, BackupSettings, RestoreBackupRequest } from "@/api/model";
+import axios from "axios";
+
+export namespace backups {
+    export function getBackups(clusterName: string): Promise<BackupSet[]> {
+        return axios.get(`/api/v1/clusters/${clusterName}/backups`).then(e => e.data);
+    }
+
+    export function updateSettings(clusterName: string, settings: BackupSettings): Promise<void> {
+        return axios.put(`/api/v1/clusters/${clusterName}/backup/settings`, settings);
+    }
+
+    export function deleteSettings(clusterName: string): Promise<void> {
+        return axios.delete(`/api/v1/clusters/${clusterName}/backup/settings`);
+    }
+
+    export function backupCluster(clusterName: string): Promise<void> {
+        return axios.post(`/api/v1/clusters/${clusterName}/backups`);
+    }
+
+    export function restoreBackup(clusterName: string, request: RestoreBackupRequest): Promise<void> {
+        return axios.post(`/api/v1/clusters/${clusterName}/backups/restore`, request);
+    }
+}
@@ -1,11 +1,22 @@
 import {
+    AvailableKubeVersion,
+    CertificateStatus,
     ChangeNodeResourcesRequest,
     Cluster,
     ClusterHeader,
     ClusterNode,
     ClusterNodeStatus,
     ClusterNodeVmStatus,
-    ClusterRequest, LogEntry
+    ClusterRequest,
+    ClusterSecretsRequest,
+    ExecutionPlan,
+    HardwareProfile,
+    ImportClusterRequest,
+    LogEntry,
+    NodeDiagnostics,
+    NodeMetadataRequest,
+    PreflightReport,
+    RefreshCertificatesRequest
 } from "@/api/model";
 import axios from "axios";
 
@@ -26,25 +37,88 @@ export namespace clusters {
         return axios.delete(`/api/v1/clusters/${name}`).then(e => e.data);
     }
 
+    export function planDeleteCluster(name: string): Promise<ExecutionPlan> {
+        return axios.delete(`/api/v1/clusters/${name}`, {params: {dryRun: true}}).then(e => e.data);
+    }
+
     export function deleteNodeFromCluster(clusterName: string, nodeName: string): Promise<ClusterNode> {
         return axios.delete(`/api/v1/clusters/${clusterName}/nodes/${nodeName}`).then(e => e.data);
     }
 
-    export function changeNodeResources(clusterName: string, nodeName: string, cores: number, memory: number): Promise<void> {
+    export function planDeleteNodeFromCluster(clusterName: string, nodeName: string): Promise<ExecutionPlan> {
+        return axios.delete(`/api/v1/clusters/${clusterName}/nodes/${nodeName}`, {params: {dryRun: true}}).then(e => e.data);
+    }
+
+    export function changeNodeResources(clusterName: string, nodeName: string, cores: number, memory: number, hardware?: HardwareProfile): Promise<void> {
         return axios.put(`/api/v1/clusters/${clusterName}/nodes/${nodeName}/resources`, {
+            cores,
+            memory,
+            hardware
+        } as ChangeNodeResourcesRequest).then(e => e.data);
+    }
+
+    export function preflightChangeNodeResources(clusterName: string, nodeName: string, cores: number, memory: number): Promise<PreflightReport> {
+        return axios.post(`/api/v1/clusters/${clusterName}/nodes/${nodeName}/resources/preflight`, {
             cores,
             memory
         } as ChangeNodeResourcesRequest).then(e => e.data);
     }
 
+    export function updateNodeMetadata(clusterName: string, nodeName: string, labels: Record<string, string>, taints: string[]): Promise<void> {
+        return axios.put(`/api/v1/clusters/${clusterName}/nodes/${nodeName}/metadata`, {
+            labels,
+            taints
+        } as NodeMetadataRequest).then(e => e.data);
+    }
+
+    export function syncNodeMetadata(clusterName: string): Promise<void> {
+        return axios.post(`/api/v1/clusters/${clusterName}/nodes/metadata/sync`).then(e => e.data);
+    }
+
+    export function getNodeDiagnostics(clusterName: string, nodeName: string): Promise<NodeDiagnostics> {
+        return axios.get(`/api/v1/clusters/${clusterName}/nodes/${nodeName}/diagnostics`).then(e => e.data);
+    }
+
+    export function rotateSshKey(clusterName: string): Promise<void> {
+        return axios.post(`/api/v1/clusters/${clusterName}/ssh-key/rotate`).then(e => e.data);
+    }
+
+    export function recoverClusters(): Promise<string[]> {
+        return axios.post("/api/v1/clusters/recover").then(e => e.data);
+    }
+
+    export function importCluster(request: ImportClusterRequest): Promise<void> {
+        return axios.post("/api/v1/clusters/import", request);
+    }
+
+    export function updateClusterSecrets(clusterName: string, request: ClusterSecretsRequest): Promise<void> {
+        return axios.put(`/api/v1/clusters/${clusterName}/secrets`, request).then(e => e.data);
+    }
+
     export function createCluster(request: ClusterRequest): Promise<void> {
         return axios.post("/api/v1/clusters", request);
     }
 
+    export function planCreateCluster(request: ClusterRequest): Promise<ExecutionPlan> {
+        return axios.post("/api/v1/clusters", request, {params: {dryRun: true}}).then(e => e.data);
+    }
+
+    export function preflightCreateCluster(request: ClusterRequest): Promise<PreflightReport> {
+        return axios.post("/api/v1/clusters/preflight", request).then(e => e.data);
+    }
+
+    export function preflightAddNode(clusterName: string, request: ClusterNode): Promise<PreflightReport> {
+        return axios.post(`/api/v1/clusters/${clusterName}/nodes/preflight`, request).then(e => e.data);
+    }
+
     export function addNodeToCluster(clusterName: string, request: ClusterNode): Promise<ClusterNode> {
         return axios.post(`/api/v1/clusters/${clusterName}/nodes`, request).then(e => e.data);
     }
 
+    export function planAddNodeToCluster(clusterName: string, request: ClusterNode): Promise<ExecutionPlan> {
+        return axios.post(`/api/v1/clusters/${clusterName}/nodes`, request, {params: {dryRun: true}}).then(e => e.data);
+    }
+
     export function generateDefaultClusterConfiguration(): Promise<ClusterRequest> {
         return axios.get("/api/v1/clusters/generate").then(e => e.data);
     }
@@ -64,4 +138,18 @@ export namespace clusters {
         return axios.get(`/api/v1/clusters/${clusterName}/status/kube`).then(e => e.data);
     }
 
+    export function clusterCertificatesStatus(clusterName: string): Promise<CertificateStatus[]> {
+        return axios.get(`/api/v1/clusters/${clusterName}/status/certificates`).then(e => e.data);
+    }
+
+    export function discoverKubeVersions(clusterName: string): Promise<AvailableKubeVersion[]> {
+        return axios.get(`/api/v1/clusters/${clusterName}/kube-versions`).then(e => e.data);
+    }
+
+    export function refreshCertificates(clusterName: string, includeCa: boolean): Promise<void> {
+        return axios.post(`/api/v1/clusters/${clusterName}/certificates/refresh`, {
+            includeCa
+        } as RefreshCertificatesRequest).then(e => e.data);
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,20 @@
+import axios from "axios";
+import { IpAllocation, IpPool } from "@/api/model";
+
+export namespace ipPools {
+    export function getIpPools(): Promise<IpPool[]> {
+        return axios.get<IpPool[]>(`/api/v1/ip-pools`).then(e => e.data);
+    }
+
+    export function saveIpPool(pool: IpPool): Promise<void> {
+        return axios.put(`/api/v1/ip-pools/${pool.bridge}`, pool);
+    }
+
+    export function deleteIpPool(bridge: string): Promise<void> {
+        return axios.delete(`/api/v1/ip-pools/${bridge}`);
+    }
+
+    export function getIpAllocations(): Promise<IpAllocation[]> {
+        return axios.get<IpAllocation[]>(`/api/v1/ip-pools/allocations`).then(e => e.data);
+    }
+}
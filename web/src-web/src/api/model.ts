@@ -19,6 +19,39 @@ export interface ClusterResource {
 	content: string;
 }
 
+export enum ScsiController {
+	VirtioScsiPci = "virtioScsiPci",
+	VirtioScsiSingle = "virtioScsiSingle",
+	Lsi = "lsi",
+	Megasas = "megasas",
+	Pvscsi = "pvscsi",
+}
+
+export enum DiskCache {
+	None = "none",
+	Writethrough = "writethrough",
+	Writeback = "writeback",
+	Directsync = "directsync",
+	Unsafe = "unsafe",
+}
+
+export interface HardwareProfile {
+	/** Emulated CPU type, e.g. 'host' or 'x86-64-v2-AES', Proxmox default when empty */
+	cpuType?: string;
+	sockets?: number;
+	numa?: boolean;
+	/** Minimum memory of the balloon device, 0 disables ballooning. Unit: MiB */
+	balloon?: number;
+	/** QEMU machine type, e.g. 'q35' */
+	machine?: string;
+	scsiController?: ScsiController;
+	/** Options of the system disk, they are applied when the VM is created */
+	diskCache?: DiskCache;
+	diskDiscard?: boolean;
+	/** Requires VirtioScsiSingle controller */
+	diskIothread?: boolean;
+}
+
 export enum ClusterNodeType {
 	Master = "master",
 	Worker = "worker",
@@ -30,16 +63,117 @@ export enum ClusterNodeLock {
 	ChangeResources = "changeResources",
 }
 
+export interface NodeNetworkInterface {
+	/** Static address of the interface, required when the interface doesn't use DHCP */
+	ipAddress?: string;
+	bridge?: string;
+	vlanTag?: number;
+	mtu?: number;
+	dhcp?: boolean;
+	/** Overrides Proxmox firewall setting of the interface */
+	firewall?: boolean;
+}
+
 export interface ClusterNode {
 	vmId: number;
 	name: string;
 	cores: number;
 	/** Unit: MiB */
 	memory: number;
+	/** Address Makoon connects to, IPv4 or IPv6 for IPv6-only network */
 	ipAddress: string;
 	storagePool: string;
 	nodeType: ClusterNodeType;
 	lock?: ClusterNodeLock;
+	/** Overrides DHCP mode of the cluster network for this node */
+	dhcp?: boolean;
+	/** Per-node settings of additional interfaces, matched by position with Network.interfaces */
+	interfaces?: NodeNetworkInterface[];
+	/** IPv6 address of dual-stack node, IPv6-only node uses ipAddress instead */
+	ip6Address?: string;
+	hardware?: HardwareProfile;
+	/** Node pool the node belongs to, empty for nodes managed one by one */
+	pool?: string;
+	/** Overrides disk size of the cluster. Unit: GiB */
+	diskSize?: number;
+	/** Kubernetes labels of the node, added to the default and pool labels */
+	labels?: Record<string, string>;
+	/** Kubernetes taints of the node, e.g. 'dedicated=db:NoSchedule' */
+	taints?: string[];
+	/** Kubernetes node name of imported node which isn't named '<cluster>-<node>' */
+	hostName?: string;
+}
+
+export interface NodePool {
+	name: string;
+	nodeType: ClusterNodeType;
+	cores: number;
+	/** Unit: MiB */
+	memory: number;
+	/** Overrides disk size of the cluster. Unit: GiB */
+	diskSize?: number;
+	storagePool: string;
+	/** Kubernetes labels of the pool nodes */
+	labels?: Record<string, string>;
+	/** Kubernetes taints of the pool nodes, e.g. 'dedicated=gpu:NoSchedule' */
+	taints?: string[];
+	desiredCount: number;
+	hardware?: HardwareProfile;
+}
+
+export interface ClusterAddon {
+	/** MicroK8s addon name, e.g. 'metallb' */
+	name: string;
+	/** Arguments passed after ':', e.g. '10.0.0.100-10.0.0.120' for metallb */
+	arguments?: string;
+}
+
+export interface AddonStatus {
+	name: string;
+	repository: string;
+	description: string;
+	enabled: boolean;
+}
+
+export interface AutoscalingSettings {
+	enabled: boolean;
+	/** Worker node pool which is scaled */
+	poolName: string;
+	minNodes: number;
+	maxNodes: number;
+	/** Time a node has to stay idle before it's removed, also the minimum time after the last scale up. Unit: minutes */
+	cooldown: number;
+	/** Node is idle when its CPU and memory requests are below this percentage */
+	scaleDownUtilization: number;
+}
+
+export interface AutoscalingState {
+	lastScaleUp?: Date;
+	lastScaleDown?: Date;
+	/** Nodes of the pool which are idle, with the time of the first idle check */
+	idleSince?: Record<string, Date>;
+	/** Reason why the pool cannot be scaled up, e.g. Proxmox node is out of memory */
+	blockedReason?: string;
+}
+
+export interface NetworkInterface {
+	bridge: string;
+	vlanTag?: number;
+	mtu?: number;
+	firewall?: boolean;
+	/** Nodes get address from DHCP, otherwise address is taken from node interfaces */
+	dhcp?: boolean;
+	subnetMask?: number;
+	gateway?: string;
+}
+
+export enum Ip6Mode {
+	Disabled = "disabled",
+	/** Address is taken from ClusterNode.ip6Address */
+	Static = "static",
+	/** Stateless autoconfiguration (SLAAC) */
+	Auto = "auto",
+	Dhcp = "dhcp",
 }
 
 export interface Network {
@@ -47,6 +181,43 @@ export interface Network {
 	subnetMask: number;
 	dns: string;
 	bridge: string;
+	/** Nodes get address from DHCP, static address, gateway and subnet mask are not used */
+	dhcp?: boolean;
+	vlanTag?: number;
+	mtu?: number;
+	firewall?: boolean;
+	/** Additional interfaces of node VMs, mapped to net1, net2, ... */
+	interfaces?: NetworkInterface[];
+	/** IPv6 configuration of the primary interface when node address is IPv4 (dual-stack) */
+	ip6Mode?: Ip6Mode;
+	ip6Gateway?: string;
+	ip6PrefixLength?: number;
+	/** Additional DNS servers, e.g. IPv6 resolvers */
+	dnsServers?: string[];
+	/** IPv6 pod CIDR, cluster is configured as dual-stack or IPv6-only when set */
+	ip6ClusterCidr?: string;
+	/** IPv6 service CIDR, cluster is configured as dual-stack or IPv6-only when set */
+	ip6ServiceCidr?: string;
+}
+
+export interface IpPool {
+	/** Bridge the pool belongs to, one pool per bridge */
+	bridge: string;
+	/** First address of the range (IPv4) */
+	rangeStart: string;
+	/** Last address of the range (IPv4), inclusive */
+	rangeEnd: string;
+	/** Gateway of the bridge subnet, the range has to be inside of it */
+	gateway?: string;
+	/** Subnet mask of the bridge subnet, e.g. 24 */
+	subnetMask?: number;
+}
+
+export interface IpAllocation {
+	ipAddress: string;
+	bridge: string;
+	clusterName: string;
+	nodeName: string;
 }
 
 export interface KeyPair {
@@ -54,6 +225,50 @@ export interface KeyPair {
 	publicKey: string;
 }
 
+export enum OsFamily {
+	Ubuntu = "ubuntu",
+	Debian = "debian",
+}
+
+export enum ChecksumAlgorithm {
+	Md5 = "md5",
+	Sha1 = "sha1",
+	Sha224 = "sha224",
+	Sha256 = "sha256",
+	Sha384 = "sha384",
+	Sha512 = "sha512",
+}
+
+/** Cloud image which can be used for the cluster nodes. */
+export interface OsImage {
+	name: string;
+	url: string;
+	/** File name on Proxmox storage (.img or .iso), taken from the URL when empty */
+	fileName?: string;
+	osFamily?: OsFamily;
+	/** Expected checksum of the downloaded file */
+	checksum?: string;
+	checksumAlgorithm?: ChecksumAlgorithm;
+	/** File with checksums of the image directory (e.g. SHA256SUMS), used when checksum is empty */
+	checksumUrl?: string;
+	/** Downloaded image older than this is downloaded again, for images published under a moving URL */
+	maxAgeDays?: number;
+}
+
+export interface KubeVersion {
+	/** Snap channel of MicroK8s, release channel of k3s */
+	version: string;
+	/** Version is kept for existing clusters, it shouldn't be used for new ones */
+	deprecated?: boolean;
+}
+
+/** Versions and images offered for new clusters, see [crate::supported::catalog]. */
+export interface Catalog {
+	microk8sVersions?: KubeVersion[];
+	k3sVersions?: KubeVersion[];
+	osImages?: OsImage[];
+}
+
 export enum ActionLogLevel {
 	Info = "info",
 	Error = "error",
@@ -66,15 +281,75 @@ export interface LogEntry {
 	level: ActionLogLevel;
 }
 
+export enum BackupMode {
+	Snapshot = "snapshot",
+	Suspend = "suspend",
+	Stop = "stop",
+}
+
+export interface BackupSettings {
+	/** Proxmox storage with backup content, can be Proxmox Backup Server storage */
+	storage: string;
+	/** Number of backups kept per VM */
+	keepLast: number;
+	/** Proxmox calendar event, e.g. 'daily' or 'sat 02:00', empty for manual backups only */
+	schedule?: string;
+	mode?: BackupMode;
+}
+
+export interface CloudInitSettings {
+	/** Proxmox storage with snippets content, the documents are uploaded there */
+	storage: string;
+	/** cloud-init user-data (#cloud-config, #! script, ...), merged with the user-data of Makoon */
+	userData?: string;
+	/** cloud-init vendor-data, it's overridden by user-data */
+	vendorData?: string;
+}
+
+export interface BackupVolume {
+	vmId: number;
+	volid: string;
+	/** Unit: MiB */
+	size: number;
+}
+
+export interface BackupSet {
+	id: string;
+	notes: string;
+	created: Date;
+	volumes: BackupVolume[];
+	/** Backup set contains every node of the cluster */
+	complete: boolean;
+}
+
+export enum SshKeyType {
+	Ed25519 = "ed25519",
+	Rsa = "rsa",
+}
+
 export enum ClusterStatus {
 	Pending = "pending",
 	Creating = "creating",
 	Sync = "sync",
 	OutOfSync = "outOfSync",
 	Destroying = "destroying",
+	/** Snapshot is being created or the cluster is being rolled back */
+	Snapshotting = "snapshotting",
+	/** SSH key of the nodes is being replaced */
+	RotatingSshKey = "rotatingSshKey",
 	Error = "error",
 }
 
+export enum Distribution {
+	MicroK8s = "microk8s",
+	K3s = "k3s",
+}
+
+export enum ClusterSecret {
+	SshKey = "sshKey",
+	NodePassword = "nodePassword",
+}
+
 export interface Cluster {
 	node: string;
 	clusterName: string;
@@ -83,6 +358,10 @@ export interface Cluster {
 	kubeVersion?: string;
 	clusterConfig: string;
 	sshKey: KeyPair;
+	/** Type of the key pair generated for the cluster, not used when own key pair was provided */
+	sshKeyType?: SshKeyType;
+	/** Additional public keys (OpenSSH format) authorized to log in to the nodes */
+	authorizedKeys?: string[];
 	nodeUsername: string;
 	nodePassword: string;
 	helmApps: HelmApp[];
@@ -92,6 +371,39 @@ export interface Cluster {
 	nodes: ClusterNode[];
 	network: Network;
 	status: ClusterStatus;
+	backup?: BackupSettings;
+	cloudInit?: CloudInitSettings;
+	nodePools?: NodePool[];
+	autoscaling?: AutoscalingSettings;
+	autoscalingState?: AutoscalingState;
+	/** MicroK8s addons enabled by Makoon */
+	addons?: ClusterAddon[];
+	distribution?: Distribution;
+	osFamily?: OsFamily;
+	/** Proxmox pool of the cluster VMs */
+	resourcePool?: string;
+	/** Identifies the cluster in the metadata of its VMs */
+	clusterId?: string;
+	/** Secrets which couldn't be recovered from Proxmox and have to be entered again */
+	missingSecrets?: ClusterSecret[];
+}
+
+export interface ClusterSecretsRequest {
+	sshKey?: KeyPair;
+	nodePassword?: string;
+}
+
+export interface ImportClusterRequest {
+	/** Kubernetes node names have to be <clusterName>-<node name> */
+	clusterName: string;
+	/** Proxmox node of the VMs */
+	node: string;
+	/** VMs of all nodes of the MicroK8s cluster */
+	vmIds: number[];
+	nodeUsername: string;
+	sshKey: KeyPair;
+	/** Password of the user on nodes added by Makoon */
+	nodePassword?: string;
 }
 
 export interface ClusterRequest {
@@ -100,7 +412,11 @@ export interface ClusterRequest {
 	kubeVersion: string;
 	node: string;
 	clusterName: string;
-	sshKey: KeyPair;
+	/** Own key pair for the cluster, a new one is generated when the private key is empty */
+	sshKey?: KeyPair;
+	sshKeyType?: SshKeyType;
+	/** Additional public keys (OpenSSH format) authorized to log in to the nodes */
+	authorizedKeys?: string[];
 	nodeUsername: string;
 	nodePassword: string;
 	helmApps: HelmApp[];
@@ -109,6 +425,13 @@ export interface ClusterRequest {
 	diskSize: number;
 	nodes: ClusterNode[];
 	network: Network;
+	cloudInit?: CloudInitSettings;
+	/** MicroK8s addons enabled at creation, helm3 is always enabled. Not used by other distributions */
+	addons: ClusterAddon[];
+	/** Kubernetes distribution, kubeVersion is a version of its catalog */
+	distribution?: Distribution;
+	/** Proxmox pool for the cluster VMs, created when it doesn't exist */
+	resourcePool?: string;
 }
 
 export interface ClusterHeader {
@@ -133,6 +456,83 @@ export interface ClusterNodeStatus {
 	status: KubeStatus;
 }
 
+export interface NodeDiagnostics {
+	nodeName: string;
+	vmId: number;
+	/** QEMU guest agent responds, the rest of agent data is empty otherwise */
+	agentAvailable: boolean;
+	/** Output of 'cloud-init status --long' */
+	cloudInitStatus?: string;
+	/** Last lines of /var/log/cloud-init-output.log */
+	cloudInitLog?: string;
+	/** Addresses reported by the guest agent, e.g. 'eth0 10.0.0.10/24' */
+	ipAddresses: string[];
+	sshReachable: boolean;
+	errors: string[];
+}
+
+export interface ValidationError {
+	/** Path of the invalid field in the request, e.g. 'nodes[1].vmId' */
+	field: string;
+	message: string;
+}
+
+export enum PreflightSeverity {
+	Ok = "ok",
+	/** Operation can run, e.g. with overcommitted resources */
+	Warning = "warning",
+	/** Operation would fail, it's not started */
+	Error = "error",
+}
+
+export interface PreflightCheck {
+	/** Checked resource, e.g. 'memory' or 'storage local-lvm' */
+	resource: string;
+	severity: PreflightSeverity;
+	message: string;
+}
+
+export interface PreflightReport {
+	/** The most severe result of the checks */
+	status: PreflightSeverity;
+	checks: PreflightCheck[];
+}
+
+export enum PlanActionType {
+	/** Call of the Proxmox API, target is the Proxmox node */
+	ProxmoxApi = "proxmoxApi",
+	/** Command run over SSH, target is the host name of the cluster node */
+	SshCommand = "sshCommand",
+	/** File uploaded over SSH, target is the host name of the cluster node */
+	UploadFile = "uploadFile",
+	/** Makoon waits for the condition before it continues */
+	Wait = "wait",
+}
+
+export interface PlanAction {
+	actionType: PlanActionType;
+	target: string;
+	description: string;
+	/** Command, VM configuration or file content, passwords are hidden */
+	details?: string;
+}
+
+export interface ExecutionPlan {
+	/** Operation of the plan, e.g. 'Create cluster [prod]' */
+	operation: string;
+	/** Actions in the order they are run */
+	actions: PlanAction[];
+}
+
+export interface CertificateStatus {
+	nodeName: string;
+	file: string;
+	/** Empty when the expiry date cannot be read */
+	expires?: Date;
+	/** Reason why certificates of the node cannot be read, e.g. the node is unreachable */
+	error?: string;
+}
+
 export enum AppStatusType {
 	Unknown = "unknown",
 	Deployed = "deployed",
@@ -187,14 +587,58 @@ export interface ChangeNodeResourcesRequest {
 	cores: number;
 	/** Unit: MiB */
 	memory: number;
+	/** New hardware profile of the node, disk options are kept */
+	hardware?: HardwareProfile;
+}
+
+export interface NodeMetadataRequest {
+	/** Kubernetes labels of the node, default and pool labels are added */
+	labels?: Record<string, string>;
+	taints?: string[];
 }
 
 export interface AvailableOsImage {
 	name: string;
 	url: string;
+	osFamily: OsFamily;
 }
 
 export interface AvailableKubeVersion {
 	version: string;
+	/** Version shouldn't be used for new clusters */
+	deprecated: boolean;
+}
+
+export interface CreateClusterSnapshotRequest {
+	name: string;
+	description?: string;
+	/** Freeze guest filesystems through the QEMU guest agent before the snapshot */
+	freezeFs?: boolean;
+}
+
+export interface ClusterSnapshot {
+	name: string;
+	description: string;
+	created?: Date;
+	vmIds: number[];
+	/** Snapshot exists on every node of the cluster */
+	complete: boolean;
+}
+
+export interface RestoreBackupRequest {
+	setId: string;
+	/** Restore only the given node, whole cluster otherwise */
+	nodeName?: string;
+}
+
+export interface RefreshCertificatesRequest {
+	/** Refresh also CA, supported only on cluster with single master node */
+	includeCa?: boolean;
+}
+
+export interface ErrorResponse {
+	message: string;
+	/** Invalid fields of the request, empty for other errors */
+	errors: ValidationError[];
 }
 
@@ -0,0 +1,20 @@
+import axios from "axios";
+import { AutoscalingSettings, NodePool } from "@/api/model";
+
+export namespace nodePools {
+    export function saveNodePool(clusterName: string, pool: NodePool): Promise<void> {
+        return axios.put(`/api/v1/clusters/${clusterName}/node-pools/${pool.name}`, pool);
+    }
+
+    export function deleteNodePool(clusterName: string, poolName: string): Promise<void> {
+        return axios.delete(`/api/v1/clusters/${clusterName}/node-pools/${poolName}`);
+    }
+
+    export function updateAutoscaling(clusterName: string, settings: AutoscalingSettings): Promise<void> {
+        return axios.put(`/api/v1/clusters/${clusterName}/autoscaling`, settings);
+    }
+
+    export function deleteAutoscaling(clusterName: string): Promise<void> {
+        return axios.delete(`/api/v1/clusters/${clusterName}/autoscaling`);
+    }
+}
@@ -0,0 +1,20 @@
+import { ClusterSnapshot, CreateClusterSnapshotRequest } from "@/api/model";
+import axios from "axios";
+
+export namespace snapshots {
+    export function getSnapshots(clusterName: string): Promise<ClusterSnapshot[]> {
+        return axios.get(`/api/v1/clusters/${clusterName}/snapshots`).then(e => e.data);
+    }
+
+    export function createSnapshot(clusterName: string, request: CreateClusterSnapshotRequest): Promise<void> {
+        return axios.post(`/api/v1/clusters/${clusterName}/snapshots`, request);
+    }
+
+    export function rollbackSnapshot(clusterName: string, snapshotName: string): Promise<void> {
+        return axios.post(`/api/v1/clusters/${clusterName}/snapshots/${snapshotName}/rollback`);
+    }
+
+    export function deleteSnapshot(clusterName: string, snapshotName: string): Promise<void> {
+        return axios.delete(`/api/v1/clusters/${clusterName}/snapshots/${snapshotName}`);
+    }
+}
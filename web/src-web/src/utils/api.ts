@@ -1,6 +1,15 @@
 import {wrapWithProcessingIndicator} from "@/store/processing-indicator-store";
 import {AxiosError} from "axios";
 import applicationStore from "@/store/application-store";
+import {ErrorResponse} from "@/api/model";
+
+function errorMessage(e: any): string {
+    const response = e instanceof AxiosError ? e.response?.data as ErrorResponse | undefined : undefined
+    if (response?.errors?.length) {
+        return `${response.message}: ${response.errors.map(i => `${i.field} - ${i.message}`).join(", ")}`
+    }
+    return response?.message ?? e?.message ?? String(e)
+}
 
 
 export async function apiCall<T>(fn: () => Promise<T>, callKey: string = Math.random().toString(16)): Promise<T> {
@@ -13,7 +22,7 @@ export async function apiCall<T>(fn: () => Promise<T>, callKey: string = Math.ra
             } else {
                 console.error(e)
             }
-            applicationStore.throwError(e)
+            applicationStore.throwError(errorMessage(e))
             throw e
         }
     })
@@ -156,6 +156,8 @@ const SettingsStep = (props: StepProps, ref: any) => {
                                               onChange={formik.handleChange}
                                               optionValue={"version"}
                                               optionLabel={"version"}
+                                              itemTemplate={(option: AvailableKubeVersion) =>
+                                                  option.deprecated ? `${option.version} (deprecated)` : option.version}
                                               options={kubeVersions}/>
 
                                     <FormError error={formik.errors.kubeVersion} touched={formik.touched.kubeVersion}/>
@@ -17,6 +17,10 @@ const ClusterStatus = (props: Props) => {
             return <i className={`pi pi-exclamation-circle text-unavailable ${props.className ?? ""}`} title="Out of sync"></i>
         case ClusterStatusValue.Destroying:
             return <i className={`pi pi-trash text-warning ${props.className ?? ""}`} title="Destroying"></i>
+        case ClusterStatusValue.Snapshotting:
+            return <i className={`pi pi-camera text-warning ${props.className ?? ""}`} title="Snapshotting"></i>
+        case ClusterStatusValue.RotatingSshKey:
+            return <i className={`pi pi-key text-warning ${props.className ?? ""}`} title="Rotating SSH key"></i>
         case ClusterStatusValue.Error:
             return <i className={`pi pi-times-circle text-danger ${props.className ?? ""}`} title="Error"></i>
     }
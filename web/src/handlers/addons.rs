@@ -0,0 +1,52 @@
+use actix_session::Session;
+use actix_web::{delete, get, put, web, HttpResponse, Responder};
+use core::model::ClusterAddon;
+
+use crate::handlers::actix::inject;
+use crate::handlers::error::HandlerError;
+use crate::logged_in;
+
+#[get("/api/v1/clusters/{cluster_name}/addons")]
+pub async fn addons_status(
+    path: web::Path<String>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    let cluster_name = path.into_inner();
+
+    let result = web::block(move || operator.addons_status(&cluster_name)).await??;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[put("/api/v1/clusters/{cluster_name}/addons/{addon_name}")]
+pub async fn enable_addon(
+    path: web::Path<(String, String)>,
+    body: web::Json<ClusterAddon>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    let (cluster_name, addon_name) = path.into_inner();
+    let mut addon = body.into_inner();
+    addon.name = addon_name;
+
+    operator.enable_addon(cluster_name, addon)?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[delete("/api/v1/clusters/{cluster_name}/addons/{addon_name}")]
+pub async fn disable_addon(
+    path: web::Path<(String, String)>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    let (cluster_name, addon_name) = path.into_inner();
+
+    operator.disable_addon(cluster_name, addon_name)?;
+    Ok(HttpResponse::Accepted().finish())
+}
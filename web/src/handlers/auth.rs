@@ -5,7 +5,7 @@ use proxmox_client::model::AccessData;
 use proxmox_client::{model::LoginRequest as ProxmoxLoginRequest, Client};
 
 use crate::handlers::actix;
-use crate::handlers::actix::{get_session, WebSession};
+use crate::handlers::actix::{get_session, inject, WebSession};
 use crate::handlers::error::HandlerError;
 use crate::handlers::model::LoginRequest;
 
@@ -38,7 +38,12 @@ pub async fn login(
 }
 
 #[post("/api/v1/logout")]
-pub async fn logout(session: Session) -> impl Responder {
+pub async fn logout(session: Session, operator: inject::Operator) -> impl Responder {
+    if let Some(web_session) = get_session(&session) {
+        if let Err(e) = operator.revoke_autoscaler_access(&web_session.access) {
+            error!("Cannot revoke autoscaler access: {}", e);
+        }
+    }
     session.purge();
     HttpResponse::Ok().finish()
 }
@@ -0,0 +1,99 @@
+use actix_session::Session;
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use core::model::{BackupSet, BackupSettings};
+
+use crate::handlers::actix::inject;
+use crate::handlers::error::HandlerError;
+use crate::handlers::model::RestoreBackupRequest;
+use crate::logged_in;
+
+#[put("/api/v1/clusters/{name}/backup/settings")]
+pub async fn update_backup_settings(
+    path: web::Path<String>,
+    body: web::Json<BackupSettings>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let name = path.into_inner();
+
+    operator.update_backup_settings(access, name, Some(body.into_inner()))?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[delete("/api/v1/clusters/{name}/backup/settings")]
+pub async fn delete_backup_settings(
+    path: web::Path<String>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let name = path.into_inner();
+
+    operator.update_backup_settings(access, name, None)?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[get("/api/v1/clusters/{name}/backups")]
+pub async fn backups(
+    path: web::Path<String>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let name = path.into_inner();
+
+    let cluster = web::block(move || {
+        let result = operator.get_cluster(&name)?;
+        Ok::<Option<core::model::Cluster>, HandlerError>(result)
+    })
+        .await??
+        .ok_or(HandlerError::NotFound("Cluster not found".to_string()))?;
+
+    let settings = match &cluster.backup {
+        Some(v) => v.clone(),
+        None => return Ok(HttpResponse::Ok().json(Vec::<BackupSet>::new())),
+    };
+
+    let result = web::block(move || {
+        let proxmox_client = proxmox_client.operations(access);
+        let volumes = proxmox_client.backups(&cluster.node, &settings.storage, None)?;
+        Ok::<Vec<BackupSet>, HandlerError>(core::backup::backup_sets(&cluster, volumes))
+    })
+        .await??;
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[post("/api/v1/clusters/{name}/backups")]
+pub async fn backup_cluster(
+    path: web::Path<String>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let name = path.into_inner();
+
+    operator.backup_cluster(access, name)?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[post("/api/v1/clusters/{name}/backups/restore")]
+pub async fn restore_backup(
+    path: web::Path<String>,
+    body: web::Json<RestoreBackupRequest>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let name = path.into_inner();
+    let body = body.into_inner();
+
+    operator.restore_cluster_backup(access, name, body.set_id, body.node_name)?;
+    Ok(HttpResponse::Accepted().finish())
+}
@@ -5,7 +5,7 @@ use proxmox_client::model::VirtualMachine;
 
 use crate::handlers::actix::inject;
 use crate::handlers::error::HandlerError;
-use crate::handlers::model::{ChangeNodeResourcesRequest, ClusterNodeVmStatus};
+use crate::handlers::model::{AvailableKubeVersion, ChangeNodeResourcesRequest, ClusterNodeVmStatus, DryRunQuery, NodeMetadataRequest, RefreshCertificatesRequest};
 use crate::logged_in;
 
 #[get("/api/v1/clusters/{cluster_name}/nodes")]
@@ -28,6 +28,7 @@ pub async fn get_nodes(
 pub async fn add_node_to_cluster(
     body: web::Json<core::model::ClusterNode>,
     path: web::Path<String>,
+    query: web::Query<DryRunQuery>,
     session: Session,
     operator: inject::Operator,
     proxmox_client: inject::ProxmoxClient,
@@ -35,10 +36,51 @@ pub async fn add_node_to_cluster(
     let access = logged_in!(session, proxmox_client);
     let cluster_name = path.into_inner();
 
-    let added_node = operator.add_node_cluster(access, cluster_name, body.0)?;
+    if query.dry_run {
+        let plan = web::block(move || {
+            operator.plan_add_node_cluster(
+                &proxmox_client.operations(access),
+                &cluster_name,
+                body.0,
+            )
+        })
+        .await??;
+        return Ok(HttpResponse::Ok().json(plan));
+    }
+    let added_node = web::block(move || {
+        operator.add_node_cluster(
+            &proxmox_client.operations(access.clone()),
+            access,
+            cluster_name,
+            body.0,
+        )
+    })
+    .await??;
     Ok(HttpResponse::Created().json(added_node))
 }
 
+#[post("/api/v1/clusters/{cluster_name}/nodes/preflight")]
+pub async fn preflight_add_node(
+    body: web::Json<core::model::ClusterNode>,
+    path: web::Path<String>,
+    session: Session,
+    operator: inject::Operator,
+    proxmox_client: inject::ProxmoxClient,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let cluster_name = path.into_inner();
+
+    let result = web::block(move || {
+        operator.preflight_add_nodes(
+            &proxmox_client.operations(access),
+            &cluster_name,
+            std::slice::from_ref(&body.0),
+        )
+    })
+    .await??;
+    Ok(HttpResponse::Ok().json(result))
+}
+
 #[put("/api/v1/clusters/{cluster_name}/nodes/{node_name}/resources")]
 pub async fn change_node_resources(
     body: web::Json<ChangeNodeResourcesRequest>,
@@ -49,23 +91,113 @@ pub async fn change_node_resources(
 ) -> actix_web::Result<impl Responder, HandlerError> {
     let access = logged_in!(session, proxmox_client);
     let (cluster_name, node_name) = path.into_inner();
-    operator.change_node_resources(access, cluster_name, node_name, body.cores, body.memory)?;
+    let body = body.into_inner();
+    web::block(move || {
+        operator.change_node_resources(
+            proxmox_client.operations(access.clone()),
+            access,
+            cluster_name,
+            node_name,
+            body.cores,
+            body.memory,
+            body.hardware,
+        )
+    })
+    .await??;
+    Ok(HttpResponse::Accepted())
+}
+
+#[post("/api/v1/clusters/{cluster_name}/nodes/{node_name}/resources/preflight")]
+pub async fn preflight_change_node_resources(
+    body: web::Json<ChangeNodeResourcesRequest>,
+    path: web::Path<(String, String)>,
+    session: Session,
+    operator: inject::Operator,
+    proxmox_client: inject::ProxmoxClient,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let (cluster_name, node_name) = path.into_inner();
+
+    let result = web::block(move || {
+        operator.preflight_change_node_resources(
+            &proxmox_client.operations(access),
+            &cluster_name,
+            &node_name,
+            body.cores,
+            body.memory,
+        )
+    })
+    .await??;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[put("/api/v1/clusters/{cluster_name}/nodes/{node_name}/metadata")]
+pub async fn update_node_metadata(
+    body: web::Json<NodeMetadataRequest>,
+    path: web::Path<(String, String)>,
+    session: Session,
+    operator: inject::Operator,
+    proxmox_client: inject::ProxmoxClient,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    let (cluster_name, node_name) = path.into_inner();
+    let body = body.into_inner();
+    operator.update_node_metadata(cluster_name, node_name, body.labels, body.taints)?;
+    Ok(HttpResponse::Accepted())
+}
+
+#[post("/api/v1/clusters/{cluster_name}/nodes/metadata/sync")]
+pub async fn sync_node_metadata(
+    path: web::Path<String>,
+    session: Session,
+    operator: inject::Operator,
+    proxmox_client: inject::ProxmoxClient,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    operator.sync_node_metadata(path.into_inner())?;
     Ok(HttpResponse::Accepted())
 }
 
 #[post("/api/v1/clusters")]
 pub async fn create_cluster(
     body: web::Json<core::model::ClusterRequest>,
+    query: web::Query<DryRunQuery>,
     session: Session,
     operator: inject::Operator,
     proxmox_client: inject::ProxmoxClient,
 ) -> actix_web::Result<impl Responder, HandlerError> {
     let access = logged_in!(session, proxmox_client);
 
-    operator.create_cluster(access, body.0)?;
+    if query.dry_run {
+        let plan = web::block(move || {
+            operator.plan_create_cluster(&proxmox_client.operations(access), body.0)
+        })
+        .await??;
+        return Ok(HttpResponse::Ok().json(plan));
+    }
+    web::block(move || {
+        operator.create_cluster(proxmox_client.operations(access.clone()), access, body.0)
+    })
+    .await??;
     Ok(HttpResponse::Created().finish())
 }
 
+#[post("/api/v1/clusters/preflight")]
+pub async fn preflight_create_cluster(
+    body: web::Json<core::model::ClusterRequest>,
+    session: Session,
+    operator: inject::Operator,
+    proxmox_client: inject::ProxmoxClient,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+
+    let result = web::block(move || {
+        operator.preflight_create_cluster(&proxmox_client.operations(access), &body.0)
+    })
+    .await??;
+    Ok(HttpResponse::Ok().json(result))
+}
+
 #[get("/api/v1/clusters")]
 pub async fn get_clusters(
     session: Session,
@@ -98,6 +230,7 @@ pub async fn get_cluster(
 #[delete("/api/v1/clusters/{name}")]
 pub async fn delete_cluster(
     path: web::Path<String>,
+    query: web::Query<DryRunQuery>,
     session: Session,
     operator: inject::Operator,
     proxmox_client: inject::ProxmoxClient,
@@ -105,13 +238,73 @@ pub async fn delete_cluster(
     let access = logged_in!(session, proxmox_client);
     let name = path.into_inner();
 
+    if query.dry_run {
+        return Ok(HttpResponse::Ok().json(operator.plan_delete_cluster(&name)?));
+    }
     operator.delete_cluster(access, name)?;
     Ok(HttpResponse::Ok().finish())
 }
 
+#[post("/api/v1/clusters/{name}/ssh-key/rotate")]
+pub async fn rotate_ssh_key(
+    path: web::Path<String>,
+    session: Session,
+    operator: inject::Operator,
+    proxmox_client: inject::ProxmoxClient,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let name = path.into_inner();
+
+    operator.rotate_ssh_key(access, name)?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[post("/api/v1/clusters/recover")]
+pub async fn recover_clusters(
+    session: Session,
+    operator: inject::Operator,
+    proxmox_client: inject::ProxmoxClient,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+
+    let result =
+        web::block(move || operator.recover_clusters(proxmox_client.operations(access))).await??;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[post("/api/v1/clusters/import")]
+pub async fn import_cluster(
+    body: web::Json<core::model::ImportClusterRequest>,
+    session: Session,
+    operator: inject::Operator,
+    proxmox_client: inject::ProxmoxClient,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+
+    web::block(move || operator.import_cluster(proxmox_client.operations(access), body.0))
+        .await??;
+    Ok(HttpResponse::Created().finish())
+}
+
+#[put("/api/v1/clusters/{name}/secrets")]
+pub async fn update_cluster_secrets(
+    path: web::Path<String>,
+    body: web::Json<core::model::ClusterSecretsRequest>,
+    session: Session,
+    operator: inject::Operator,
+    proxmox_client: inject::ProxmoxClient,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    let name = path.into_inner();
+
+    operator.update_cluster_secrets(&name, body.0)?;
+    Ok(HttpResponse::Ok().finish())
+}
+
 #[delete("/api/v1/clusters/{cluster_name}/nodes/{node_name}")]
 pub async fn delete_node_from_cluster(
     path: web::Path<(String, String)>,
+    query: web::Query<DryRunQuery>,
     session: Session,
     operator: inject::Operator,
     proxmox_client: inject::ProxmoxClient,
@@ -119,6 +312,10 @@ pub async fn delete_node_from_cluster(
     let access = logged_in!(session, proxmox_client);
     let (cluster_name, node_name) = path.into_inner();
 
+    if query.dry_run {
+        let plan = operator.plan_delete_node_from_cluster(&cluster_name, &node_name)?;
+        return Ok(HttpResponse::Ok().json(plan));
+    }
     let deleted_node = operator.delete_node_from_cluster(access, cluster_name, node_name)?;
     Ok(HttpResponse::Ok().json(deleted_node))
 }
@@ -127,13 +324,13 @@ pub async fn delete_node_from_cluster(
 pub async fn generate_default_cluster_configuration(
     session: Session,
     proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
 ) -> actix_web::Result<impl Responder, HandlerError> {
     let access = logged_in!(session, proxmox_client);
 
     let result = web::block(move || {
-        let generator =
-            core::DefaultClusterConfigurationGenerator::new(proxmox_client.operations(access));
-        Ok::<core::model::ClusterRequest, HandlerError>(generator.generate()?)
+        let result = operator.generate_cluster_configuration(proxmox_client.operations(access))?;
+        Ok::<core::model::ClusterRequest, HandlerError>(result)
     })
         .await??;
 
@@ -231,3 +428,70 @@ pub async fn cluster_kube_status(
 
     Ok(HttpResponse::Ok().json(result))
 }
+
+#[get("/api/v1/clusters/{name}/status/certificates")]
+pub async fn cluster_certificates_status(
+    path: web::Path<String>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    let name = path.into_inner();
+
+    let result = web::block(move || operator.certificates_status(&name)).await??;
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[get("/api/v1/clusters/{name}/kube-versions")]
+pub async fn discover_kube_versions(
+    path: web::Path<String>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    let name = path.into_inner();
+
+    let result: Vec<AvailableKubeVersion> = web::block(move || operator.discover_kube_versions(&name))
+        .await??
+        .iter()
+        .map(AvailableKubeVersion::new)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[get("/api/v1/clusters/{cluster_name}/nodes/{node_name}/diagnostics")]
+pub async fn node_diagnostics(
+    path: web::Path<(String, String)>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let (cluster_name, node_name) = path.into_inner();
+
+    let result = web::block(move || {
+        operator.node_diagnostics(proxmox_client.operations(access), &cluster_name, &node_name)
+    })
+    .await??;
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[post("/api/v1/clusters/{name}/certificates/refresh")]
+pub async fn refresh_certificates(
+    path: web::Path<String>,
+    body: web::Json<RefreshCertificatesRequest>,
+    session: Session,
+    operator: inject::Operator,
+    proxmox_client: inject::ProxmoxClient,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    let name = path.into_inner();
+
+    operator.refresh_certificates(name, body.include_ca)?;
+    Ok(HttpResponse::Accepted().finish())
+}
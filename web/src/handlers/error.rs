@@ -3,11 +3,15 @@ use std::sync::PoisonError;
 
 use actix_web::error::BlockingError;
 use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use core::model::ValidationError;
 use serde::{Deserialize, Serialize};
 
+use crate::handlers::model::ErrorResponse;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub enum HandlerError {
-    BadRequest(String),
+    BadRequest(String, Vec<ValidationError>),
     NotFound(String),
     UnAuthorized,
     InternalServerError(String),
@@ -19,7 +23,7 @@ impl Display for HandlerError {
             HandlerError::UnAuthorized => write!(f, ""),
             HandlerError::NotFound(e) => write!(f, "{}", e),
             HandlerError::InternalServerError(e) => write!(f, "{}", e),
-            HandlerError::BadRequest(e) => write!(f, "{}", e),
+            HandlerError::BadRequest(e, _) => write!(f, "{}", e),
         }
     }
 }
@@ -30,7 +34,21 @@ impl actix_web::error::ResponseError for HandlerError {
             HandlerError::UnAuthorized => StatusCode::UNAUTHORIZED,
             HandlerError::NotFound(_) => StatusCode::NOT_FOUND,
             HandlerError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            HandlerError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            HandlerError::BadRequest(_, _) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            HandlerError::BadRequest(message, errors) => {
+                HttpResponse::BadRequest().json(ErrorResponse {
+                    message: message.clone(),
+                    errors: errors.clone(),
+                })
+            }
+            _ => HttpResponse::build(self.status_code())
+                .content_type("text/plain; charset=utf-8")
+                .body(self.to_string()),
         }
     }
 }
@@ -69,7 +87,10 @@ impl From<core::Error> for HandlerError {
     fn from(value: core::Error) -> Self {
         match value {
             core::Error::ResourceAlreadyExists => {
-                HandlerError::BadRequest("Cluster already exists".to_string())
+                HandlerError::BadRequest("Cluster already exists".to_string(), vec![])
+            }
+            core::Error::Validation(errors) => {
+                HandlerError::BadRequest("Request is invalid".to_string(), errors)
             }
             core::Error::Generic(e) => HandlerError::InternalServerError(e),
             core::Error::ResourceNotFound => {
@@ -0,0 +1,61 @@
+use actix_session::Session;
+use actix_web::{delete, get, put, web, HttpResponse, Responder};
+use core::model::IpPool;
+
+use crate::handlers::actix::inject;
+use crate::handlers::error::HandlerError;
+use crate::logged_in;
+
+#[get("/api/v1/ip-pools")]
+pub async fn ip_pools(
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+
+    let result = web::block(move || operator.ip_pools()).await??;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[put("/api/v1/ip-pools/{bridge}")]
+pub async fn save_ip_pool(
+    path: web::Path<String>,
+    body: web::Json<IpPool>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    let mut pool = body.into_inner();
+    pool.bridge = path.into_inner();
+
+    web::block(move || operator.save_ip_pool(pool)).await??;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[delete("/api/v1/ip-pools/{bridge}")]
+pub async fn delete_ip_pool(
+    path: web::Path<String>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    let bridge = path.into_inner();
+
+    web::block(move || operator.delete_ip_pool(&bridge)).await??;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[get("/api/v1/ip-pools/allocations")]
+pub async fn ip_allocations(
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+
+    let result = web::block(move || operator.ip_allocations()).await??;
+    Ok(HttpResponse::Ok().json(result))
+}
@@ -1,12 +1,17 @@
 pub mod actix;
+pub mod addons;
 pub mod apps;
 pub mod auth;
+pub mod backups;
 pub mod cluster;
 pub mod cluster_resources;
 pub mod error;
 pub mod export;
+pub mod ip_pools;
 pub mod model;
 pub mod network;
+pub mod node_pools;
 pub mod nodes;
+pub mod snapshots;
 pub mod storage;
 pub mod settings;
\ No newline at end of file
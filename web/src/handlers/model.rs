@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 
@@ -67,6 +70,20 @@ pub struct ChangeNodeResourcesRequest {
     pub cores: u16,
     #[doc = "Unit: MiB"]
     pub memory: u32,
+    #[doc = "New hardware profile of the node, disk options are kept"]
+    #[serde(default)]
+    pub hardware: Option<core::model::HardwareProfile>,
+}
+
+#[typeshare]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeMetadataRequest {
+    #[doc = "Kubernetes labels of the node, default and pool labels are added"]
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub taints: Vec<String>,
 }
 
 #[typeshare]
@@ -75,13 +92,15 @@ pub struct ChangeNodeResourcesRequest {
 pub struct AvailableOsImage {
     pub name: String,
     pub url: String,
+    pub os_family: core::model::OsFamily,
 }
 
 impl AvailableOsImage {
-    pub fn new(name: &str, url: &str) -> Self {
+    pub fn new(image: &core::model::OsImage) -> Self {
         AvailableOsImage {
-            name: name.to_string(),
-            url: url.to_string(),
+            name: image.name.clone(),
+            url: image.url.clone(),
+            os_family: image.os_family.clone(),
         }
     }
 }
@@ -91,12 +110,82 @@ impl AvailableOsImage {
 #[serde(rename_all = "camelCase")]
 pub struct AvailableKubeVersion {
     pub version: String,
+    #[doc = "Version shouldn't be used for new clusters"]
+    pub deprecated: bool,
 }
 
 impl AvailableKubeVersion {
-    pub fn new(version: &str) -> Self {
+    pub fn new(version: &core::model::KubeVersion) -> Self {
         AvailableKubeVersion {
-            version: version.to_string()
+            version: version.version.clone(),
+            deprecated: version.deprecated,
         }
     }
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize, Debug)]
+pub struct KubeVersionsQuery {
+    #[serde(default)]
+    pub distribution: core::model::Distribution,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunQuery {
+    #[doc = "Returns the execution plan instead of running the operation"]
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[typeshare]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateClusterSnapshotRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[doc = "Freeze guest filesystems through the QEMU guest agent before the snapshot"]
+    #[serde(default)]
+    pub freeze_fs: bool,
+}
+
+#[typeshare]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterSnapshot {
+    pub name: String,
+    pub description: String,
+    pub created: Option<NaiveDateTime>,
+    pub vm_ids: Vec<u32>,
+    #[doc = "Snapshot exists on every node of the cluster"]
+    pub complete: bool,
+}
+
+
+#[typeshare]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreBackupRequest {
+    pub set_id: String,
+    #[doc = "Restore only the given node, whole cluster otherwise"]
+    #[serde(default)]
+    pub node_name: Option<String>,
+}
+
+#[typeshare]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshCertificatesRequest {
+    #[doc = "Refresh also CA, supported only on cluster with single master node"]
+    #[serde(default)]
+    pub include_ca: bool,
+}
+
+#[typeshare]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorResponse {
+    pub message: String,
+    #[doc = "Invalid fields of the request, empty for other errors"]
+    pub errors: Vec<core::model::ValidationError>,
+}
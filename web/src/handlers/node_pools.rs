@@ -0,0 +1,76 @@
+use actix_session::Session;
+use actix_web::{delete, put, web, HttpResponse, Responder};
+use core::model::{AutoscalingSettings, NodePool};
+
+use crate::handlers::actix::inject;
+use crate::handlers::error::HandlerError;
+use crate::logged_in;
+
+#[put("/api/v1/clusters/{cluster_name}/node-pools/{pool_name}")]
+pub async fn save_node_pool(
+    path: web::Path<(String, String)>,
+    body: web::Json<NodePool>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let (cluster_name, pool_name) = path.into_inner();
+    let mut pool = body.into_inner();
+    pool.name = pool_name;
+
+    web::block(move || {
+        operator.save_node_pool(
+            proxmox_client.operations(access.clone()),
+            access,
+            cluster_name,
+            pool,
+        )
+    })
+    .await??;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[delete("/api/v1/clusters/{cluster_name}/node-pools/{pool_name}")]
+pub async fn delete_node_pool(
+    path: web::Path<(String, String)>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    let (cluster_name, pool_name) = path.into_inner();
+
+    web::block(move || operator.delete_node_pool(&cluster_name, &pool_name)).await??;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[put("/api/v1/clusters/{cluster_name}/autoscaling")]
+pub async fn update_autoscaling(
+    path: web::Path<String>,
+    body: web::Json<AutoscalingSettings>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let cluster_name = path.into_inner();
+
+    web::block(move || operator.update_autoscaling(access, &cluster_name, body.into_inner()))
+        .await??;
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[delete("/api/v1/clusters/{cluster_name}/autoscaling")]
+pub async fn delete_autoscaling(
+    path: web::Path<String>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let _ = logged_in!(session, proxmox_client);
+    let cluster_name = path.into_inner();
+
+    web::block(move || operator.delete_autoscaling(&cluster_name)).await??;
+    Ok(HttpResponse::Ok().finish())
+}
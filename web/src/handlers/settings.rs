@@ -1,21 +1,22 @@
-use actix_web::{get, HttpResponse, Responder};
+use actix_web::{get, web, HttpResponse, Responder};
 use crate::handlers::error::HandlerError;
 use crate::handlers::model::AvailableKubeVersion;
 use crate::handlers::model::AvailableOsImage;
+use crate::handlers::model::KubeVersionsQuery;
 
 #[get("/api/v1/os-images")]
 pub async fn os_images() -> actix_web::Result<impl Responder, HandlerError> {
     let result: Vec<AvailableOsImage> = core::supported::os_images().iter()
-        .map(|(k, v)| AvailableOsImage::new(k, v))
+        .map(AvailableOsImage::new)
         .collect();
 
     Ok(HttpResponse::Ok().json(result))
 }
 
 #[get("/api/v1/kube-versions")]
-pub async fn kube_versions() -> actix_web::Result<impl Responder, HandlerError> {
-    let result: Vec<AvailableKubeVersion> = core::supported::kube_versions().iter()
-        .map(|e| AvailableKubeVersion::new(e))
+pub async fn kube_versions(query: web::Query<KubeVersionsQuery>) -> actix_web::Result<impl Responder, HandlerError> {
+    let result: Vec<AvailableKubeVersion> = core::supported::kube_versions(&query.distribution).iter()
+        .map(AvailableKubeVersion::new)
         .collect();
 
     Ok(HttpResponse::Ok().json(result))
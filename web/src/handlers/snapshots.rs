@@ -0,0 +1,104 @@
+use actix_session::Session;
+use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use chrono::DateTime;
+
+use crate::handlers::actix::inject;
+use crate::handlers::error::HandlerError;
+use crate::handlers::model::{ClusterSnapshot, CreateClusterSnapshotRequest};
+use crate::logged_in;
+
+#[get("/api/v1/clusters/{name}/snapshots")]
+pub async fn snapshots(
+    path: web::Path<String>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let name = path.into_inner();
+
+    let cluster = web::block(move || {
+        let result = operator.get_cluster(&name)?;
+        Ok::<Option<core::model::Cluster>, HandlerError>(result)
+    })
+        .await??
+        .ok_or(HandlerError::NotFound("Cluster not found".to_string()))?;
+
+    let result = web::block(move || {
+        let proxmox_client = proxmox_client.operations(access);
+        let mut result: Vec<ClusterSnapshot> = Vec::new();
+        for node in cluster.nodes.iter() {
+            for snapshot in proxmox_client.snapshots(&cluster.node, node.vm_id)? {
+                // Proxmox lists the live state of the VM as a "current" pseudo snapshot
+                if snapshot.name == "current" {
+                    continue;
+                }
+                match result.iter_mut().find(|i| i.name == snapshot.name) {
+                    Some(v) => v.vm_ids.push(node.vm_id),
+                    None => result.push(ClusterSnapshot {
+                        name: snapshot.name,
+                        description: snapshot.description.trim().to_string(),
+                        created: snapshot
+                            .snap_time
+                            .and_then(|i| DateTime::from_timestamp(i as i64, 0))
+                            .map(|i| i.naive_utc()),
+                        vm_ids: vec![node.vm_id],
+                        complete: false,
+                    }),
+                }
+            }
+        }
+        for snapshot in result.iter_mut() {
+            snapshot.complete = snapshot.vm_ids.len() == cluster.nodes.len();
+        }
+        result.sort_by_key(|i| std::cmp::Reverse(i.created));
+        Ok::<Vec<ClusterSnapshot>, HandlerError>(result)
+    })
+        .await??;
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[post("/api/v1/clusters/{name}/snapshots")]
+pub async fn create_snapshot(
+    path: web::Path<String>,
+    body: web::Json<CreateClusterSnapshotRequest>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let name = path.into_inner();
+    let body = body.into_inner();
+
+    operator.create_cluster_snapshot(access, name, body.name, body.description, body.freeze_fs)?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[post("/api/v1/clusters/{name}/snapshots/{snapshot_name}/rollback")]
+pub async fn rollback_snapshot(
+    path: web::Path<(String, String)>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let (name, snapshot_name) = path.into_inner();
+
+    operator.rollback_cluster_snapshot(access, name, snapshot_name)?;
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[delete("/api/v1/clusters/{name}/snapshots/{snapshot_name}")]
+pub async fn delete_snapshot(
+    path: web::Path<(String, String)>,
+    session: Session,
+    proxmox_client: inject::ProxmoxClient,
+    operator: inject::Operator,
+) -> actix_web::Result<impl Responder, HandlerError> {
+    let access = logged_in!(session, proxmox_client);
+    let (name, snapshot_name) = path.into_inner();
+
+    operator.delete_cluster_snapshot(access, name, snapshot_name)?;
+    Ok(HttpResponse::Accepted().finish())
+}
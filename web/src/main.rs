@@ -12,6 +12,7 @@ use actix_web::middleware::Logger;
 use actix_web::{get, web, App, HttpResponse, HttpServer, Responder};
 use env_logger::Env;
 use mime_guess::from_path;
+use proxmox_client::model::AccessData;
 use rust_embed::RustEmbed;
 
 use crate::handlers::actix::inject;
@@ -54,8 +55,31 @@ async fn main() -> std::io::Result<()> {
             .map_err(|_| std::io::Error::from(ErrorKind::InvalidData))?,
     );
 
+    // API token lets autoscaler work without tickets of logged users, e.g. after restart
+    let autoscaler_access = match (
+        env::var("MAKOON_PROXMOX_HOST"),
+        env::var("MAKOON_PROXMOX_API_TOKEN"),
+    ) {
+        (Ok(host), Ok(api_token)) => {
+            let port: u16 = env::var("MAKOON_PROXMOX_PORT")
+                .unwrap_or("8006".to_string())
+                .parse()
+                .map_err(|_| std::io::Error::from(ErrorKind::InvalidInput))?;
+            Some(AccessData::from_api_token(
+                host,
+                port,
+                "/api2/json".to_string(),
+                api_token,
+            ))
+        }
+        _ => None,
+    };
+
     let operator = core::Operator::new(
-        core::Config::default(),
+        core::Config {
+            autoscaler_access,
+            ..Default::default()
+        },
         core::Dispatcher::new(proxmox_client.clone(), repo.clone()),
         repo.clone(),
     );
@@ -84,14 +108,34 @@ async fn main() -> std::io::Result<()> {
             .service(handlers::cluster::get_cluster)
             .service(handlers::cluster::get_nodes)
             .service(handlers::cluster::create_cluster)
+            .service(handlers::cluster::preflight_create_cluster)
             .service(handlers::cluster::delete_cluster)
             .service(handlers::cluster::logs_for_cluster)
             .service(handlers::cluster::clear_logs_for_cluster)
             .service(handlers::cluster::cluster_vm_status)
             .service(handlers::cluster::cluster_kube_status)
+            .service(handlers::cluster::cluster_certificates_status)
+            .service(handlers::cluster::refresh_certificates)
+            .service(handlers::cluster::discover_kube_versions)
             .service(handlers::cluster::add_node_to_cluster)
+            .service(handlers::cluster::preflight_add_node)
             .service(handlers::cluster::delete_node_from_cluster)
             .service(handlers::cluster::change_node_resources)
+            .service(handlers::cluster::preflight_change_node_resources)
+            .service(handlers::cluster::update_node_metadata)
+            .service(handlers::cluster::sync_node_metadata)
+            .service(handlers::cluster::node_diagnostics)
+            .service(handlers::node_pools::save_node_pool)
+            .service(handlers::node_pools::delete_node_pool)
+            .service(handlers::node_pools::update_autoscaling)
+            .service(handlers::node_pools::delete_autoscaling)
+            .service(handlers::cluster::rotate_ssh_key)
+            .service(handlers::cluster::recover_clusters)
+            .service(handlers::cluster::import_cluster)
+            .service(handlers::cluster::update_cluster_secrets)
+            .service(handlers::addons::addons_status)
+            .service(handlers::addons::enable_addon)
+            .service(handlers::addons::disable_addon)
             .service(handlers::apps::apps_status)
             .service(handlers::apps::save_helm_app)
             .service(handlers::apps::update_helm_app)
@@ -103,7 +147,20 @@ async fn main() -> std::io::Result<()> {
             .service(handlers::cluster_resources::delete_cluster_resources)
             .service(handlers::cluster_resources::install_cluster_resources)
             .service(handlers::cluster_resources::uninstall_cluster_resources)
+            .service(handlers::snapshots::snapshots)
+            .service(handlers::snapshots::create_snapshot)
+            .service(handlers::snapshots::rollback_snapshot)
+            .service(handlers::snapshots::delete_snapshot)
+            .service(handlers::backups::update_backup_settings)
+            .service(handlers::backups::delete_backup_settings)
+            .service(handlers::backups::backups)
+            .service(handlers::backups::backup_cluster)
+            .service(handlers::backups::restore_backup)
             .service(handlers::network::networks_bridges)
+            .service(handlers::ip_pools::ip_pools)
+            .service(handlers::ip_pools::ip_allocations)
+            .service(handlers::ip_pools::save_ip_pool)
+            .service(handlers::ip_pools::delete_ip_pool)
             .service(handlers::nodes::nodes)
             .service(handlers::storage::storage)
             .service(handlers::export::export_cluster_data)